@@ -0,0 +1,48 @@
+#ifndef CVK_DEBUG_GLSL
+#define CVK_DEBUG_GLSL
+
+#ifdef CVK_SHADER_DEBUG
+
+#extension GL_EXT_debug_printf : require
+
+#ifndef CVK_ASSERT_SET
+#define CVK_ASSERT_SET 0
+#endif
+
+#ifndef CVK_ASSERT_BINDING
+#define CVK_ASSERT_BINDING 0
+#endif
+
+struct CvkAssertRecord {
+    uint id;
+    uint line;
+    uvec2 values;
+};
+
+layout(std430, set = CVK_ASSERT_SET, binding = CVK_ASSERT_BINDING) buffer CvkAssertBuffer {
+    uint cvk_assert_count;
+    uint cvk_assert_capacity;
+    CvkAssertRecord cvk_assert_records[];
+};
+
+void cvk_assert_fail(uint id, uint line, uvec2 values) {
+    uint index = atomicAdd(cvk_assert_count, 1);
+
+    if (index < cvk_assert_capacity) {
+        cvk_assert_records[index] = CvkAssertRecord(id, line, values);
+    }
+}
+
+#define SHADER_PRINTF(...) debugPrintfEXT(__VA_ARGS__)
+#define SHADER_ASSERT(cond, id) if (!(cond)) { cvk_assert_fail(id, __LINE__, uvec2(0)); }
+#define SHADER_ASSERT_VALUES(cond, id, a, b) if (!(cond)) { cvk_assert_fail(id, __LINE__, uvec2(a, b)); }
+
+#else
+
+#define SHADER_PRINTF(...)
+#define SHADER_ASSERT(cond, id)
+#define SHADER_ASSERT_VALUES(cond, id, a, b)
+
+#endif
+
+#endif
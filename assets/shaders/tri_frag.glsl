@@ -1,9 +1,12 @@
 #version 450
 
+#include "include/debug.glsl"
+
 layout(location = 0) in vec3 fragColor;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
+    SHADER_ASSERT(all(greaterThanEqual(fragColor, vec3(0.0))) && all(lessThanEqual(fragColor, vec3(1.0))), 1);
     outColor = vec4(fragColor, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,418 @@
+pub type Vec3 = [f32; 3];
+
+#[inline]
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// --------------------- Aabb ---------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        min: [f32::INFINITY; 3],
+        max: [f32::NEG_INFINITY; 3],
+    };
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    #[inline]
+    pub fn grow_point(&mut self, point: Vec3) {
+        for (axis, value) in point.into_iter().enumerate() {
+            self.min[axis] = self.min[axis].min(value);
+            self.max[axis] = self.max[axis].max(value);
+        }
+    }
+
+    #[inline]
+    pub fn grow(&mut self, other: &Aabb) {
+        self.grow_point(other.min);
+        self.grow_point(other.max);
+    }
+
+    #[inline]
+    pub fn extent(&self) -> Vec3 {
+        sub(self.max, self.min)
+    }
+
+    #[inline]
+    pub fn centroid(&self) -> Vec3 {
+        [
+            (self.min[0] + self.max[0]) * 0.5,
+            (self.min[1] + self.max[1]) * 0.5,
+            (self.min[2] + self.max[2]) * 0.5,
+        ]
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let [x, y, z] = self.extent();
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    pub fn intersect_ray(&self, ray: &Ray, max_t: f32) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = max_t;
+
+        for axis in 0..3 {
+            let inv = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv;
+
+            if inv < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+
+            if t_max < t_min {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+// --------------------- Ray ---------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub t: f32,
+    pub triangle: u32,
+    pub barycentrics: [f32; 2],
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    pub fn intersect_triangle(&self, [a, b, c]: [Vec3; 3]) -> Option<(f32, [f32; 2])> {
+        const EPSILON: f32 = 1e-7;
+
+        let edge1 = sub(b, a);
+        let edge2 = sub(c, a);
+        let p = cross(self.direction, edge2);
+        let det = dot(edge1, p);
+
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = sub(self.origin, a);
+        let u = dot(s, p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = cross(s, edge1);
+        let v = dot(self.direction, q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = dot(edge2, q) * inv_det;
+        (t > EPSILON).then_some((t, [u, v]))
+    }
+}
+
+// --------------------- Bvh ---------------------
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct BvhNode {
+    pub min: Vec3,
+    pub left_or_first: u32,
+    pub max: Vec3,
+    pub count: u32,
+}
+
+impl BvhNode {
+    #[inline]
+    pub const fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+
+    #[inline]
+    pub const fn bounds(&self) -> Aabb {
+        Aabb {
+            min: self.min,
+            max: self.max,
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+    pub triangle_indices: Vec<u32>,
+}
+
+struct BuildTriangle {
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bounds: Aabb,
+    count: u32,
+}
+
+impl Bvh {
+    pub const MAX_LEAF_SIZE: u32 = 4;
+    const BIN_COUNT: usize = 12;
+
+    pub fn build(vertices: &[Vec3], indices: &[[u32; 3]]) -> Self {
+        let triangles: Vec<BuildTriangle> = indices
+            .iter()
+            .map(|triangle| {
+                let mut bounds = Aabb::EMPTY;
+                for &index in triangle {
+                    bounds.grow_point(vertices[index as usize]);
+                }
+
+                BuildTriangle {
+                    bounds,
+                    centroid: bounds.centroid(),
+                }
+            })
+            .collect();
+
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity((2 * triangles.len()).max(1)),
+            triangle_indices: (0..triangles.len() as u32).collect(),
+        };
+
+        bvh.nodes.push(BvhNode {
+            left_or_first: 0,
+            count: triangles.len() as u32,
+            ..Default::default()
+        });
+
+        bvh.update_bounds(0, &triangles);
+        bvh.subdivide(0, &triangles);
+
+        bvh
+    }
+
+    #[inline]
+    pub fn root(&self) -> Option<&BvhNode> {
+        self.nodes.first()
+    }
+
+    fn update_bounds(&mut self, node_index: usize, triangles: &[BuildTriangle]) {
+        let node = self.nodes[node_index];
+        let first = node.left_or_first as usize;
+
+        let mut bounds = Aabb::EMPTY;
+        for &triangle in &self.triangle_indices[first..first + node.count as usize] {
+            bounds.grow(&triangles[triangle as usize].bounds);
+        }
+
+        let node = &mut self.nodes[node_index];
+        node.min = bounds.min;
+        node.max = bounds.max;
+    }
+
+    fn find_split(&self, node: &BvhNode, triangles: &[BuildTriangle]) -> Option<(usize, f32, f32)> {
+        let first = node.left_or_first as usize;
+        let node_triangles = &self.triangle_indices[first..first + node.count as usize];
+
+        let mut centroid_bounds = Aabb::EMPTY;
+        for &triangle in node_triangles {
+            centroid_bounds.grow_point(triangles[triangle as usize].centroid);
+        }
+
+        let mut best: Option<(usize, f32, f32)> = None;
+
+        for axis in 0..3 {
+            let min = centroid_bounds.min[axis];
+            let extent = centroid_bounds.max[axis] - min;
+
+            if extent <= 0.0 {
+                continue;
+            }
+
+            let mut bins = [Bin {
+                bounds: Aabb::EMPTY,
+                count: 0,
+            }; Self::BIN_COUNT];
+
+            let scale = Self::BIN_COUNT as f32 / extent;
+            for &triangle in node_triangles {
+                let triangle = &triangles[triangle as usize];
+                let bin = (((triangle.centroid[axis] - min) * scale) as usize).min(Self::BIN_COUNT - 1);
+                bins[bin].count += 1;
+                bins[bin].bounds.grow(&triangle.bounds);
+            }
+
+            let mut left_costs = [0.0; Self::BIN_COUNT - 1];
+            let mut left_bounds = Aabb::EMPTY;
+            let mut left_count = 0;
+            for i in 0..Self::BIN_COUNT - 1 {
+                left_bounds.grow(&bins[i].bounds);
+                left_count += bins[i].count;
+                left_costs[i] = left_count as f32 * left_bounds.surface_area();
+            }
+
+            let mut right_bounds = Aabb::EMPTY;
+            let mut right_count = 0;
+            for i in (1..Self::BIN_COUNT).rev() {
+                right_bounds.grow(&bins[i].bounds);
+                right_count += bins[i].count;
+
+                let cost = left_costs[i - 1] + right_count as f32 * right_bounds.surface_area();
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, min + i as f32 / scale, cost));
+                }
+            }
+        }
+
+        best
+    }
+
+    fn subdivide(&mut self, node_index: usize, triangles: &[BuildTriangle]) {
+        let node = self.nodes[node_index];
+
+        if node.count <= Self::MAX_LEAF_SIZE {
+            return;
+        }
+
+        let Some((axis, split, cost)) = self.find_split(&node, triangles) else {
+            return;
+        };
+
+        let leaf_cost = node.count as f32 * node.bounds().surface_area();
+        if cost >= leaf_cost {
+            return;
+        }
+
+        let first = node.left_or_first as usize;
+        let mut i = first;
+        let mut j = first + node.count as usize - 1;
+
+        while i <= j {
+            if triangles[self.triangle_indices[i] as usize].centroid[axis] < split {
+                i += 1;
+            } else {
+                self.triangle_indices.swap(i, j);
+                if j == 0 {
+                    break;
+                }
+                j -= 1;
+            }
+        }
+
+        let left_count = (i - first) as u32;
+        if left_count == 0 || left_count == node.count {
+            return;
+        }
+
+        let left_index = self.nodes.len();
+
+        self.nodes.push(BvhNode {
+            left_or_first: first as u32,
+            count: left_count,
+            ..Default::default()
+        });
+        self.nodes.push(BvhNode {
+            left_or_first: i as u32,
+            count: node.count - left_count,
+            ..Default::default()
+        });
+
+        let parent = &mut self.nodes[node_index];
+        parent.left_or_first = left_index as u32;
+        parent.count = 0;
+
+        self.update_bounds(left_index, triangles);
+        self.update_bounds(left_index + 1, triangles);
+        self.subdivide(left_index, triangles);
+        self.subdivide(left_index + 1, triangles);
+    }
+
+    pub fn intersect(&self, ray: &Ray, vertices: &[Vec3], indices: &[[u32; 3]]) -> Option<RayHit> {
+        let root = self.root()?;
+
+        let mut closest: Option<RayHit> = None;
+        let mut stack = Vec::with_capacity(64);
+
+        if root.bounds().intersect_ray(ray, f32::INFINITY).is_some() {
+            stack.push(0usize);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_t = closest.map_or(f32::INFINITY, |hit| hit.t);
+
+            if node.bounds().intersect_ray(ray, max_t).is_none() {
+                continue;
+            }
+
+            if node.is_leaf() {
+                let first = node.left_or_first as usize;
+
+                for &triangle in &self.triangle_indices[first..first + node.count as usize] {
+                    let [a, b, c] = indices[triangle as usize];
+                    let positions = [vertices[a as usize], vertices[b as usize], vertices[c as usize]];
+
+                    if let Some((t, barycentrics)) = ray.intersect_triangle(positions)
+                        && closest.is_none_or(|hit| t < hit.t)
+                    {
+                        closest = Some(RayHit {
+                            t,
+                            triangle,
+                            barycentrics,
+                        });
+                    }
+                }
+            } else {
+                stack.push(node.left_or_first as usize);
+                stack.push(node.left_or_first as usize + 1);
+            }
+        }
+
+        closest
+    }
+}
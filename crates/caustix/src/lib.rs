@@ -0,0 +1,10 @@
+pub mod bvh;
+pub mod mesh;
+pub mod packing;
+
+pub use bvh::*;
+pub use mesh::*;
+pub use packing::*;
+
+#[cfg(test)]
+pub mod tests;
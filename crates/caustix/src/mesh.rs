@@ -0,0 +1,129 @@
+use std::fmt;
+
+use crate::Vec3;
+use crate::bvh::{cross, dot, sub};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshError {
+    IndexOutOfBounds { triangle: usize, index: u32 },
+    AttributeCountMismatch,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IndexOutOfBounds { triangle, index } => {
+                write!(f, "Triangle {triangle} references vertex {index}, which does not exist")
+            }
+            Self::AttributeCountMismatch => write!(f, "Vertex attributes have different lengths"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+fn check_indices(vertex_count: usize, indices: &[[u32; 3]]) -> Result<(), MeshError> {
+    for (triangle, vertices) in indices.iter().enumerate() {
+        if let Some(&index) = vertices.iter().find(|&&index| index as usize >= vertex_count) {
+            return Err(MeshError::IndexOutOfBounds { triangle, index });
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+#[inline]
+fn scale(a: Vec3, s: f32) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+#[inline]
+fn normalize_or(a: Vec3, fallback: Vec3) -> Vec3 {
+    let length = dot(a, a).sqrt();
+    if length > 1e-12 { scale(a, 1.0 / length) } else { fallback }
+}
+
+fn perpendicular(normal: Vec3) -> Vec3 {
+    let axis = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+    normalize_or(cross(axis, normal), [1.0, 0.0, 0.0])
+}
+
+// --------------------- Normals ---------------------
+
+pub fn generate_normals(positions: &[Vec3], indices: &[[u32; 3]]) -> Result<Vec<Vec3>, MeshError> {
+    check_indices(positions.len(), indices)?;
+
+    let mut normals = vec![[0.0; 3]; positions.len()];
+
+    for triangle in indices {
+        let [a, b, c] = triangle.map(|i| positions[i as usize]);
+        let face_normal = cross(sub(b, a), sub(c, a));
+
+        for &i in triangle {
+            normals[i as usize] = add(normals[i as usize], face_normal);
+        }
+    }
+
+    Ok(normals.into_iter().map(|normal| normalize_or(normal, [0.0, 0.0, 1.0])).collect())
+}
+
+// --------------------- Tangents ---------------------
+
+/// Accumulates per-triangle tangents at the vertices and orthogonalizes them against the normal. This is
+/// not MikkTSpace: normal maps baked with MikkTSpace tangents can show seams on vertices shared by
+/// triangles with different uv orientations.
+pub fn generate_tangents(
+    positions: &[Vec3],
+    normals: &[Vec3],
+    uvs: &[[f32; 2]],
+    indices: &[[u32; 3]],
+) -> Result<Vec<[f32; 4]>, MeshError> {
+    if normals.len() != positions.len() || uvs.len() != positions.len() {
+        return Err(MeshError::AttributeCountMismatch);
+    }
+    check_indices(positions.len(), indices)?;
+
+    let mut tangents = vec![[0.0; 3]; positions.len()];
+    let mut bitangents = vec![[0.0; 3]; positions.len()];
+
+    for triangle in indices {
+        let [a, b, c] = triangle.map(|i| positions[i as usize]);
+        let [uv_a, uv_b, uv_c] = triangle.map(|i| uvs[i as usize]);
+
+        let edge1 = sub(b, a);
+        let edge2 = sub(c, a);
+        let duv1 = [uv_b[0] - uv_a[0], uv_b[1] - uv_a[1]];
+        let duv2 = [uv_c[0] - uv_a[0], uv_c[1] - uv_a[1]];
+
+        let det = duv1[0] * duv2[1] - duv2[0] * duv1[1];
+        if det.abs() < 1e-12 {
+            continue;
+        }
+
+        let r = 1.0 / det;
+        let tangent = scale(sub(scale(edge1, duv2[1]), scale(edge2, duv1[1])), r);
+        let bitangent = scale(sub(scale(edge2, duv1[0]), scale(edge1, duv2[0])), r);
+
+        for &i in triangle {
+            tangents[i as usize] = add(tangents[i as usize], tangent);
+            bitangents[i as usize] = add(bitangents[i as usize], bitangent);
+        }
+    }
+
+    Ok(normals
+        .iter()
+        .zip(tangents.iter().zip(&bitangents))
+        .map(|(&normal, (&tangent, &bitangent))| {
+            let orthogonal = sub(tangent, scale(normal, dot(normal, tangent)));
+            let tangent = normalize_or(orthogonal, perpendicular(normal));
+
+            let handedness = if dot(cross(normal, tangent), bitangent) < 0.0 { -1.0 } else { 1.0 };
+
+            [tangent[0], tangent[1], tangent[2], handedness]
+        })
+        .collect())
+}
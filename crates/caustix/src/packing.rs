@@ -0,0 +1,313 @@
+use ash::vk;
+
+use crate::Vec3;
+
+// --------------------- Scalar packing ---------------------
+
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exp == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let half_exp = exp - 127 + 15;
+
+    if half_exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    if half_exp <= 0 {
+        if half_exp < -10 {
+            return sign;
+        }
+
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exp) as u32;
+        let round_bit = 1 << (shift - 1);
+        let remainder = mantissa & ((1 << shift) - 1);
+
+        let mut half = mantissa >> shift;
+        if remainder > round_bit || (remainder == round_bit && half & 1 == 1) {
+            half += 1;
+        }
+
+        return sign | half as u16;
+    }
+
+    let mut half = ((half_exp as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    if remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1) {
+        half += 1;
+    }
+
+    sign | half as u16
+}
+
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exp = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+
+    let bits = match (exp, mantissa) {
+        (0, 0) => sign,
+        (0, mantissa) => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        (0x1f, mantissa) => sign | 0x7f80_0000 | (mantissa << 13),
+        (exp, mantissa) => sign | ((exp + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+#[inline]
+fn snorm(value: f32, bits: u32) -> u32 {
+    let max = ((1 << (bits - 1)) - 1) as f32;
+    let value = (value.clamp(-1.0, 1.0) * max).round() as i32;
+    value as u32 & ((1 << bits) - 1)
+}
+
+#[inline]
+fn unorm(value: f32, bits: u32) -> u32 {
+    let max = ((1 << bits) - 1) as f32;
+    (value.clamp(0.0, 1.0) * max).round() as u32
+}
+
+#[inline]
+fn unsnorm(value: u32, bits: u32) -> f32 {
+    let shift = 32 - bits;
+    let value = ((value << shift) as i32) >> shift;
+    (value as f32 / ((1 << (bits - 1)) - 1) as f32).max(-1.0)
+}
+
+pub fn pack_half2(value: [f32; 2]) -> u32 {
+    f32_to_f16(value[0]) as u32 | (f32_to_f16(value[1]) as u32) << 16
+}
+
+pub fn unpack_half2(packed: u32) -> [f32; 2] {
+    [f16_to_f32(packed as u16), f16_to_f32((packed >> 16) as u16)]
+}
+
+pub fn pack_snorm_10_10_10_2(value: Vec3, w: f32) -> u32 {
+    snorm(value[0], 10) | snorm(value[1], 10) << 10 | snorm(value[2], 10) << 20 | snorm(w, 2) << 30
+}
+
+pub fn unpack_snorm_10_10_10_2(packed: u32) -> (Vec3, f32) {
+    (
+        [
+            unsnorm(packed & 0x3ff, 10),
+            unsnorm((packed >> 10) & 0x3ff, 10),
+            unsnorm((packed >> 20) & 0x3ff, 10),
+        ],
+        unsnorm(packed >> 30, 2),
+    )
+}
+
+pub fn pack_unorm_8x4(value: [f32; 4]) -> u32 {
+    unorm(value[0], 8) | unorm(value[1], 8) << 8 | unorm(value[2], 8) << 16 | unorm(value[3], 8) << 24
+}
+
+pub fn pack_snorm_16x2(value: [f32; 2]) -> u32 {
+    snorm(value[0], 16) | snorm(value[1], 16) << 16
+}
+
+pub fn unpack_snorm_16x2(packed: u32) -> [f32; 2] {
+    [unsnorm(packed & 0xffff, 16), unsnorm(packed >> 16, 16)]
+}
+
+// --------------------- Normal encoding ---------------------
+
+pub fn oct_encode(normal: Vec3) -> [f32; 2] {
+    let length = normal[0].abs() + normal[1].abs() + normal[2].abs();
+
+    if length == 0.0 {
+        return [0.0, 0.0];
+    }
+
+    let x = normal[0] / length;
+    let y = normal[1] / length;
+
+    if normal[2] >= 0.0 {
+        [x, y]
+    } else {
+        [
+            (1.0 - y.abs()) * x.signum(),
+            (1.0 - x.abs()) * y.signum(),
+        ]
+    }
+}
+
+pub fn oct_decode(encoded: [f32; 2]) -> Vec3 {
+    let [x, y] = encoded;
+    let z = 1.0 - x.abs() - y.abs();
+    let t = (-z).max(0.0);
+
+    let normal = [x - t * x.signum(), y - t * y.signum(), z];
+    let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+
+    [normal[0] / length, normal[1] / length, normal[2] / length]
+}
+
+// --------------------- Packed vertex ---------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackedFormat {
+    Float3,
+    Half2,
+    Snorm10x3A2,
+    Snorm16x2,
+    Unorm8x4,
+}
+
+impl PackedFormat {
+    pub const fn size(&self) -> u32 {
+        match self {
+            PackedFormat::Float3 => 12,
+            PackedFormat::Half2 | PackedFormat::Snorm10x3A2 | PackedFormat::Snorm16x2 | PackedFormat::Unorm8x4 => 4,
+        }
+    }
+
+    pub const fn vk_format(self) -> vk::Format {
+        match self {
+            PackedFormat::Float3 => vk::Format::R32G32B32_SFLOAT,
+            PackedFormat::Half2 => vk::Format::R16G16_SFLOAT,
+            PackedFormat::Snorm10x3A2 => vk::Format::A2B10G10R10_SNORM_PACK32,
+            PackedFormat::Snorm16x2 => vk::Format::R16G16_SNORM,
+            PackedFormat::Unorm8x4 => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct PackedVertex {
+    pub position: Vec3,
+    pub normal: u32,
+    pub tangent: u32,
+    pub uv: u32,
+}
+
+impl PackedVertex {
+    pub const ATTRIBUTES: [(u32, PackedFormat); 4] = [
+        (0, PackedFormat::Float3),
+        (12, PackedFormat::Snorm10x3A2),
+        (16, PackedFormat::Snorm10x3A2),
+        (20, PackedFormat::Half2),
+    ];
+
+    pub const fn binding_description(binding: u32) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding,
+            stride: size_of::<Self>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }
+    }
+
+    /// Shader locations follow the order of [`ATTRIBUTES`](Self::ATTRIBUTES): position, normal, tangent, uv.
+    pub fn attribute_descriptions(binding: u32) -> [vk::VertexInputAttributeDescription; 4] {
+        std::array::from_fn(|location| {
+            let (offset, format) = Self::ATTRIBUTES[location];
+
+            vk::VertexInputAttributeDescription {
+                location: location as u32,
+                binding,
+                format: format.vk_format(),
+                offset,
+            }
+        })
+    }
+
+    pub fn new(position: Vec3, normal: Vec3, tangent: [f32; 4], uv: [f32; 2]) -> Self {
+        Self {
+            position,
+            normal: pack_snorm_10_10_10_2(normal, 0.0),
+            tangent: pack_snorm_10_10_10_2([tangent[0], tangent[1], tangent[2]], tangent[3]),
+            uv: pack_half2(uv),
+        }
+    }
+}
+
+// --------------------- Meshlets ---------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Meshlet {
+    pub vertex_offset: u32,
+    pub triangle_offset: u32,
+    pub vertex_count: u32,
+    pub triangle_count: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Meshlets {
+    pub meshlets: Vec<Meshlet>,
+    pub vertices: Vec<u32>,
+    pub triangles: Vec<[u8; 3]>,
+}
+
+impl Meshlets {
+    pub const MAX_VERTICES: usize = 64;
+    pub const MAX_TRIANGLES: usize = 124;
+
+    pub fn build(indices: &[[u32; 3]]) -> Self {
+        Self::build_with_limits(indices, Self::MAX_VERTICES, Self::MAX_TRIANGLES)
+    }
+
+    pub fn build_with_limits(indices: &[[u32; 3]], max_vertices: usize, max_triangles: usize) -> Self {
+        assert!(
+            (3..=256).contains(&max_vertices) && max_triangles > 0,
+            "Meshlets need between 3 and 256 vertices and at least one triangle"
+        );
+
+        let mut result = Self::default();
+        let mut current = Meshlet::default();
+        let mut local_vertices: Vec<u32> = vec![];
+
+        for triangle in indices {
+            let new_vertices = triangle
+                .iter()
+                .enumerate()
+                .filter(|&(i, vertex)| !local_vertices.contains(vertex) && !triangle[..i].contains(vertex))
+                .count();
+
+            if local_vertices.len() + new_vertices > max_vertices || current.triangle_count as usize == max_triangles {
+                result.push_meshlet(&mut current, &mut local_vertices);
+            }
+
+            let local = triangle.map(|vertex| match local_vertices.iter().position(|&v| v == vertex) {
+                Some(i) => i as u8,
+                None => {
+                    local_vertices.push(vertex);
+                    (local_vertices.len() - 1) as u8
+                }
+            });
+
+            result.triangles.push(local);
+            current.triangle_count += 1;
+        }
+
+        result.push_meshlet(&mut current, &mut local_vertices);
+        result
+    }
+
+    fn push_meshlet(&mut self, current: &mut Meshlet, local_vertices: &mut Vec<u32>) {
+        if current.triangle_count == 0 {
+            return;
+        }
+
+        current.vertex_count = local_vertices.len() as u32;
+        self.vertices.append(local_vertices);
+        self.meshlets.push(*current);
+
+        *current = Meshlet {
+            vertex_offset: self.vertices.len() as u32,
+            triangle_offset: self.triangles.len() as u32,
+            ..Default::default()
+        };
+    }
+}
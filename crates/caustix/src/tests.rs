@@ -0,0 +1,203 @@
+use ash::vk;
+
+use crate::{
+    Aabb, Bvh, MeshError, Meshlets, PackedFormat, PackedVertex, Ray, Vec3, f16_to_f32, f32_to_f16, generate_normals,
+    generate_tangents, oct_decode, oct_encode, pack_snorm_10_10_10_2, unpack_snorm_10_10_10_2,
+};
+
+fn grid(size: u32) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    for y in 0..=size {
+        for x in 0..=size {
+            vertices.push([x as f32, y as f32, 0.0]);
+        }
+    }
+
+    let row = size + 1;
+    for y in 0..size {
+        for x in 0..size {
+            let i = y * row + x;
+            indices.push([i, i + 1, i + row]);
+            indices.push([i + 1, i + row + 1, i + row]);
+        }
+    }
+
+    (vertices, indices)
+}
+
+#[test]
+pub fn test_aabb() {
+    let mut aabb = Aabb::EMPTY;
+    assert!(aabb.is_empty());
+    assert_eq!(aabb.surface_area(), 0.0);
+
+    aabb.grow_point([0.0, 0.0, 0.0]);
+    aabb.grow_point([1.0, 2.0, 3.0]);
+
+    assert_eq!(aabb.centroid(), [0.5, 1.0, 1.5]);
+    assert_eq!(aabb.surface_area(), 22.0);
+}
+
+#[test]
+pub fn test_bvh_build() {
+    let (vertices, indices) = grid(16);
+    let bvh = Bvh::build(&vertices, &indices);
+
+    let root = bvh.root().unwrap();
+    assert_eq!(root.min, [0.0, 0.0, 0.0]);
+    assert_eq!(root.max, [16.0, 16.0, 0.0]);
+    assert!(!root.is_leaf());
+
+    let mut sorted = bvh.triangle_indices.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..indices.len() as u32).collect::<Vec<_>>());
+
+    let leaf_triangles: u32 = bvh.nodes.iter().filter(|node| node.is_leaf()).map(|node| node.count).sum();
+    assert_eq!(leaf_triangles, indices.len() as u32);
+    assert_eq!(size_of::<crate::BvhNode>(), 32);
+}
+
+#[test]
+pub fn test_bvh_intersect() {
+    let (vertices, indices) = grid(8);
+    let bvh = Bvh::build(&vertices, &indices);
+
+    let hit = bvh
+        .intersect(&Ray::new([2.25, 3.25, 5.0], [0.0, 0.0, -1.0]), &vertices, &indices)
+        .unwrap();
+    assert!((hit.t - 5.0).abs() < 1e-5);
+    assert_eq!(hit.triangle, 2 * (3 * 8 + 2));
+
+    assert!(bvh.intersect(&Ray::new([20.0, 20.0, 5.0], [0.0, 0.0, -1.0]), &vertices, &indices).is_none());
+    assert!(bvh.intersect(&Ray::new([2.25, 3.25, 5.0], [0.0, 0.0, 1.0]), &vertices, &indices).is_none());
+}
+
+#[test]
+pub fn test_half_float() {
+    for value in [0.0, 1.0, -2.5, 0.333, 65504.0, 6.1e-5, 1e-7] {
+        let half = f16_to_f32(f32_to_f16(value));
+        assert!((half - value).abs() <= value.abs() * 1e-3 + 6e-8, "{value} -> {half}");
+    }
+
+    assert_eq!(f32_to_f16(1.0), 0x3c00);
+    assert_eq!(f32_to_f16(-2.0), 0xc000);
+    assert_eq!(f32_to_f16(1e6), 0x7c00);
+    assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+}
+
+#[test]
+pub fn test_normal_packing() {
+    let normal = [0.267, -0.534, 0.802];
+
+    let (unpacked, w) = unpack_snorm_10_10_10_2(pack_snorm_10_10_10_2(normal, -1.0));
+    for axis in 0..3 {
+        assert!((unpacked[axis] - normal[axis]).abs() < 2e-3);
+    }
+    assert_eq!(w, -1.0);
+
+    for normal in [[0.0, 0.0, 1.0], [0.0, 0.0, -1.0], [0.577, -0.577, -0.577]] {
+        let decoded = oct_decode(oct_encode(normal));
+        for axis in 0..3 {
+            assert!((decoded[axis] - normal[axis]).abs() < 1e-3, "{normal:?} -> {decoded:?}");
+        }
+    }
+}
+
+#[test]
+pub fn test_packed_vertex_formats() {
+    let formats = PackedVertex::ATTRIBUTES.map(|(_, format)| format.vk_format());
+    assert_eq!(
+        formats,
+        [
+            vk::Format::R32G32B32_SFLOAT,
+            vk::Format::A2B10G10R10_SNORM_PACK32,
+            vk::Format::A2B10G10R10_SNORM_PACK32,
+            vk::Format::R16G16_SFLOAT,
+        ]
+    );
+
+    let (offset, format) = PackedVertex::ATTRIBUTES[3];
+    assert_eq!((offset + format.size()) as usize, size_of::<PackedVertex>());
+
+    assert_eq!(PackedFormat::Unorm8x4.vk_format(), vk::Format::R8G8B8A8_UNORM);
+
+    let binding = PackedVertex::binding_description(1);
+    assert_eq!((binding.binding, binding.stride), (1, 24));
+
+    let attributes = PackedVertex::attribute_descriptions(1);
+    assert_eq!(attributes.map(|attribute| (attribute.location, attribute.offset)), [(0, 0), (1, 12), (2, 16), (3, 20)]);
+    assert!(attributes.iter().all(|attribute| attribute.binding == 1));
+}
+
+#[test]
+pub fn test_meshlets() {
+    let (_, indices) = grid(16);
+    let meshlets = Meshlets::build_with_limits(&indices, 32, 40);
+
+    assert_eq!(meshlets.triangles.len(), indices.len());
+
+    let mut triangle_count = 0;
+    for meshlet in &meshlets.meshlets {
+        assert!(meshlet.vertex_count <= 32);
+        assert!(meshlet.triangle_count <= 40);
+        assert_eq!(meshlet.triangle_offset, triangle_count);
+
+        let vertices = &meshlets.vertices[meshlet.vertex_offset as usize..][..meshlet.vertex_count as usize];
+        let triangles = &meshlets.triangles[meshlet.triangle_offset as usize..][..meshlet.triangle_count as usize];
+
+        for (local, triangle) in triangles.iter().zip(&indices[triangle_count as usize..]) {
+            assert_eq!(local.map(|i| vertices[i as usize]), *triangle);
+        }
+
+        triangle_count += meshlet.triangle_count;
+    }
+
+    assert_eq!(triangle_count as usize, indices.len());
+}
+
+#[test]
+pub fn test_generate_normals() {
+    let (vertices, indices) = grid(4);
+    let normals = generate_normals(&vertices, &indices).unwrap();
+
+    assert_eq!(normals.len(), vertices.len());
+    for normal in normals {
+        assert_eq!(normal, [0.0, 0.0, 1.0]);
+    }
+
+    let normals = generate_normals(&[[0.0; 3]], &[]).unwrap();
+    assert_eq!(normals, vec![[0.0, 0.0, 1.0]]);
+
+    assert_eq!(
+        generate_normals(&vertices, &[[0, 1, 2], [0, 1, vertices.len() as u32]]),
+        Err(MeshError::IndexOutOfBounds { triangle: 1, index: vertices.len() as u32 })
+    );
+}
+
+#[test]
+pub fn test_generate_tangents() {
+    let (vertices, indices) = grid(4);
+    let normals = generate_normals(&vertices, &indices).unwrap();
+
+    let uvs = vertices.iter().map(|v| [v[0] / 4.0, v[1] / 4.0]).collect::<Vec<_>>();
+    for tangent in generate_tangents(&vertices, &normals, &uvs, &indices).unwrap() {
+        assert_eq!(tangent, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    let mirrored = vertices.iter().map(|v| [-v[0] / 4.0, v[1] / 4.0]).collect::<Vec<_>>();
+    for tangent in generate_tangents(&vertices, &normals, &mirrored, &indices).unwrap() {
+        assert_eq!(tangent, [-1.0, 0.0, 0.0, -1.0]);
+    }
+
+    let degenerate = vec![[0.0, 0.0]; vertices.len()];
+    for tangent in generate_tangents(&vertices, &normals, &degenerate, &indices).unwrap() {
+        assert!(tangent[2].abs() < 1e-6 && (tangent[0].hypot(tangent[1]) - 1.0).abs() < 1e-6);
+    }
+
+    assert_eq!(
+        generate_tangents(&vertices, &normals, &uvs[1..], &indices),
+        Err(MeshError::AttributeCountMismatch)
+    );
+}
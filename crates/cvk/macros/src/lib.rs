@@ -1,14 +1,57 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use syn::spanned::Spanned;
 
 mod macro_impl;
 
+#[cfg(test)]
+mod tests;
+
 #[proc_macro_derive(VkHandle, attributes(handle))]
 pub fn derive_vk_handle(input: TokenStream) -> TokenStream {
-    let parse_result = syn::parse::<syn::ItemStruct>(input);
+    let item = syn::parse_macro_input!(input as syn::Item);
+
+    match item {
+        syn::Item::Struct(item) => macro_impl::derive_vk_handle(item),
+        syn::Item::Enum(item) => Err(syn::Error::new(
+            item.enum_token.span,
+            "VkHandle can only be derived for structs, not enums",
+        )),
+        syn::Item::Union(item) => Err(syn::Error::new(
+            item.union_token.span,
+            "VkHandle can only be derived for structs, not unions",
+        )),
+        item => Err(syn::Error::new(item.span(), "VkHandle can only be derived for structs")),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+#[proc_macro_derive(GpuStruct, attributes(gpu))]
+pub fn derive_gpu_struct(input: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(input as syn::Item);
+
+    match item {
+        syn::Item::Struct(item) => macro_impl::derive_gpu_struct(item),
+        item => Err(syn::Error::new(item.span(), "GpuStruct can only be derived for structs")),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+#[proc_macro]
+pub fn include_spv(input: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+
+    macro_impl::include_spv(path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro]
+pub fn compile_glsl(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as macro_impl::CompileGlslInput);
 
-    match parse_result {
-        Ok(item) => macro_impl::derive_vk_handle(item).into(),
-        Err(_) => quote! { compile_error!("Item needs to be a struct") }.into(),
-    } 
-}
\ No newline at end of file
+    macro_impl::compile_glsl(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -1,7 +1,7 @@
 use proc_macro2::TokenStream;
 use quote::{ToTokens, quote};
 
-pub fn derive_vk_handle(item: syn::ItemStruct) -> TokenStream {
+pub fn derive_vk_handle(item: syn::ItemStruct) -> syn::Result<TokenStream> {
     let item_ident = item.ident;
 
     let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
@@ -12,10 +12,34 @@ pub fn derive_vk_handle(item: syn::ItemStruct) -> TokenStream {
     let mut handle_field = None;
     let first_field = fields.first().map(|&f| (0, f));
 
+    for item_attr in item.attrs.iter().filter(|attr| attr.path().is_ident("handle")) {
+        let parse_result = item_attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("index") {
+                let index = meta.value()?.parse::<syn::LitInt>()?.base10_parse::<usize>()?;
+
+                match fields.get(index) {
+                    Some(&field) => {
+                        attr_field = Some((index, field));
+                        Ok(())
+                    }
+                    None => Err(meta.error("Handle index is out of range")),
+                }
+            } else {
+                Err(meta.error("Unsupported 'handle' argument"))
+            }
+        });
+
+        parse_result?;
+    }
+
     for (i, &field) in fields.iter().enumerate() {
         for field_attr in &field.attrs {
-            if attr_field.is_none() && field_attr.path().is_ident("handle") {
-                attr_field = Some((i, field));
+            if field_attr.path().is_ident("handle") {
+                field_attr.meta.require_path_only()?;
+
+                if attr_field.is_none() {
+                    attr_field = Some((i, field));
+                }
             }
         }
 
@@ -34,7 +58,7 @@ pub fn derive_vk_handle(item: syn::ItemStruct) -> TokenStream {
             syn::Index::from(i).to_token_stream()
         };
 
-        quote! {
+        Ok(quote! {
                 impl #impl_generics crate::core::VkHandle for #item_ident #ty_generics #where_clause {
                     type HandleType = #field_type;
 
@@ -49,11 +73,504 @@ pub fn derive_vk_handle(item: syn::ItemStruct) -> TokenStream {
                     pub fn handle(&self) -> #field_type {
                         self.#field_ident
                     }
+
+                    #[inline]
+                    pub fn raw_handle(&self) -> u64 {
+                        <#field_type as ::ash::vk::Handle>::as_raw(self.#field_ident)
+                    }
+
+                    #[inline]
+                    pub fn is_null(&self) -> bool {
+                        self.raw_handle() == 0
+                    }
                 }
-            }
+            })
     } else {
-        quote! {
-            compile_error!("Failed to find a suitable field to be used as the handle")
+        Err(syn::Error::new_spanned(
+            &item_ident,
+            "VkHandle requires a field to be used as the handle, but the struct has no fields",
+        ))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GpuLayout {
+    Std140,
+    Std430,
+}
+
+impl GpuLayout {
+    fn name(self) -> &'static str {
+        match self {
+            GpuLayout::Std140 => "std140",
+            GpuLayout::Std430 => "std430",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GpuScalar {
+    Float,
+    Int,
+    Uint,
+}
+
+enum GpuType {
+    Scalar(GpuScalar),
+    Vector(GpuScalar, usize),
+    Matrix(usize),
+    Array(Box<GpuType>, usize),
+}
+
+struct GpuField {
+    ident: syn::Ident,
+    ty: GpuType,
+    offset: usize,
+}
+
+fn round_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+impl GpuScalar {
+    fn parse(ty: &syn::Type) -> Option<Self> {
+        match ty {
+            syn::Type::Path(path) if path.qself.is_none() => {
+                let ident = path.path.get_ident()?;
+
+                if ident == "f32" {
+                    Some(GpuScalar::Float)
+                } else if ident == "i32" {
+                    Some(GpuScalar::Int)
+                } else if ident == "u32" {
+                    Some(GpuScalar::Uint)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn glsl_prefix(self) -> &'static str {
+        match self {
+            GpuScalar::Float => "",
+            GpuScalar::Int => "i",
+            GpuScalar::Uint => "u",
+        }
+    }
+
+    fn glsl_name(self) -> &'static str {
+        match self {
+            GpuScalar::Float => "float",
+            GpuScalar::Int => "int",
+            GpuScalar::Uint => "uint",
+        }
+    }
+}
+
+impl GpuType {
+    fn parse(ty: &syn::Type) -> syn::Result<Self> {
+        if let Some(scalar) = GpuScalar::parse(ty) {
+            return Ok(GpuType::Scalar(scalar));
+        }
+
+        let syn::Type::Array(array) = ty else {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "Unsupported GpuStruct field type, expected f32, i32, u32 or an array of them",
+            ));
+        };
+
+        let len = match &array.len {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse::<usize>()?,
+            len => return Err(syn::Error::new_spanned(len, "GpuStruct array lengths have to be integer literals")),
+        };
+
+        let elem = GpuType::parse(&array.elem)?;
+
+        Ok(match elem {
+            GpuType::Scalar(scalar) if (2..=4).contains(&len) => GpuType::Vector(scalar, len),
+            GpuType::Vector(GpuScalar::Float, rows) if rows == len => GpuType::Matrix(len),
+            elem => GpuType::Array(Box::new(elem), len),
+        })
+    }
+
+    fn glsl_name(&self) -> String {
+        match self {
+            GpuType::Scalar(scalar) => scalar.glsl_name().to_string(),
+            GpuType::Vector(scalar, n) => format!("{}vec{n}", scalar.glsl_prefix()),
+            GpuType::Matrix(n) => format!("mat{n}"),
+            GpuType::Array(elem, _) => elem.glsl_name(),
+        }
+    }
+
+    fn glsl_suffix(&self) -> String {
+        match self {
+            GpuType::Array(elem, len) => format!("[{len}]{}", elem.glsl_suffix()),
+            _ => String::new(),
+        }
+    }
+
+    fn rust_size(&self) -> usize {
+        match self {
+            GpuType::Scalar(_) => 4,
+            GpuType::Vector(_, n) => 4 * n,
+            GpuType::Matrix(n) => 4 * n * n,
+            GpuType::Array(elem, len) => elem.rust_size() * len,
+        }
+    }
+
+    fn alignment(&self, layout: GpuLayout) -> usize {
+        match self {
+            GpuType::Scalar(_) => 4,
+            GpuType::Vector(_, 2) => 8,
+            GpuType::Vector(_, _) => 16,
+            GpuType::Matrix(n) => GpuType::Vector(GpuScalar::Float, *n).array_alignment(layout),
+            GpuType::Array(elem, _) => elem.array_alignment(layout),
+        }
+    }
+
+    fn array_alignment(&self, layout: GpuLayout) -> usize {
+        match layout {
+            GpuLayout::Std140 => round_up(self.alignment(layout), 16),
+            GpuLayout::Std430 => self.alignment(layout),
+        }
+    }
+
+    fn array_stride(&self, layout: GpuLayout) -> usize {
+        round_up(self.size(layout), self.array_alignment(layout))
+    }
+
+    fn size(&self, layout: GpuLayout) -> usize {
+        match self {
+            GpuType::Scalar(_) | GpuType::Vector(_, _) => self.rust_size(),
+            GpuType::Matrix(n) => GpuType::Vector(GpuScalar::Float, *n).array_stride(layout) * n,
+            GpuType::Array(elem, len) => elem.array_stride(layout) * len,
+        }
+    }
+
+    fn check_strides(&self, layout: GpuLayout) -> Result<(), String> {
+        match self {
+            GpuType::Scalar(_) | GpuType::Vector(_, _) => Ok(()),
+            GpuType::Matrix(n) => {
+                let stride = GpuType::Vector(GpuScalar::Float, *n).array_stride(layout);
+
+                if stride != 4 * n {
+                    Err(format!(
+                        "mat{n} columns have a stride of {stride} bytes in {}, but [[f32; {n}]; {n}] columns are {} bytes apart; use an array of [f32; 4] columns instead",
+                        layout.name(),
+                        4 * n
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            GpuType::Array(elem, _) => {
+                let stride = elem.array_stride(layout);
+
+                if stride != elem.rust_size() {
+                    Err(format!(
+                        "{} array elements have a stride of {stride} bytes in {}, but the Rust elements are {} bytes apart; pad the element type to {stride} bytes",
+                        elem.glsl_name(),
+                        layout.name(),
+                        elem.rust_size()
+                    ))
+                } else {
+                    elem.check_strides(layout)
+                }
+            }
+        }
+    }
+}
+
+fn is_gpu_padding(field: &syn::Field) -> syn::Result<bool> {
+    let mut padding = field
+        .ident
+        .as_ref()
+        .map(|ident| ident.to_string().starts_with('_'))
+        .unwrap_or(false);
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("gpu")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("padding") {
+                padding = true;
+                Ok(())
+            } else {
+                Err(meta.error("Unsupported 'gpu' field argument"))
+            }
+        })?;
+    }
+
+    Ok(padding)
+}
+
+fn has_repr_c(item: &syn::ItemStruct) -> syn::Result<bool> {
+    let mut repr_c = false;
+
+    for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                let _content;
+                syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(repr_c)
+}
+
+pub fn derive_gpu_struct(item: syn::ItemStruct) -> syn::Result<TokenStream> {
+    let item_ident = &item.ident;
+
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&item.generics, "GpuStruct can not be derived for generic structs"));
+    }
+
+    if !has_repr_c(&item)? {
+        return Err(syn::Error::new_spanned(item_ident, "GpuStruct requires the struct to be #[repr(C)]"));
+    }
+
+    let syn::Fields::Named(named_fields) = &item.fields else {
+        return Err(syn::Error::new_spanned(&item.fields, "GpuStruct requires named fields"));
+    };
+
+    let mut layout = GpuLayout::Std430;
+    let mut glsl_name = item_ident.to_string();
+
+    for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("gpu")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("std140") {
+                layout = GpuLayout::Std140;
+                Ok(())
+            } else if meta.path.is_ident("std430") {
+                layout = GpuLayout::Std430;
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                glsl_name = meta.value()?.parse::<syn::LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("Unsupported 'gpu' argument"))
+            }
+        })?;
+    }
+
+    let mut fields = Vec::new();
+    let mut rust_offset = 0;
+    let mut glsl_offset = 0;
+    let mut struct_alignment = 4;
+
+    for field in &named_fields.named {
+        let field_ident = field.ident.clone().unwrap();
+        let ty = GpuType::parse(&field.ty)?;
+
+        if is_gpu_padding(field)? {
+            rust_offset += ty.rust_size();
+            continue;
         }
+
+        ty.check_strides(layout).map_err(|msg| syn::Error::new_spanned(&field.ty, msg))?;
+
+        let alignment = ty.alignment(layout);
+        let required_offset = round_up(glsl_offset, alignment);
+
+        if rust_offset != required_offset {
+            let msg = if rust_offset < required_offset {
+                format!(
+                    "Field '{field_ident}' is at offset {rust_offset}, but {} places it at offset {required_offset}; add {} bytes of padding before it",
+                    layout.name(),
+                    required_offset - rust_offset
+                )
+            } else {
+                format!(
+                    "Field '{field_ident}' is at offset {rust_offset}, but {} places it at offset {required_offset}; remove {} bytes of padding before it",
+                    layout.name(),
+                    rust_offset - required_offset
+                )
+            };
+
+            return Err(syn::Error::new_spanned(&field_ident, msg));
+        }
+
+        struct_alignment = struct_alignment.max(alignment);
+        rust_offset += ty.rust_size();
+        glsl_offset = required_offset + ty.size(layout);
+
+        fields.push(GpuField {
+            ident: field_ident,
+            ty,
+            offset: required_offset,
+        });
+    }
+
+    if layout == GpuLayout::Std140 {
+        struct_alignment = round_up(struct_alignment, 16);
     }
+
+    let size = round_up(glsl_offset, struct_alignment);
+
+    if rust_offset != size {
+        return Err(syn::Error::new_spanned(
+            item_ident,
+            format!(
+                "Struct is {rust_offset} bytes, but its {} size is {size} bytes; adjust the trailing padding",
+                layout.name()
+            ),
+        ));
+    }
+
+    let mut glsl = format!("struct {glsl_name} {{\n");
+
+    for field in &fields {
+        glsl += &format!("    {} {}{};\n", field.ty.glsl_name(), field.ident, field.ty.glsl_suffix());
+    }
+
+    glsl += "};\n";
+
+    let layout_variant = match layout {
+        GpuLayout::Std140 => quote! { Std140 },
+        GpuLayout::Std430 => quote! { Std430 },
+    };
+
+    let offset_asserts = fields.iter().map(|field| {
+        let field_ident = &field.ident;
+        let offset = field.offset;
+
+        quote! { assert!(::core::mem::offset_of!(#item_ident, #field_ident) == #offset); }
+    });
+
+    Ok(quote! {
+        impl ::cvk::GpuStruct for #item_ident {
+            const LAYOUT: ::cvk::GpuLayout = ::cvk::GpuLayout::#layout_variant;
+            const SIZE: usize = #size;
+            const GLSL_NAME: &'static str = #glsl_name;
+            const GLSL: &'static str = #glsl;
+        }
+
+        const _: () = {
+            assert!(::core::mem::size_of::<#item_ident>() == #size);
+            #(#offset_asserts)*
+        };
+    })
+}
+
+fn resolve_shader_path(path: &syn::LitStr) -> syn::Result<std::path::PathBuf> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new_spanned(path, "CARGO_MANIFEST_DIR is not set"))?;
+
+    Ok(std::path::Path::new(&manifest_dir).join(path.value()))
+}
+
+/// Every path in `dependencies` is pulled in with `include_bytes!` so that changing it rebuilds the shader.
+fn spv_words_to_tokens(dependencies: &[std::path::PathBuf], words: &[u32]) -> TokenStream {
+    let dependencies = dependencies.iter().map(|path| path.to_string_lossy());
+
+    quote! {
+        {
+            #(const _: &[u8] = include_bytes!(#dependencies);)*
+            const WORDS: &[u32] = &[#(#words),*];
+            WORDS
+        }
+    }
+}
+
+pub fn include_spv(path: syn::LitStr) -> syn::Result<TokenStream> {
+    let full_path = resolve_shader_path(&path)?;
+
+    let data = std::fs::read(&full_path)
+        .map_err(|error| syn::Error::new_spanned(&path, format!("Failed to read '{}': {error}", full_path.display())))?;
+
+    if data.len() % 4 != 0 || data.len() < 4 {
+        return Err(syn::Error::new_spanned(&path, "SPIR-V file size is not a multiple of 4 bytes"));
+    }
+
+    let words = data
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect::<Vec<u32>>();
+
+    if words[0] != 0x07230203 {
+        return Err(syn::Error::new_spanned(&path, "File is not a SPIR-V module"));
+    }
+
+    Ok(spv_words_to_tokens(&[full_path], &words))
+}
+
+pub struct CompileGlslInput {
+    stage: syn::Ident,
+    path: syn::LitStr,
+}
+
+impl syn::parse::Parse for CompileGlslInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let stage = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let path = input.parse()?;
+        let _ = input.parse::<Option<syn::Token![,]>>()?;
+
+        Ok(Self { stage, path })
+    }
+}
+
+#[cfg(feature = "glsl")]
+pub fn compile_glsl(input: CompileGlslInput) -> syn::Result<TokenStream> {
+    let CompileGlslInput { stage, path } = input;
+
+    let shader_kind = match stage.to_string().as_str() {
+        "vertex" => shaderc::ShaderKind::Vertex,
+        "fragment" => shaderc::ShaderKind::Fragment,
+        "compute" => shaderc::ShaderKind::Compute,
+        "geometry" => shaderc::ShaderKind::Geometry,
+        "tess_control" => shaderc::ShaderKind::TessControl,
+        "tess_evaluation" => shaderc::ShaderKind::TessEvaluation,
+        _ => return Err(syn::Error::new_spanned(&stage, "Unsupported shader stage")),
+    };
+
+    let full_path = resolve_shader_path(&path)?;
+
+    let source = std::fs::read_to_string(&full_path)
+        .map_err(|error| syn::Error::new_spanned(&path, format!("Failed to read '{}': {error}", full_path.display())))?;
+
+    let includes = std::cell::RefCell::new(std::collections::BTreeSet::new());
+
+    let compiler = shaderc::Compiler::new().map_err(|error| syn::Error::new_spanned(&path, error.to_string()))?;
+    let mut options = shaderc::CompileOptions::new().map_err(|error| syn::Error::new_spanned(&path, error.to_string()))?;
+    options.set_optimization_level(shaderc::OptimizationLevel::Performance);
+    options.set_include_callback(|name, _include_type, requesting_source, _depth| {
+        let include_path = std::path::Path::new(requesting_source)
+            .parent()
+            .map(|dir| dir.join(name))
+            .unwrap_or_else(|| name.into());
+
+        let content = std::fs::read_to_string(&include_path)
+            .map_err(|error| format!("Failed to read include '{}': {error}", include_path.display()))?;
+
+        includes.borrow_mut().insert(include_path.clone());
+
+        Ok(shaderc::ResolvedInclude {
+            resolved_name: include_path.to_string_lossy().into(),
+            content,
+        })
+    });
+
+    let artifact = compiler
+        .compile_into_spirv(&source, shader_kind, &full_path.to_string_lossy(), "main", Some(&options))
+        .map_err(|error| syn::Error::new_spanned(&path, error.to_string()))?;
+
+    drop(options);
+    let mut dependencies = vec![full_path];
+    dependencies.extend(includes.into_inner());
+
+    Ok(spv_words_to_tokens(&dependencies, artifact.as_binary()))
+}
+
+#[cfg(not(feature = "glsl"))]
+pub fn compile_glsl(input: CompileGlslInput) -> syn::Result<TokenStream> {
+    Err(syn::Error::new_spanned(
+        &input.stage,
+        format!("compile_glsl! requires the 'glsl' feature of cvk-macros to compile '{}'", input.path.value()),
+    ))
 }
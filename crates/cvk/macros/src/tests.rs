@@ -0,0 +1,129 @@
+use crate::macro_impl::derive_gpu_struct;
+
+fn glsl_of(tokens: proc_macro2::TokenStream) -> String {
+    let expanded = derive_gpu_struct(syn::parse2(tokens).unwrap()).unwrap();
+    let item_impl = syn::parse2::<syn::File>(expanded).unwrap();
+
+    let syn::Item::Impl(item_impl) = &item_impl.items[0] else {
+        panic!("Expected an impl block");
+    };
+
+    item_impl
+        .items
+        .iter()
+        .find_map(|item| match item {
+            syn::ImplItem::Const(item) if item.ident == "GLSL" => match &item.expr {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+pub fn test_gpu_struct_std430() {
+    let glsl = glsl_of(quote::quote! {
+        #[repr(C)]
+        #[gpu(std430)]
+        struct Light {
+            position: [f32; 3],
+            intensity: f32,
+            color: [f32; 3],
+            _pad: u32,
+            view: [[f32; 4]; 4],
+        }
+    });
+
+    assert_eq!(
+        glsl,
+        "struct Light {\n    vec3 position;\n    float intensity;\n    vec3 color;\n    mat4 view;\n};\n"
+    );
+}
+
+#[test]
+pub fn test_gpu_struct_std140_arrays() {
+    let glsl = glsl_of(quote::quote! {
+        #[repr(C)]
+        #[gpu(std140, name = "Globals")]
+        struct GlobalData {
+            offsets: [[f32; 4]; 8],
+            time: f32,
+            #[gpu(padding)]
+            padding: [u32; 3],
+        }
+    });
+
+    assert_eq!(glsl, "struct Globals {\n    vec4 offsets[8];\n    float time;\n};\n");
+}
+
+#[test]
+pub fn test_gpu_struct_layout_errors() {
+    let missing_padding = derive_gpu_struct(syn::parse_quote! {
+        #[repr(C)]
+        struct Light {
+            intensity: f32,
+            position: [f32; 3],
+        }
+    });
+
+    let error = missing_padding.unwrap_err().to_string();
+    assert!(error.contains("add 12 bytes of padding"), "{error}");
+
+    let float_array = derive_gpu_struct(syn::parse_quote! {
+        #[repr(C)]
+        #[gpu(std140)]
+        struct Weights {
+            weights: [f32; 8],
+        }
+    });
+
+    assert!(float_array.is_err());
+
+    let mat3 = derive_gpu_struct(syn::parse_quote! {
+        #[repr(C)]
+        struct Transform {
+            normal: [[f32; 3]; 3],
+        }
+    });
+
+    assert!(mat3.is_err());
+
+    let no_repr = derive_gpu_struct(syn::parse_quote! {
+        struct Plain {
+            value: f32,
+        }
+    });
+
+    assert!(no_repr.is_err());
+}
+
+#[test]
+pub fn test_include_spv() {
+    let dir = std::env::temp_dir().join(format!("cvk_macros_test_include_spv_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let spv_path = dir.join("module.spv");
+    let words: [u32; 5] = [0x07230203, 0x00010000, 0, 1, 0];
+    std::fs::write(&spv_path, words.iter().flat_map(|w| w.to_le_bytes()).collect::<Vec<u8>>()).unwrap();
+
+    let lit = syn::LitStr::new(spv_path.to_str().unwrap(), proc_macro2::Span::call_site());
+    let expanded = crate::macro_impl::include_spv(lit).unwrap().to_string();
+    assert!(expanded.contains("119734787u32"), "{expanded}");
+
+    let unaligned_path = dir.join("unaligned.spv");
+    std::fs::write(&unaligned_path, b"not spir-v").unwrap();
+
+    let lit = syn::LitStr::new(unaligned_path.to_str().unwrap(), proc_macro2::Span::call_site());
+    let error = crate::macro_impl::include_spv(lit).unwrap_err().to_string();
+    assert!(error.contains("multiple of 4 bytes"), "{error}");
+
+    let wrong_magic_path = dir.join("wrong_magic.spv");
+    std::fs::write(&wrong_magic_path, b"not spirv module").unwrap();
+
+    let lit = syn::LitStr::new(wrong_magic_path.to_str().unwrap(), proc_macro2::Span::call_site());
+    let error = crate::macro_impl::include_spv(lit).unwrap_err().to_string();
+    assert!(error.contains("not a SPIR-V module"), "{error}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
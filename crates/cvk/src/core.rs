@@ -1,17 +1,39 @@
+pub mod barrier;
 pub mod command_buffer;
 pub mod context;
 mod device;
 mod instance;
+pub mod markers;
+pub mod stats;
+pub mod swapchain;
+pub mod timestamp;
 
+pub use barrier::{Access, BarrierBuilder};
 pub use command_buffer::*;
 pub use context::*;
+pub use device::Queue;
+pub use instance::SurfaceWindow;
+pub use markers::CommandMarkers;
+pub use stats::{SubmitStats, SubmitThresholds};
+pub use swapchain::{ColorSpace, PresentMode, Swapchain, SwapchainBuilder};
+pub use timestamp::{GpuProfiler, GpuZone, TimestampQueryPool};
 
 
 
 pub trait VkHandle {
-    type HandleType;
+    type HandleType: vk::Handle;
 
     fn handle(&self) -> Self::HandleType;
+
+    #[inline]
+    fn raw_handle(&self) -> u64 {
+        vk::Handle::as_raw(self.handle())
+    }
+
+    #[inline]
+    fn is_null(&self) -> bool {
+        self.raw_handle() == 0
+    }
 }
 
 
@@ -20,6 +42,7 @@ use ash::vk;
 pub use vk::Format;
 
 #[derive(Clone, Copy, Debug, utils::Paramters)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Extent2D {
     pub width: u32,
     pub height: u32,
@@ -0,0 +1,378 @@
+use std::ops::BitOr;
+
+use ash::vk;
+
+use crate::{BufferRegionLike, Context, GpuUse, Image, ImageLayout, Recording, Result, VkHandle};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Access {
+    pub stages: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+}
+
+impl Access {
+    pub const NONE: Access = Access::new(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE);
+    pub const ALL: Access = Access::new(
+        vk::PipelineStageFlags2::ALL_COMMANDS,
+        vk::AccessFlags2::from_raw(vk::AccessFlags2::MEMORY_READ.as_raw() | vk::AccessFlags2::MEMORY_WRITE.as_raw()),
+    );
+
+    pub const TRANSFER_READ: Access = Access::new(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_READ);
+    pub const TRANSFER_WRITE: Access = Access::new(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE);
+    pub const HOST_READ: Access = Access::new(vk::PipelineStageFlags2::HOST, vk::AccessFlags2::HOST_READ);
+    pub const HOST_WRITE: Access = Access::new(vk::PipelineStageFlags2::HOST, vk::AccessFlags2::HOST_WRITE);
+
+    pub const VERTEX_BUFFER: Access =
+        Access::new(vk::PipelineStageFlags2::VERTEX_INPUT, vk::AccessFlags2::VERTEX_ATTRIBUTE_READ);
+    pub const INDEX_BUFFER: Access = Access::new(vk::PipelineStageFlags2::VERTEX_INPUT, vk::AccessFlags2::INDEX_READ);
+    pub const INDIRECT_BUFFER: Access =
+        Access::new(vk::PipelineStageFlags2::DRAW_INDIRECT, vk::AccessFlags2::INDIRECT_COMMAND_READ);
+    pub const UNIFORM_READ: Access =
+        Access::new(vk::PipelineStageFlags2::ALL_GRAPHICS, vk::AccessFlags2::UNIFORM_READ).with_compute();
+
+    pub const VERTEX_SHADER_READ: Access =
+        Access::new(vk::PipelineStageFlags2::VERTEX_SHADER, vk::AccessFlags2::SHADER_READ);
+    pub const FRAGMENT_SHADER_READ: Access =
+        Access::new(vk::PipelineStageFlags2::FRAGMENT_SHADER, vk::AccessFlags2::SHADER_READ);
+    pub const COMPUTE_SHADER_READ: Access =
+        Access::new(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_READ);
+    pub const COMPUTE_SHADER_WRITE: Access =
+        Access::new(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_WRITE);
+
+    pub const COLOR_ATTACHMENT_WRITE: Access = Access::new(
+        vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+    );
+    pub const DEPTH_ATTACHMENT_WRITE: Access = Access::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS.as_raw() | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    );
+
+    const WRITES: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+        vk::AccessFlags2::SHADER_WRITE.as_raw()
+            | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags2::TRANSFER_WRITE.as_raw()
+            | vk::AccessFlags2::HOST_WRITE.as_raw()
+            | vk::AccessFlags2::MEMORY_WRITE.as_raw(),
+    );
+
+    #[inline]
+    pub const fn new(stages: vk::PipelineStageFlags2, access: vk::AccessFlags2) -> Self {
+        Self { stages, access }
+    }
+
+    /// The accesses an image in `layout` is used with. Layouts without a single purpose, like GENERAL,
+    /// fall back to [`Access::ALL`].
+    pub fn for_layout(layout: ImageLayout) -> Access {
+        let depth_tests = vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS;
+
+        match layout {
+            ImageLayout::UNDEFINED | ImageLayout::PRESENT_SRC_KHR => Access::NONE,
+            ImageLayout::PREINITIALIZED => Access::HOST_WRITE,
+            ImageLayout::TRANSFER_SRC_OPTIMAL => Access::TRANSFER_READ,
+            ImageLayout::TRANSFER_DST_OPTIMAL => Access::TRANSFER_WRITE,
+            ImageLayout::SHADER_READ_ONLY_OPTIMAL => {
+                Access::VERTEX_SHADER_READ | Access::FRAGMENT_SHADER_READ | Access::COMPUTE_SHADER_READ
+            }
+            ImageLayout::COLOR_ATTACHMENT_OPTIMAL => Access::new(
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+            ),
+            ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+            | ImageLayout::DEPTH_ATTACHMENT_OPTIMAL
+            | ImageLayout::STENCIL_ATTACHMENT_OPTIMAL => Access::new(
+                depth_tests,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+            | ImageLayout::DEPTH_READ_ONLY_OPTIMAL
+            | ImageLayout::STENCIL_READ_ONLY_OPTIMAL => {
+                Access::new(depth_tests, vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ) | Access::FRAGMENT_SHADER_READ
+            }
+            _ => Access::ALL,
+        }
+    }
+
+    /// Keeps the stages but drops read accesses, since a source scope only needs to make writes available.
+    #[inline]
+    pub fn writes(self) -> Access {
+        Access::new(self.stages, self.access & Self::WRITES)
+    }
+
+    #[inline]
+    const fn with_compute(self) -> Self {
+        Self::new(
+            vk::PipelineStageFlags2::from_raw(self.stages.as_raw() | vk::PipelineStageFlags2::COMPUTE_SHADER.as_raw()),
+            self.access,
+        )
+    }
+
+    fn legacy_stages(self) -> vk::PipelineStageFlags {
+        vk::PipelineStageFlags::from_raw(self.stages.as_raw() as u32)
+    }
+
+    fn legacy_access(self) -> vk::AccessFlags {
+        vk::AccessFlags::from_raw(self.access.as_raw() as u32)
+    }
+}
+
+impl BitOr for Access {
+    type Output = Access;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Access::new(self.stages | rhs.stages, self.access | rhs.access)
+    }
+}
+
+struct ImageBarrier<'a> {
+    image: &'a Image,
+    src: Access,
+    dst: Access,
+    old_layout: ImageLayout,
+    new_layout: ImageLayout,
+}
+
+struct BufferBarrier {
+    buffer: vk::Buffer,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    src: Access,
+    dst: Access,
+}
+
+#[derive(Default)]
+pub struct BarrierBuilder<'a> {
+    memory: Vec<(Access, Access)>,
+    buffers: Vec<BufferBarrier>,
+    images: Vec<ImageBarrier<'a>>,
+    gpu_uses: Vec<GpuUse>,
+}
+
+impl<'a> BarrierBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty() && self.buffers.is_empty() && self.images.is_empty()
+    }
+
+    pub fn memory(mut self, src: Access, dst: Access) -> Self {
+        self.memory.push((src, dst));
+        self
+    }
+
+    pub fn buffer<T: Copy>(mut self, region: impl BufferRegionLike<T> + 'a, src: Access, dst: Access) -> Self {
+        self.gpu_uses.push(region.gpu_use().clone());
+        self.buffers.push(BufferBarrier {
+            buffer: region.buffer(),
+            offset: region.offset() * size_of::<T>() as vk::DeviceSize,
+            size: region.size(),
+            src,
+            dst,
+        });
+        self
+    }
+
+    pub fn image(self, image: &'a Image, src: Access, dst: Access, new_layout: ImageLayout) -> Self {
+        self.image_from(image, src, dst, image.layout(), new_layout)
+    }
+
+    pub fn image_from(
+        mut self,
+        image: &'a Image,
+        src: Access,
+        dst: Access,
+        old_layout: ImageLayout,
+        new_layout: ImageLayout,
+    ) -> Self {
+        self.gpu_uses.push(image.gpu_use().clone());
+        self.images.push(ImageBarrier {
+            image,
+            src,
+            dst,
+            old_layout,
+            new_layout,
+        });
+        self
+    }
+}
+
+fn full_range(image: &Image) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::default()
+        .aspect_mask(image.aspect_mask())
+        .base_mip_level(0)
+        .level_count(vk::REMAINING_MIP_LEVELS)
+        .base_array_layer(0)
+        .layer_count(vk::REMAINING_ARRAY_LAYERS)
+}
+
+// --------------------- Barrier commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn pipeline_barrier(&mut self, barriers: BarrierBuilder<'a>) {
+        self.try_pipeline_barrier(barriers)
+            .unwrap_or_else(|error| panic!("Failed to record pipeline barrier: {error}"))
+    }
+
+    pub fn try_pipeline_barrier(&mut self, barriers: BarrierBuilder<'a>) -> Result<()> {
+        if barriers.is_empty() {
+            return Ok(());
+        }
+
+        for barrier in &barriers.images {
+            barrier.image.check_layout_recording(self)?;
+        }
+
+        for gpu_use in &barriers.gpu_uses {
+            self.track(gpu_use);
+        }
+
+        match Context::handles().extensions.synchronization2 {
+            Some(ref synchronization2) => self.pipeline_barrier2(synchronization2, &barriers),
+            None => self.pipeline_barrier_legacy(&barriers),
+        }
+
+        for barrier in &barriers.images {
+            barrier.image.try_record_layout(self, barrier.new_layout)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn transition_image_layout_from(&mut self, image: &'a Image, old_layout: ImageLayout, new_layout: ImageLayout) {
+        self.pipeline_barrier(BarrierBuilder::new().image_from(image, Access::ALL, Access::ALL, old_layout, new_layout));
+    }
+
+    fn pipeline_barrier2(&mut self, synchronization2: &ash::khr::synchronization2::Device, barriers: &BarrierBuilder<'a>) {
+        let memory = barriers
+            .memory
+            .iter()
+            .map(|(src, dst)| {
+                vk::MemoryBarrier2::default()
+                    .src_stage_mask(src.stages)
+                    .src_access_mask(src.access)
+                    .dst_stage_mask(dst.stages)
+                    .dst_access_mask(dst.access)
+            })
+            .collect::<Vec<_>>();
+
+        let buffers = barriers
+            .buffers
+            .iter()
+            .map(|barrier| {
+                vk::BufferMemoryBarrier2::default()
+                    .src_stage_mask(barrier.src.stages)
+                    .src_access_mask(barrier.src.access)
+                    .dst_stage_mask(barrier.dst.stages)
+                    .dst_access_mask(barrier.dst.access)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .buffer(barrier.buffer)
+                    .offset(barrier.offset)
+                    .size(barrier.size)
+            })
+            .collect::<Vec<_>>();
+
+        let images = barriers
+            .images
+            .iter()
+            .map(|barrier| {
+                vk::ImageMemoryBarrier2::default()
+                    .src_stage_mask(barrier.src.stages)
+                    .src_access_mask(barrier.src.access)
+                    .dst_stage_mask(barrier.dst.stages)
+                    .dst_access_mask(barrier.dst.access)
+                    .old_layout(barrier.old_layout)
+                    .new_layout(barrier.new_layout)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(barrier.image.handle())
+                    .subresource_range(full_range(barrier.image))
+            })
+            .collect::<Vec<_>>();
+
+        let dependency_info = vk::DependencyInfo::default()
+            .memory_barriers(&memory)
+            .buffer_memory_barriers(&buffers)
+            .image_memory_barriers(&images);
+
+        unsafe { synchronization2.cmd_pipeline_barrier2(self.handle(), &dependency_info) };
+    }
+
+    fn pipeline_barrier_legacy(&mut self, barriers: &BarrierBuilder<'a>) {
+        let mut src_stages = vk::PipelineStageFlags::empty();
+        let mut dst_stages = vk::PipelineStageFlags::empty();
+
+        let mut stages = |src: Access, dst: Access| {
+            src_stages |= src.legacy_stages();
+            dst_stages |= dst.legacy_stages();
+        };
+
+        let memory = barriers
+            .memory
+            .iter()
+            .map(|&(src, dst)| {
+                stages(src, dst);
+                vk::MemoryBarrier::default()
+                    .src_access_mask(src.legacy_access())
+                    .dst_access_mask(dst.legacy_access())
+            })
+            .collect::<Vec<_>>();
+
+        let buffers = barriers
+            .buffers
+            .iter()
+            .map(|barrier| {
+                stages(barrier.src, barrier.dst);
+                vk::BufferMemoryBarrier::default()
+                    .src_access_mask(barrier.src.legacy_access())
+                    .dst_access_mask(barrier.dst.legacy_access())
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .buffer(barrier.buffer)
+                    .offset(barrier.offset)
+                    .size(barrier.size)
+            })
+            .collect::<Vec<_>>();
+
+        let images = barriers
+            .images
+            .iter()
+            .map(|barrier| {
+                stages(barrier.src, barrier.dst);
+                vk::ImageMemoryBarrier::default()
+                    .src_access_mask(barrier.src.legacy_access())
+                    .dst_access_mask(barrier.dst.legacy_access())
+                    .old_layout(barrier.old_layout)
+                    .new_layout(barrier.new_layout)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(barrier.image.handle())
+                    .subresource_range(full_range(barrier.image))
+            })
+            .collect::<Vec<_>>();
+
+        if src_stages.is_empty() {
+            src_stages = vk::PipelineStageFlags::TOP_OF_PIPE;
+        }
+        if dst_stages.is_empty() {
+            dst_stages = vk::PipelineStageFlags::BOTTOM_OF_PIPE;
+        }
+
+        unsafe {
+            Context::cached_device().cmd_pipeline_barrier(
+                self.handle(),
+                src_stages,
+                dst_stages,
+                vk::DependencyFlags::empty(),
+                &memory,
+                &buffers,
+                &images,
+            );
+        }
+    }
+}
@@ -1,8 +1,17 @@
-use std::marker::PhantomData;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::{any::Any, marker::PhantomData};
 
 use ash::vk;
+use parking_lot::Mutex;
+use utils::Shared;
 
-use crate::{Context, Fence, VkHandle};
+use crate::core::stats;
+use crate::profiling::profile_scope;
+use crate::sync::{finish_submission, track_submission};
+use crate::{Context, Fence, GpuUse, Image, ImageLayout, Queue, QueueFamily, Result, Semaphore, TimelineValue, VkHandle};
+
+pub use vk::PipelineStageFlags as PipelineStage;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CommandBufferUses {
@@ -10,35 +19,131 @@ pub enum CommandBufferUses {
     Multi,
 }
 
+thread_local! {
+    /// Keeps the pools of a thread alive while it runs. Command buffers hold their own `Shared`, so a pool is
+    /// destroyed once the thread has exited and its last command buffer is dropped.
+    static THREAD_POOLS: RefCell<HashMap<QueueFamily, Shared<CommandPool>>> = RefCell::default();
+}
+
+#[derive(cvk_macros::VkHandle, Debug)]
+pub struct CommandPool {
+    handle: vk::CommandPool,
+    family: QueueFamily,
+    lock: Mutex<()>,
+}
+
+impl CommandPool {
+    pub(crate) fn try_new(family: QueueFamily) -> Result<Self> {
+        let info = vk::CommandPoolCreateInfo::default()
+            .queue_family_index(family.0)
+            .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
+
+        let handle = unsafe { Context::try_handles()?.device.create_command_pool(&info, None) }?;
+
+        Ok(Self {
+            handle,
+            family,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub(crate) fn for_current_thread(family: QueueFamily) -> Result<Shared<Self>> {
+        THREAD_POOLS.with_borrow_mut(|pools| {
+            if let Some(pool) = pools.get(&family) {
+                return Ok(pool.clone());
+            }
+
+            let pool = Shared::new(Self::try_new(family)?);
+            pools.insert(family, pool.clone());
+
+            Ok(pool)
+        })
+    }
+
+    /// Releases the pools of the calling thread. They are destroyed right away unless a command buffer
+    /// still uses them.
+    pub(crate) fn release_current_thread() {
+        THREAD_POOLS.with_borrow_mut(HashMap::clear);
+    }
+
+    #[inline]
+    pub fn family(&self) -> QueueFamily {
+        self.family
+    }
+}
+
+impl Drop for CommandPool {
+    fn drop(&mut self) {
+        if let Ok(handles) = Context::try_handles() {
+            unsafe { handles.device.destroy_command_pool(self.handle, None) };
+        }
+    }
+}
+
 #[derive(cvk_macros::VkHandle)]
 pub struct CommandBuffer {
     handle: vk::CommandBuffer,
+    pool: Shared<CommandPool>,
     fence: Fence,
     uses: CommandBufferUses,
     usable: bool,
+    retained: Vec<Shared<dyn Any + Send + Sync>>,
+    gpu_uses: Vec<GpuUse>,
+    submission: Option<TimelineValue>,
 }
 
 impl CommandBuffer {
     pub fn new(uses: CommandBufferUses) -> Self {
+        Self::try_new(uses).unwrap_or_else(|error| panic!("Failed to allocate command buffer: {error}"))
+    }
+
+    pub fn try_new(uses: CommandBufferUses) -> Result<Self> {
+        Self::try_new_in(uses, Context::try_handles()?.main_queue.family())
+    }
+
+    pub fn new_in(uses: CommandBufferUses, family: QueueFamily) -> Self {
+        Self::try_new_in(uses, family).unwrap_or_else(|error| panic!("Failed to allocate command buffer: {error}"))
+    }
+
+    pub fn try_new_in(uses: CommandBufferUses, family: QueueFamily) -> Result<Self> {
+        let pool = CommandPool::for_current_thread(family)?;
+
         let info = vk::CommandBufferAllocateInfo::default()
             .command_buffer_count(1u32)
-            .command_pool(Context::get().device().command_pool)
+            .command_pool(pool.handle)
             .level(vk::CommandBufferLevel::PRIMARY);
 
-        let handle = unsafe { Context::get_device().allocate_command_buffers(&info) }
-            .expect("Failed to allocate command buffer")[0];
+        let handle = {
+            let _lock = pool.lock.lock();
+            unsafe { Context::cached_device().allocate_command_buffers(&info) }?[0]
+        };
+
+        let handle = utils::guard(handle, |handle| {
+            let _lock = pool.lock.lock();
+            unsafe {
+                Context::cached_device().free_command_buffers(pool.handle, &[handle]);
+            }
+        });
 
-        let fence = Fence::new(true);
+        let fence = Fence::try_new(true)?;
 
-        Self {
+        let handle = utils::ScopeGuard::into_inner(handle);
+
+        Ok(Self {
             handle,
+            pool,
             fence,
             uses,
             usable: true,
-        }
+            retained: vec![],
+            gpu_uses: vec![],
+            submission: None,
+        })
     }
 
     pub fn run_single_use<'a>(recorder: impl FnOnce(&mut Recording<'a>)) {
+        stats::record_single_use_submit();
+
         let mut recording = Self::new(CommandBufferUses::Single).start_recording();
 
         recorder(&mut recording);
@@ -46,8 +151,26 @@ impl CommandBuffer {
         recording.submit().wait();
     }
 
+    pub fn try_run_single_use<'a>(recorder: impl FnOnce(&mut Recording<'a>)) -> Result<()> {
+        stats::record_single_use_submit();
+
+        let mut recording = Self::try_new(CommandBufferUses::Single)?.try_start_recording()?;
+
+        recorder(&mut recording);
+
+        recording.try_submit()?.try_wait()?;
+        Ok(())
+    }
+
     pub fn start_recording<'a>(self) -> Recording<'a> {
-        assert!(self.usable, "Command buffer is no longer usable");
+        self.try_start_recording()
+            .unwrap_or_else(|error| panic!("Failed to start recording of command buffer: {error}"))
+    }
+
+    pub fn try_start_recording<'a>(mut self) -> Result<Recording<'a>> {
+        if !self.usable {
+            return Err(crate::Error::InvalidParams("Single use command buffer was already submitted"));
+        }
 
         let flags = match self.uses {
             CommandBufferUses::Single => vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
@@ -56,11 +179,34 @@ impl CommandBuffer {
 
         let info = vk::CommandBufferBeginInfo::default().flags(flags);
 
-        self.fence.wait();
-        unsafe { Context::get_device().begin_command_buffer(self.handle, &info) }
-            .expect("Failed to start recording of command buffer");
+        self.fence.try_wait_internal(u64::MAX)?;
+        self.finish();
 
-        Recording { cmd_buf: self, _marker: PhantomData::default() }
+        {
+            let _lock = self.pool.lock.lock();
+            unsafe { Context::cached_device().begin_command_buffer(self.handle, &info) }?;
+        }
+
+        Ok(Recording {
+            cmd_buf: self,
+            bound_layout: None,
+            pending_layouts: PendingLayouts::default(),
+            _marker: PhantomData::default(),
+        })
+    }
+
+    #[inline]
+    pub fn last_submission(&self) -> Option<TimelineValue> {
+        self.submission
+    }
+
+    fn finish(&mut self) {
+        self.retained.clear();
+        self.gpu_uses.clear();
+
+        if let Some(value) = self.submission.take() {
+            finish_submission(value);
+        }
     }
 }
 
@@ -68,37 +214,157 @@ impl Drop for CommandBuffer {
     fn drop(&mut self) {
         println!("dropping cmd buf");
 
-        self.fence.wait();
+        if self.submission.is_some() {
+            let _ = self.fence.try_wait_internal(u64::MAX);
+        }
+        self.finish();
+        let _lock = self.pool.lock.lock();
         unsafe {
-            Context::get_device()
-                .free_command_buffers(Context::get().device().command_pool, &[self.handle]);
+            Context::cached_device().free_command_buffers(self.pool.handle, &[self.handle]);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SubmitInfoBuilder<'a> {
+    waits: Vec<(&'a Semaphore, PipelineStage)>,
+    signals: Vec<&'a Semaphore>,
+    queue: Option<Queue>,
+}
+
+impl<'a> SubmitInfoBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wait(mut self, semaphore: &'a Semaphore, stages: PipelineStage) -> Self {
+        self.waits.push((semaphore, stages));
+        self
+    }
+
+    pub fn signal(mut self, semaphore: &'a Semaphore) -> Self {
+        self.signals.push(semaphore);
+        self
+    }
+
+    pub fn queue(mut self, queue: Queue) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+}
+
+/// Images whose tracked layout an unsubmitted recording has changed, with the layout each had before.
+/// Dropping them without a submit gives the images back with their previous layout.
+#[derive(Default)]
+pub(crate) struct PendingLayouts<'a>(Vec<(&'a Image, ImageLayout)>);
+
+impl<'a> PendingLayouts<'a> {
+    #[inline]
+    pub(crate) fn push(&mut self, image: &'a Image, previous: ImageLayout) {
+        self.0.push((image, previous));
+    }
+
+    fn submitted(&mut self) {
+        for (image, _) in self.0.drain(..) {
+            image.finish_layout_recording(None);
+        }
+    }
+}
+
+impl Drop for PendingLayouts<'_> {
+    fn drop(&mut self) {
+        for (image, previous) in self.0.drain(..) {
+            image.finish_layout_recording(Some(previous));
         }
     }
 }
 
 pub struct Recording<'a> {
     cmd_buf: CommandBuffer,
-    _marker: PhantomData<&'a ()>,
+    bound_layout: Option<vk::PipelineLayout>,
+    pub(crate) pending_layouts: PendingLayouts<'a>,
+    _marker: PhantomData<fn(&'a ()) -> &'a ()>,
 }
 
 impl<'a> Recording<'a> {
-    pub fn submit(mut self) -> SubmittedRecording<'a> {
-        unsafe { Context::get_device().end_command_buffer(self.cmd_buf.handle) }
-            .expect("Failed to end recording of command buffer");
+    pub fn retain<R: Any + Send + Sync>(&mut self, resource: &Shared<R>) {
+        self.cmd_buf.retained.push(resource.clone());
+    }
+
+    pub fn track(&mut self, gpu_use: &GpuUse) {
+        self.cmd_buf.gpu_uses.push(gpu_use.clone());
+    }
+
+    #[inline]
+    pub fn bound_layout(&self) -> Option<vk::PipelineLayout> {
+        self.bound_layout
+    }
+
+    #[inline]
+    pub(crate) fn set_bound_layout(&mut self, layout: vk::PipelineLayout) {
+        self.bound_layout = Some(layout);
+    }
+
+    pub fn submit(self) -> SubmittedRecording {
+        self.try_submit()
+            .unwrap_or_else(|error| panic!("Failed to submit command buffer: {error}"))
+    }
+
+    pub fn try_submit(self) -> Result<SubmittedRecording> {
+        self.try_submit_with(SubmitInfoBuilder::new())
+    }
+
+    pub fn submit_with(self, info: SubmitInfoBuilder<'_>) -> SubmittedRecording {
+        self.try_submit_with(info)
+            .unwrap_or_else(|error| panic!("Failed to submit command buffer: {error}"))
+    }
+
+    pub fn try_submit_with(mut self, info: SubmitInfoBuilder<'_>) -> Result<SubmittedRecording> {
+        profile_scope!("cvk::submit");
+
+        let queue = match info.queue {
+            Some(queue) => queue,
+            None => Context::try_handles()?.main_queue,
+        };
+        if queue.family() != self.cmd_buf.pool.family {
+            return Err(crate::Error::InvalidParams(
+                "Command buffer needs to be submitted to a queue of the family it was allocated for",
+            ));
+        }
+
+        {
+            let _lock = self.cmd_buf.pool.lock.lock();
+            unsafe { Context::cached_device().end_command_buffer(self.cmd_buf.handle) }?;
+        }
 
         let handles = [self.handle()];
+        let wait_semaphores = info.waits.iter().map(|(semaphore, _)| semaphore.handle()).collect::<Vec<_>>();
+        let wait_stages = info.waits.iter().map(|&(_, stages)| stages).collect::<Vec<_>>();
+        let signal_semaphores = info.signals.iter().map(|semaphore| semaphore.handle()).collect::<Vec<_>>();
 
-        let submit_info = vk::SubmitInfo::default().command_buffers(handles.as_slice());
+        let submit_info = vk::SubmitInfo::default()
+            .command_buffers(handles.as_slice())
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .signal_semaphores(&signal_semaphores);
 
         if self.cmd_buf.uses == CommandBufferUses::Single {
             self.cmd_buf.usable = false;
         }
-        self.cmd_buf.fence.reset();
+        self.cmd_buf.fence.try_reset()?;
+
+        let value = track_submission(self.cmd_buf.fence.handle(), || {
+            unsafe { Context::cached_device().queue_submit(queue.handle(), &[submit_info], self.cmd_buf.fence.handle()) }?;
+            Ok(())
+        })?;
 
-        unsafe { Context::get_device().queue_submit(Context::get().device().main_queue.handle(), &[submit_info], self.cmd_buf.fence.handle()) }
-            .expect("Failed to submit command buffer");
+        for gpu_use in self.cmd_buf.gpu_uses.drain(..) {
+            gpu_use.mark(value);
+        }
+        self.pending_layouts.submitted();
+        self.cmd_buf.submission = Some(value);
 
-        SubmittedRecording { cmd_buf: self.cmd_buf, _marker: self._marker }
+        Ok(SubmittedRecording { cmd_buf: self.cmd_buf })
     }
 }
 
@@ -110,15 +376,22 @@ impl<'a> VkHandle for Recording<'a> {
     }
 }
 
-pub struct SubmittedRecording<'a> {
+/// A submitted command buffer. It no longer borrows what the recording used: every command tracks the
+/// resources it touches, and those wait for the submission before they are destroyed.
+pub struct SubmittedRecording {
     cmd_buf: CommandBuffer,
-    _marker: PhantomData<&'a ()>,
 }
 
-
-impl<'a> SubmittedRecording<'a> {
-    pub fn wait(self) -> CommandBuffer {
+impl SubmittedRecording {
+    pub fn wait(mut self) -> CommandBuffer {
         self.cmd_buf.fence.wait();
+        self.cmd_buf.finish();
         self.cmd_buf
     }
+
+    pub fn try_wait(mut self) -> Result<CommandBuffer> {
+        self.cmd_buf.fence.try_wait_with_timeout(u64::MAX)?;
+        self.cmd_buf.finish();
+        Ok(self.cmd_buf)
+    }
 }
\ No newline at end of file
@@ -7,9 +7,13 @@ use parking_lot::{
 
 use ash::vk;
 
-use winit::window::Window;
-
+use std::any::Any;
 use std::ffi::CString;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::{AllocationReport, CommandPool, Error, QueueFamily, Result};
 
 type ContextReadGuard = MappedRwLockReadGuard<'static, Context>;
 type ContextWriteGuard = MappedRwLockWriteGuard<'static, Context>;
@@ -18,7 +22,7 @@ type DeviceReadGuard = MappedRwLockReadGuard<'static, ash::Device>;
 
 pub struct Context {
     glsl_compiler: shaderc::Compiler,
-    allocator: vk_mem::Allocator,
+    allocator: Arc<vk_mem::Allocator>,
     device: Device,
     instance: Instance,
 }
@@ -32,50 +36,122 @@ pub enum ApiVersion {
     V1_3 = vk::API_VERSION_1_3,
 }
 
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+    pub struct ValidationFeatures: u32 {
+        const GPU_ASSISTED = 1 << 0;
+        const BEST_PRACTICES = 1 << 1;
+        const SYNCHRONIZATION = 1 << 2;
+        const DEBUG_PRINTF = 1 << 3;
+    }
+}
+
+impl ValidationFeatures {
+    pub(crate) fn to_vk(self) -> Vec<vk::ValidationFeatureEnableEXT> {
+        let mut features = vec![];
+
+        if self.contains(Self::GPU_ASSISTED) {
+            features.push(vk::ValidationFeatureEnableEXT::GPU_ASSISTED);
+            features.push(vk::ValidationFeatureEnableEXT::GPU_ASSISTED_RESERVE_BINDING_SLOT);
+        }
+        if self.contains(Self::BEST_PRACTICES) {
+            features.push(vk::ValidationFeatureEnableEXT::BEST_PRACTICES);
+        }
+        if self.contains(Self::SYNCHRONIZATION) {
+            features.push(vk::ValidationFeatureEnableEXT::SYNCHRONIZATION_VALIDATION);
+        }
+        if self.contains(Self::DEBUG_PRINTF) {
+            features.push(vk::ValidationFeatureEnableEXT::DEBUG_PRINTF);
+        }
+
+        features
+    }
+}
+
 #[derive(utils::Paramters)]
 pub struct ContextInfo {
+    #[param(default = CString::from(c"Vulkan App"))]
     pub app_name: CString,
+    #[param(default = CString::from(c"Engine"))]
     pub engine_name: CString,
+    #[param(default = ApiVersion::V1_3)]
     pub version: ApiVersion,
     pub debugging: bool,
-    pub window: Option<Window>,
+    #[flag]
+    pub validation_features: ValidationFeatures,
+    #[no_param]
+    pub window: Option<Box<dyn SurfaceWindow>>,
 }
 
-impl Default for ContextInfo {
-    fn default() -> Self {
-        Self {
-            app_name: CString::from(c"Vulkan App"),
-            engine_name: CString::from(c"Engine"),
-            version: ApiVersion::V1_3,
-            debugging: false,
-            window: None,
-        }
+impl ContextInfo {
+    pub fn window(mut self, window: impl SurfaceWindow) -> Self {
+        self.window = Some(Box::new(window));
+        self
     }
 }
 
 static CONTEXT: RwLock<Option<Context>> = RwLock::new(None);
 
+/// Copies of the device handles used on hot paths, reachable without locking the context.
+pub struct DeviceHandles {
+    pub device: ash::Device,
+    pub main_queue: Queue,
+    pub present_queue: Queue,
+    pub extensions: DeviceExtensions,
+}
+
+// Handles are leaked on purpose, so references returned by `Context::handles` stay valid after `destroy`.
+static DEVICE_HANDLES: AtomicPtr<DeviceHandles> = AtomicPtr::new(ptr::null_mut());
+
 impl Context {
     pub fn init(info: ContextInfo) {
-        let instance = Instance::new(info);
+        Self::try_init(info).unwrap_or_else(|error| panic!("Failed to initialize the Vulkan context: {error}"))
+    }
+
+    pub fn try_init(info: ContextInfo) -> Result<()> {
+        let instance = Instance::try_new(info)?;
 
-        let device = Device::new(&instance);
+        let device = Device::try_new(&instance)?;
 
         let allocator_info = vk_mem::AllocatorCreateInfo::new(&instance.instance, &device.device, device.physical_device);
 
-        let allocator = unsafe { vk_mem::Allocator::new(allocator_info) }.expect("Failed to create the allocator");
+        let allocator = unsafe { vk_mem::Allocator::new(allocator_info) }.map_err(Error::Allocation)?;
 
-        let glsl_compiler = shaderc::Compiler::new().expect("Failed to create GLSL compiler");
+        let glsl_compiler = shaderc::Compiler::new()
+            .map_err(|error| Error::ShaderCompilation(error.to_string()))?;
+
+        let handles = Box::leak(Box::new(DeviceHandles {
+            device: device.device.clone(),
+            main_queue: device.main_queue,
+            present_queue: device.present_queue,
+            extensions: device.extensions.clone(),
+        }));
 
         *CONTEXT.write() = Some(Context {
             glsl_compiler,
-            allocator,
+            allocator: Arc::new(allocator),
             device,
             instance,
         });
+        DEVICE_HANDLES.store(handles, Ordering::Release);
+
+        Ok(())
     }
 
     pub fn destroy() {
+        crate::CommandMarkers::disable();
+
+        #[cfg(debug_assertions)]
+        {
+            let report = Self::allocation_report();
+            if !report.is_empty() {
+                println!("Leaked allocations at context destruction:\n{report}");
+            }
+        }
+
+        CommandPool::release_current_thread();
+
+        DEVICE_HANDLES.store(ptr::null_mut(), Ordering::Release);
         *CONTEXT.write() = None;
     }
 
@@ -85,8 +161,8 @@ impl Context {
         })
     }
 
-    pub fn try_get() -> Option<ContextReadGuard> {
-        RwLockReadGuard::try_map(CONTEXT.read(), |context| context.as_ref()).ok()
+    pub fn try_get() -> Result<ContextReadGuard> {
+        RwLockReadGuard::try_map(CONTEXT.read(), |context| context.as_ref()).map_err(|_| Error::NotInitialized)
     }
 
     pub fn get_mut() -> ContextWriteGuard {
@@ -95,8 +171,8 @@ impl Context {
         })
     }
 
-    pub fn try_get_mut() -> Option<ContextWriteGuard> {
-        RwLockWriteGuard::try_map(CONTEXT.write(), |context| context.as_mut()).ok()
+    pub fn try_get_mut() -> Result<ContextWriteGuard> {
+        RwLockWriteGuard::try_map(CONTEXT.write(), |context| context.as_mut()).map_err(|_| Error::NotInitialized)
     }
     
     pub fn get_device() -> DeviceReadGuard {
@@ -105,6 +181,42 @@ impl Context {
         })
     }
 
+    pub fn handles() -> &'static DeviceHandles {
+        Self::try_handles().expect("Vulkan context is not initialized")
+    }
+
+    pub fn try_handles() -> Result<&'static DeviceHandles> {
+        let handles = DEVICE_HANDLES.load(Ordering::Acquire);
+        unsafe { handles.as_ref() }.ok_or(Error::NotInitialized)
+    }
+
+    #[inline]
+    pub fn cached_device() -> &'static ash::Device {
+        &Self::handles().device
+    }
+
+    #[inline]
+    pub fn cached_main_queue() -> Queue {
+        Self::handles().main_queue
+    }
+
+    pub fn wait_idle() {
+        Self::try_wait_idle().unwrap_or_else(|error| panic!("Failed to wait for device idle: {error}"))
+    }
+
+    pub fn try_wait_idle() -> Result<()> {
+        unsafe { Self::try_handles()?.device.device_wait_idle() }?;
+        Ok(())
+    }
+
+    pub fn allocation_report() -> AllocationReport {
+        crate::resource::report::allocation_report()
+    }
+
+    pub fn export_object_graph(path: impl AsRef<std::path::Path>) -> Result<()> {
+        Self::allocation_report().write_to_file(path)
+    }
+
     pub fn instance(&self) -> &Instance {
         &self.instance
     }
@@ -113,19 +225,59 @@ impl Context {
         &self.device
     }
 
+    pub fn has_resizable_bar(&self) -> bool {
+        self.device.resizable_bar
+    }
+
+    pub fn has_dynamic_rendering(&self) -> bool {
+        self.device.extensions.dynamic_rendering.is_some()
+    }
+
+    pub fn max_sampler_anisotropy(&self) -> Option<f32> {
+        self.device.max_sampler_anisotropy
+    }
+
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self.device.timestamp_period
+    }
+
+    pub fn main_queue(&self) -> Queue {
+        self.device.main_queue
+    }
+
+    pub fn present_queue(&self) -> Queue {
+        self.device.present_queue
+    }
+
+    pub fn main_queue_family(&self) -> QueueFamily {
+        self.device.main_queue.family()
+    }
+
+    pub fn present_queue_family(&self) -> QueueFamily {
+        self.device.present_queue.family()
+    }
+
     pub fn allocator(&self) -> &vk_mem::Allocator {
         &self.allocator
     }
 
+    pub fn shared_allocator(&self) -> &Arc<vk_mem::Allocator> {
+        &self.allocator
+    }
+
     pub fn glsl_compiler(&self) -> &shaderc::Compiler {
         &self.glsl_compiler
     }
 
-    pub fn window(&self) -> Option<&Window> {
-        Some(&self.instance.surface.as_ref()?.window)
+    pub fn window(&self) -> Option<&dyn SurfaceWindow> {
+        Some(self.instance.surface.as_ref()?.window.as_ref())
+    }
+
+    pub fn window_as<W: SurfaceWindow>(&self) -> Option<&W> {
+        (self.window()? as &dyn Any).downcast_ref()
     }
 
-    pub fn window_mut(&mut self) -> Option<&mut Window> {
-        Some(&mut self.instance.surface.as_mut()?.window)
+    pub fn window_as_mut<W: SurfaceWindow>(&mut self) -> Option<&mut W> {
+        (self.instance.surface.as_mut()?.window.as_mut() as &mut dyn Any).downcast_mut()
     }
 }
\ No newline at end of file
@@ -3,9 +3,14 @@ use std::ffi::{CStr, CString};
 use ash::vk;
 
 use crate::core::instance::{Instance, Surface};
+use crate::{Error, QueueFamily, Result};
 
+#[derive(Clone)]
 pub struct DeviceExtensions {
     pub swapchain: Option<ash::khr::swapchain::Device>,
+    pub buffer_marker: Option<ash::amd::buffer_marker::Device>,
+    pub dynamic_rendering: Option<ash::khr::dynamic_rendering::Device>,
+    pub synchronization2: Option<ash::khr::synchronization2::Device>,
 }
 
 pub struct Device {
@@ -15,12 +20,34 @@ pub struct Device {
     pub main_queue: Queue,
     pub present_queue: Queue,
 
-    pub command_pool: vk::CommandPool,
-
     pub extensions: DeviceExtensions,
+
+    pub resizable_bar: bool,
+    pub max_sampler_anisotropy: Option<f32>,
+    pub timestamp_period: Option<f32>,
 }
 
 impl Device {
+    const RESIZABLE_BAR_MIN_HEAP_SIZE: vk::DeviceSize = 256 * 1024 * 1024;
+
+    fn detect_resizable_bar(physical_device: vk::PhysicalDevice, instance: &ash::Instance) -> bool {
+        let props = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+
+        props.memory_types_as_slice().iter().any(|memory_type| {
+            let flags = vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE;
+
+            memory_type.property_flags.contains(flags)
+                && props.memory_heaps[memory_type.heap_index as usize].size > Self::RESIZABLE_BAR_MIN_HEAP_SIZE
+        })
+    }
+
+    fn supports_extension(physical_device: vk::PhysicalDevice, instance: &ash::Instance, name: &CStr) -> bool {
+        unsafe { instance.enumerate_device_extension_properties(physical_device) }
+            .unwrap_or_default()
+            .iter()
+            .any(|prop| prop.extension_name_as_c_str() == Ok(name))
+    }
+
     fn check_physical_device(
         physical_device: vk::PhysicalDevice,
         instance: &Instance,
@@ -109,18 +136,17 @@ impl Device {
     }
 
     pub fn new(instance: &Instance) -> Self {
+        Self::try_new(instance).unwrap_or_else(|error| panic!("Failed to create device: {error}"))
+    }
+
+    pub fn try_new(instance: &Instance) -> Result<Self> {
         let mut required_extensions = vec![];
 
         if instance.surface.is_some() {
             required_extensions.push(ash::khr::swapchain::NAME.as_ptr());
         }
 
-        for physical_device in unsafe {
-            instance
-                .instance
-                .enumerate_physical_devices()
-                .expect("Failed to enumerate physical devices")
-        } {
+        for physical_device in unsafe { instance.instance.enumerate_physical_devices() }? {
             if let Some((main_idx, present_idx)) =
                 Self::check_physical_device(physical_device, instance, &required_extensions)
             {
@@ -137,19 +163,70 @@ impl Device {
                 })
                 .collect();
 
-                let mut features2 = vk::PhysicalDeviceFeatures2::default();
+                let buffer_marker = Self::supports_extension(
+                    physical_device,
+                    &instance.instance,
+                    ash::amd::buffer_marker::NAME,
+                );
+
+                let dynamic_rendering = Self::supports_extension(
+                    physical_device,
+                    &instance.instance,
+                    ash::khr::dynamic_rendering::NAME,
+                );
+
+                let synchronization2 = Self::supports_extension(
+                    physical_device,
+                    &instance.instance,
+                    ash::khr::synchronization2::NAME,
+                );
+
+                let mut enabled_extensions = required_extensions.clone();
+                if buffer_marker {
+                    enabled_extensions.push(ash::amd::buffer_marker::NAME.as_ptr());
+                }
+                if dynamic_rendering {
+                    enabled_extensions.push(ash::khr::dynamic_rendering::NAME.as_ptr());
+                }
+                if synchronization2 {
+                    enabled_extensions.push(ash::khr::synchronization2::NAME.as_ptr());
+                }
+
+                let supported_features = unsafe { instance.instance.get_physical_device_features(physical_device) };
+                let limits = unsafe { instance.instance.get_physical_device_properties(physical_device) }.limits;
+
+                let max_sampler_anisotropy = (supported_features.sampler_anisotropy == vk::TRUE)
+                    .then_some(limits.max_sampler_anisotropy);
+
+                let timestamp_period = (limits.timestamp_compute_and_graphics == vk::TRUE && limits.timestamp_period > 0.0)
+                    .then_some(limits.timestamp_period);
+
+                let mut features2 = vk::PhysicalDeviceFeatures2::default().features(
+                    vk::PhysicalDeviceFeatures::default().sampler_anisotropy(max_sampler_anisotropy.is_some()),
+                );
+
+                let mut dynamic_rendering_features =
+                    vk::PhysicalDeviceDynamicRenderingFeatures::default().dynamic_rendering(true);
+                if dynamic_rendering {
+                    features2 = features2.push_next(&mut dynamic_rendering_features);
+                }
+
+                let mut synchronization2_features =
+                    vk::PhysicalDeviceSynchronization2Features::default().synchronization2(true);
+                if synchronization2 {
+                    features2 = features2.push_next(&mut synchronization2_features);
+                }
 
                 let device_info = vk::DeviceCreateInfo::default()
                     .queue_create_infos(queue_infos.as_slice())
-                    .enabled_extension_names(&required_extensions)
+                    .enabled_extension_names(&enabled_extensions)
                     .push_next(&mut features2);
 
                 let device = unsafe {
                     instance
                         .instance
                         .create_device(physical_device, &device_info, None)
-                }
-                .expect("Failed to create device");
+                }?;
 
                 let main_queue = Queue {
                     handle: unsafe {
@@ -178,26 +255,30 @@ impl Device {
                         .surface
                         .is_some()
                         .then(|| ash::khr::swapchain::Device::new(&instance.instance, &device)),
+                    buffer_marker: buffer_marker
+                        .then(|| ash::amd::buffer_marker::Device::new(&instance.instance, &device)),
+                    dynamic_rendering: dynamic_rendering
+                        .then(|| ash::khr::dynamic_rendering::Device::new(&instance.instance, &device)),
+                    synchronization2: synchronization2
+                        .then(|| ash::khr::synchronization2::Device::new(&instance.instance, &device)),
                 };
 
-                let command_pool_info = vk::CommandPoolCreateInfo::default()
-                    .queue_family_index(main_idx)
-                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
+                let resizable_bar = Self::detect_resizable_bar(physical_device, &instance.instance);
 
-                let command_pool = unsafe { device.create_command_pool(&command_pool_info, None) }
-                    .expect("Failed to create command pool");
-
-                return Self {
+                return Ok(Self {
                     physical_device,
                     device,
                     main_queue,
                     present_queue,
-                    command_pool,
                     extensions,
-                };
+                    resizable_bar,
+                    max_sampler_anisotropy,
+                    timestamp_period,
+                });
             }
         }
-        panic!("Failed to find a suitable physical device");
+
+        Err(Error::Unsupported(String::from("Failed to find a suitable physical device")))
     }
 }
 
@@ -205,14 +286,20 @@ impl Drop for Device {
     fn drop(&mut self) {
         println!("dropping the device");
         unsafe {
-            self.device.destroy_command_pool(self.command_pool, None);
             self.device.destroy_device(None);
         }
     }
 }
 
-#[derive(cvk_macros::VkHandle)]
+#[derive(cvk_macros::VkHandle, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Queue {
     pub handle: vk::Queue,
     pub family_idx: u32,
 }
+
+impl Queue {
+    #[inline]
+    pub fn family(&self) -> QueueFamily {
+        QueueFamily(self.family_idx)
+    }
+}
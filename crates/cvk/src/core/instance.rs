@@ -1,10 +1,14 @@
+use std::any::Any;
 use std::ffi::{CStr, CString, c_void};
 
 use ash::vk;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use winit::window::Window;
 
-use crate::ContextInfo;
+use crate::{ContextInfo, DebugPrintf, Error, Result, ValidationFeatures};
+
+pub trait SurfaceWindow: HasWindowHandle + HasDisplayHandle + Any + Send + Sync {}
+
+impl<W: HasWindowHandle + HasDisplayHandle + Any + Send + Sync> SurfaceWindow for W {}
 
 pub struct Instance {
     pub debug_utils: Option<DebugUtils>,
@@ -22,36 +26,70 @@ impl Instance {
         callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
         _user_data: *mut c_void,
     ) -> u32 {
-        if let Some(msg) = unsafe { (*callback_data).message_as_c_str() } {
-            println!("Validation Layer:\n {}", msg.to_string_lossy());
+        let callback_data = unsafe { &*callback_data };
+
+        let is_debug_printf = unsafe { callback_data.message_id_name_as_c_str() }
+            .is_some_and(|name| name.to_bytes().ends_with(b"DEBUG-PRINTF"));
+
+        if let Some(msg) = unsafe { callback_data.message_as_c_str() } {
+            if is_debug_printf {
+                if !DebugPrintf::capture(&msg.to_string_lossy()) {
+                    println!("Debug Printf:\n {}", msg.to_string_lossy());
+                }
+            } else {
+                println!("Validation Layer:\n {}", msg.to_string_lossy());
+            }
         }
 
         vk::FALSE
     }
 
     pub fn new(info: ContextInfo) -> Self {
-        let entry = unsafe { ash::Entry::load().expect("Failed to load Vulkan entry") };
+        Self::try_new(info).unwrap_or_else(|error| panic!("Failed to create instance: {error}"))
+    }
 
-        let layer_names = unsafe { entry.enumerate_instance_layer_properties().unwrap() }
+    pub fn try_new(info: ContextInfo) -> Result<Self> {
+        let entry = unsafe { ash::Entry::load() }?;
+
+        let layer_names = unsafe { entry.enumerate_instance_layer_properties() }?
             .iter()
             .filter_map(|prop| Some(CString::from(prop.layer_name_as_c_str().ok()?)))
             .collect::<Vec<_>>();
 
-        let extension_names =
-            unsafe { entry.enumerate_instance_extension_properties(None).unwrap() }
+        let mut extension_names =
+            unsafe { entry.enumerate_instance_extension_properties(None) }?
                 .iter()
                 .filter_map(|prop| Some(CString::from(prop.extension_name_as_c_str().ok()?)))
                 .collect::<Vec<_>>();
 
+        let validation_features = if info.debugging {
+            info.validation_features
+        } else {
+            ValidationFeatures::empty()
+        };
+
+        if validation_features.contains(ValidationFeatures::GPU_ASSISTED | ValidationFeatures::DEBUG_PRINTF) {
+            return Err(Error::InvalidParams(
+                "GPU-assisted validation and debug printf cannot be enabled at the same time",
+            ));
+        }
+
+        if !validation_features.is_empty() && layer_names.iter().any(|name| name.as_c_str() == Self::VALIDATION_LAYER) {
+            extension_names.extend(
+                unsafe { entry.enumerate_instance_extension_properties(Some(Self::VALIDATION_LAYER)) }?
+                    .iter()
+                    .filter_map(|prop| Some(CString::from(prop.extension_name_as_c_str().ok()?))),
+            );
+        }
+
         let mut required_layers: Vec<*const i8> = vec![];
         let mut required_extensions: Vec<*const i8> = vec![];
 
         if let Some(ref window) = info.window {
-            let raw_display_handle = window.display_handle().unwrap().as_raw();
+            let raw_display_handle = window.display_handle()?.as_raw();
 
             let mut surface_extenstions =
-                ash_window::enumerate_required_extensions(raw_display_handle)
-                    .expect("Failed to enumerate surface extensions")
+                ash_window::enumerate_required_extensions(raw_display_handle)?
                     .into_iter()
                     .map(|&raw| raw)
                     .collect::<Vec<_>>();
@@ -64,23 +102,27 @@ impl Instance {
             required_extensions.push(ash::ext::debug_utils::NAME.as_ptr());
         }
 
+        if !validation_features.is_empty() {
+            required_extensions.push(ash::ext::validation_features::NAME.as_ptr());
+        }
+
         for &ext in required_extensions.iter() {
             let ext_cstr = CString::from(unsafe { CStr::from_ptr(ext) });
             if !extension_names.contains(&ext_cstr) {
-                panic!(
+                return Err(Error::Unsupported(format!(
                     "The required extension '{}' is not supported",
                     ext_cstr.to_string_lossy()
-                );
+                )));
             }
         }
 
         for &layer in required_layers.iter() {
             let layer_cstr = CString::from(unsafe { CStr::from_ptr(layer) });
             if !layer_names.contains(&layer_cstr) {
-                panic!(
+                return Err(Error::Unsupported(format!(
                     "The required layer '{}' is not present",
                     layer_cstr.to_string_lossy()
-                );
+                )));
             }
         }
 
@@ -96,44 +138,64 @@ impl Instance {
             .enabled_layer_names(required_layers.as_slice())
             .enabled_extension_names(required_extensions.as_slice());
 
+        let enabled_validation_features = validation_features.to_vk();
+        let mut validation_features_info = vk::ValidationFeaturesEXT::default()
+            .enabled_validation_features(&enabled_validation_features);
+
+        if !validation_features.is_empty() {
+            instance_info = instance_info.push_next(&mut validation_features_info);
+        }
+
         let mut debug_messenger_info = None;
 
         if info.debugging {
             use vk::DebugUtilsMessageSeverityFlagsEXT as Severity;
             use vk::DebugUtilsMessageTypeFlagsEXT as Type;
 
+            let mut severity = Severity::VERBOSE | Severity::WARNING | Severity::ERROR;
+            if validation_features.contains(ValidationFeatures::DEBUG_PRINTF) {
+                severity |= Severity::INFO;
+            }
+
             debug_messenger_info = Some(vk::DebugUtilsMessengerCreateInfoEXT::default()
-                .message_severity(Severity::VERBOSE | Severity::WARNING | Severity::ERROR)
+                .message_severity(severity)
                 .message_type(Type::GENERAL | Type::PERFORMANCE | Type::VALIDATION)
                 .pfn_user_callback(Some(Self::debug_callback)));
             instance_info = instance_info.push_next(debug_messenger_info.as_mut().unwrap());
 
         };
 
-        let instance = unsafe {
-            entry
-                .create_instance(&instance_info, None)
-                .expect("Failed to create VkInstance")
-        };
+        let instance = unsafe { entry.create_instance(&instance_info, None) }?;
+
+        let instance = utils::guard(instance, |instance| unsafe {
+            instance.destroy_instance(None);
+        });
 
         let debug_utils = if let Some(messenger_info) = debug_messenger_info {
-            Some(DebugUtils::new(&entry, &instance, &messenger_info))
+            Some(DebugUtils::new(&entry, &instance, &messenger_info)?)
         } else {
             None
         };
 
-        let surface = if let Some(window) = info.window {
-            Some(Surface::new(&entry, &instance, window))
-        } else {
-            None
+        let surface = match info.window {
+            Some(window) => match Surface::new(&entry, &instance, window) {
+                Ok(surface) => Some(surface),
+                Err(error) => {
+                    if let Some(DebugUtils { ref fns, messenger }) = debug_utils {
+                        unsafe { fns.destroy_debug_utils_messenger(messenger, None) };
+                    }
+                    return Err(error);
+                }
+            },
+            None => None,
         };
 
-        Self {
+        Ok(Self {
             debug_utils,
             surface,
-            instance,
+            instance: utils::ScopeGuard::into_inner(instance),
             _entry: entry,
-        }
+        })
     }
 }
 
@@ -167,41 +229,33 @@ impl DebugUtils {
         entry: &ash::Entry,
         instance: &ash::Instance,
         messenger_info: &vk::DebugUtilsMessengerCreateInfoEXT,
-    ) -> Self {
+    ) -> Result<Self> {
         let fns = ash::ext::debug_utils::Instance::new(&entry, &instance);
 
-        let messenger = unsafe { fns.create_debug_utils_messenger(messenger_info, None) }
-            .expect("Failed to create debug messenger");
+        let messenger = unsafe { fns.create_debug_utils_messenger(messenger_info, None) }?;
 
-        Self { fns, messenger }
+        Ok(Self { fns, messenger })
     }
 }
 
 #[derive(cvk_macros::VkHandle)]
 pub struct Surface {
     pub(crate) handle: vk::SurfaceKHR,
-    pub(crate) window: Window,
+    pub(crate) window: Box<dyn SurfaceWindow>,
     pub(crate) fns: ash::khr::surface::Instance,
 }
 
 impl Surface {
-    fn new(entry: &ash::Entry, instance: &ash::Instance, window: Window) -> Self {
-        let display_handle = window
-            .display_handle()
-            .expect("Failed to acquire display handle")
-            .as_raw();
-        let window_handle = window
-            .window_handle()
-            .expect("Failed to acquire window handle")
-            .as_raw();
-
-        Self {
+    fn new(entry: &ash::Entry, instance: &ash::Instance, window: Box<dyn SurfaceWindow>) -> Result<Self> {
+        let display_handle = window.display_handle()?.as_raw();
+        let window_handle = window.window_handle()?.as_raw();
+
+        Ok(Self {
             handle: unsafe {
                 ash_window::create_surface(entry, instance, display_handle, window_handle, None)
-                    .expect("Failed to create surface")
-            },
+            }?,
             window,
             fns: ash::khr::surface::Instance::new(&entry, &instance),
-        }
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use ash::vk;
+use parking_lot::Mutex;
+use vk_mem::Alloc;
+
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{BufferUsage, Context, GpuUse, MemoryUsage, Recording, Result, VkHandle};
+
+struct MarkerState {
+    buffer: vk::Buffer,
+    allocation: vk_mem::Allocation,
+    gpu_use: GpuUse,
+    labels: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl MarkerState {
+    fn new() -> Result<Self> {
+        let size = size_of::<u32>() as vk::DeviceSize;
+
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .usage(BufferUsage::TRANSFER_DST);
+
+        let alloc_info = vk_mem::AllocationCreateInfo {
+            usage: MemoryUsage::Readback.as_vma(),
+            flags: MemoryUsage::Readback.vma_flags(),
+            ..Default::default()
+        };
+
+        let context = Context::try_get()?;
+        let (buffer, allocation) = unsafe { context.allocator().create_buffer(&buffer_info, &alloc_info) }
+            .map_err(crate::Error::Allocation)?;
+
+        track_allocation(LiveAllocation::new(
+            ResourceKind::Buffer,
+            vk::Handle::as_raw(buffer),
+            size,
+            Some("command marker buffer".to_owned()),
+        ));
+
+        Ok(Self {
+            buffer,
+            allocation,
+            gpu_use: GpuUse::default(),
+            labels: vec![],
+            ids: HashMap::new(),
+        })
+    }
+
+    fn id(&mut self, label: &str) -> u32 {
+        if let Some(&id) = self.ids.get(label) {
+            return id;
+        }
+
+        self.labels.push(label.to_owned());
+        let id = self.labels.len() as u32;
+        self.ids.insert(label.to_owned(), id);
+        id
+    }
+
+    fn last_reached(&self) -> Option<&str> {
+        let context = Context::get();
+        let allocator = context.allocator();
+        allocator
+            .invalidate_allocation(&self.allocation, 0, size_of::<u32>() as vk::DeviceSize)
+            .ok()?;
+
+        let ptr = allocator.get_allocation_info(&self.allocation).mapped_data as *const u32;
+        if ptr.is_null() {
+            return None;
+        }
+        let id = unsafe { ptr.read_volatile() };
+
+        self.labels.get((id as usize).checked_sub(1)?).map(String::as_str)
+    }
+}
+
+impl Drop for MarkerState {
+    fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        untrack_allocation(ResourceKind::Buffer, vk::Handle::as_raw(self.buffer));
+        unsafe {
+            Context::get()
+                .allocator()
+                .destroy_buffer(self.buffer, &mut self.allocation);
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static HANG_TIMEOUT_NS: AtomicU64 = AtomicU64::new(0);
+static STATE: Mutex<Option<MarkerState>> = Mutex::new(None);
+
+pub struct CommandMarkers;
+
+impl CommandMarkers {
+    pub const DEFAULT_HANG_TIMEOUT: Duration = Duration::from_secs(2);
+
+    pub fn enable(hang_timeout: Duration) {
+        Self::try_enable(hang_timeout)
+            .unwrap_or_else(|error| panic!("Failed to enable command markers: {error}"))
+    }
+
+    pub fn try_enable(hang_timeout: Duration) -> Result<()> {
+        *STATE.lock() = Some(MarkerState::new()?);
+
+        HANG_TIMEOUT_NS.store(hang_timeout.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+        ENABLED.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn disable() {
+        ENABLED.store(false, Ordering::Release);
+        *STATE.lock() = None;
+    }
+
+    #[inline]
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    pub fn hang_timeout() -> Option<Duration> {
+        Self::is_enabled().then(|| Duration::from_nanos(HANG_TIMEOUT_NS.load(Ordering::Relaxed)))
+    }
+
+    pub fn last_reached() -> Option<String> {
+        STATE.lock().as_ref()?.last_reached().map(str::to_owned)
+    }
+
+    pub(crate) fn report_hang(waited: Duration) {
+        match Self::last_reached() {
+            Some(label) => println!(
+                "Warning: fence wait exceeded {waited:?}, possible GPU hang after marker '{label}'"
+            ),
+            None => println!("Warning: fence wait exceeded {waited:?}, possible GPU hang before the first marker"),
+        }
+    }
+
+    pub(crate) fn report_device_lost() {
+        match Self::last_reached() {
+            Some(label) => println!("Error: device lost, last reached marker '{label}'"),
+            None => println!("Error: device lost before the first marker"),
+        }
+    }
+}
+
+// --------------------- Marker commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn marker(&mut self, label: &str) {
+        if !CommandMarkers::is_enabled() {
+            return;
+        }
+
+        let mut state = STATE.lock();
+        let Some(state) = state.as_mut() else {
+            return;
+        };
+
+        let id = state.id(label);
+        let buffer = state.buffer;
+
+        match Context::handles().extensions.buffer_marker {
+            Some(ref buffer_marker) => unsafe {
+                buffer_marker.cmd_write_buffer_marker(
+                    self.handle(),
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    buffer,
+                    0,
+                    id,
+                );
+            },
+            None => {
+                let barrier = vk::MemoryBarrier::default()
+                    .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+
+                let device = Context::cached_device();
+                unsafe {
+                    device.cmd_pipeline_barrier(
+                        self.handle(),
+                        vk::PipelineStageFlags::ALL_COMMANDS,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::DependencyFlags::empty(),
+                        &[barrier],
+                        &[],
+                        &[],
+                    );
+                    device.cmd_fill_buffer(self.handle(), buffer, 0, size_of::<u32>() as vk::DeviceSize, id);
+                }
+            }
+        }
+
+        self.track(&state.gpu_use);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use parking_lot::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubmitThresholds {
+    pub single_use_submits: u32,
+    pub staging_allocations: u32,
+    pub fence_waits: u32,
+}
+
+impl Default for SubmitThresholds {
+    fn default() -> Self {
+        Self {
+            single_use_submits: 100,
+            staging_allocations: 100,
+            fence_waits: 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct SubmitStats {
+    pub single_use_submits: u32,
+    pub staging_allocations: u32,
+    pub fence_waits: u32,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static THRESHOLDS: Mutex<Option<SubmitThresholds>> = Mutex::new(None);
+
+static SINGLE_USE_SUBMITS: AtomicU32 = AtomicU32::new(0);
+static STAGING_ALLOCATIONS: AtomicU32 = AtomicU32::new(0);
+static FENCE_WAITS: AtomicU32 = AtomicU32::new(0);
+
+impl SubmitStats {
+    pub fn enable(thresholds: SubmitThresholds) {
+        *THRESHOLDS.lock() = Some(thresholds);
+        ENABLED.store(true, Ordering::Release);
+    }
+
+    pub fn disable() {
+        ENABLED.store(false, Ordering::Release);
+        *THRESHOLDS.lock() = None;
+    }
+
+    #[inline]
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    pub fn current() -> Self {
+        Self {
+            single_use_submits: SINGLE_USE_SUBMITS.load(Ordering::Relaxed),
+            staging_allocations: STAGING_ALLOCATIONS.load(Ordering::Relaxed),
+            fence_waits: FENCE_WAITS.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn end_frame() -> Self {
+        let stats = Self {
+            single_use_submits: SINGLE_USE_SUBMITS.swap(0, Ordering::Relaxed),
+            staging_allocations: STAGING_ALLOCATIONS.swap(0, Ordering::Relaxed),
+            fence_waits: FENCE_WAITS.swap(0, Ordering::Relaxed),
+        };
+
+        if let Some(thresholds) = *THRESHOLDS.lock() {
+            stats.warn(&thresholds);
+        }
+
+        stats
+    }
+
+    fn warn(&self, thresholds: &SubmitThresholds) {
+        if self.single_use_submits > thresholds.single_use_submits {
+            println!(
+                "Warning: {} single-use submits this frame (threshold {}); record batches of commands into one CommandBuffer instead",
+                self.single_use_submits, thresholds.single_use_submits
+            );
+        }
+
+        if self.staging_allocations > thresholds.staging_allocations {
+            println!(
+                "Warning: {} staging allocations this frame (threshold {}); use a FrameAllocator or MemoryUsage::PreferDeviceMapped for per-frame uploads",
+                self.staging_allocations, thresholds.staging_allocations
+            );
+        }
+
+        if self.fence_waits > thresholds.fence_waits {
+            println!(
+                "Warning: {} blocking fence waits this frame (threshold {}); submit work together and wait once per frame",
+                self.fence_waits, thresholds.fence_waits
+            );
+        }
+    }
+}
+
+#[inline]
+fn record(counter: &AtomicU32) {
+    if SubmitStats::is_enabled() {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_single_use_submit() {
+    record(&SINGLE_USE_SUBMITS);
+}
+
+pub(crate) fn record_staging_allocation() {
+    record(&STAGING_ALLOCATIONS);
+}
+
+pub(crate) fn record_fence_wait() {
+    record(&FENCE_WAITS);
+}
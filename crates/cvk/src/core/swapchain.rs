@@ -0,0 +1,322 @@
+use ash::vk::{self, Format};
+use utils::{Buildable, ParamTracker, TryBuild};
+
+use crate::profiling::profile_scope;
+use crate::{Attachment, Context, DeviceHandles, Extent2D, Image, ImageUsage, Semaphore, Sharing, VkHandle};
+
+pub use vk::{ColorSpaceKHR as ColorSpace, PresentModeKHR as PresentMode};
+
+pub struct Swapchain {
+    handle: vk::SwapchainKHR,
+    images: Vec<Image>,
+    views: Vec<vk::ImageView>,
+
+    format: Format,
+    color_space: ColorSpace,
+    extent: Extent2D,
+    present_mode: PresentMode,
+
+    builder: SwapchainBuilder,
+}
+
+impl Swapchain {
+    #[inline]
+    pub const fn format(&self) -> Format {
+        self.format
+    }
+
+    #[inline]
+    pub const fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    #[inline]
+    pub const fn extent(&self) -> Extent2D {
+        self.extent
+    }
+
+    #[inline]
+    pub const fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    #[inline]
+    pub fn images(&self) -> &[Image] {
+        &self.images
+    }
+
+    #[inline]
+    pub fn image(&self, index: u32) -> &Image {
+        &self.images[index as usize]
+    }
+
+    pub fn attachment(&self, index: u32) -> Attachment<'_> {
+        Attachment::from_raw(&self.images[index as usize], self.views[index as usize])
+    }
+
+    pub fn acquire_next_image(&self, signal: &Semaphore) -> (u32, bool) {
+        self.try_acquire_next_image(signal)
+            .unwrap_or_else(|error| panic!("Failed to acquire swapchain image: {error}"))
+    }
+
+    pub fn try_acquire_next_image(&self, signal: &Semaphore) -> crate::Result<(u32, bool)> {
+        profile_scope!("cvk::acquire_next_image");
+
+        let handles = Context::try_handles()?;
+        let swapchain_fns = Self::fns(handles)?;
+
+        let (index, suboptimal) =
+            unsafe { swapchain_fns.acquire_next_image(self.handle, u64::MAX, signal.handle(), vk::Fence::null()) }?;
+
+        Ok((index, suboptimal))
+    }
+
+    pub fn present(&self, index: u32, wait: &Semaphore) -> bool {
+        self.try_present(index, wait)
+            .unwrap_or_else(|error| panic!("Failed to present swapchain image: {error}"))
+    }
+
+    pub fn try_present(&self, index: u32, wait: &Semaphore) -> crate::Result<bool> {
+        profile_scope!("cvk::present");
+
+        let handles = Context::try_handles()?;
+        let swapchain_fns = Self::fns(handles)?;
+
+        let swapchains = [self.handle];
+        let indices = [index];
+        let wait_semaphores = [wait.handle()];
+
+        let present_info = vk::PresentInfoKHR::default()
+            .swapchains(&swapchains)
+            .image_indices(&indices)
+            .wait_semaphores(&wait_semaphores);
+
+        let suboptimal =
+            unsafe { swapchain_fns.queue_present(handles.present_queue.handle, &present_info) }?;
+
+        Ok(suboptimal)
+    }
+
+    pub fn recreate(&mut self, extent: impl Into<Extent2D>) {
+        self.try_recreate(extent)
+            .unwrap_or_else(|error| panic!("Failed to recreate swapchain: {error}"))
+    }
+
+    pub fn try_recreate(&mut self, extent: impl Into<Extent2D>) -> crate::Result<()> {
+        for image in &self.images {
+            image.gpu_use().try_wait_idle()?;
+        }
+
+        let mut builder = self.builder.clone();
+        builder.extent = Some(extent.into());
+
+        let swapchain = builder.create(self.handle)?;
+        *self = swapchain;
+
+        Ok(())
+    }
+
+    fn fns(handles: &DeviceHandles) -> crate::Result<&ash::khr::swapchain::Device> {
+        handles
+            .extensions
+            .swapchain
+            .as_ref()
+            .ok_or_else(|| crate::Error::Unsupported("Device does not support swapchains".to_string()))
+    }
+}
+
+impl VkHandle for Swapchain {
+    type HandleType = vk::SwapchainKHR;
+
+    fn handle(&self) -> Self::HandleType {
+        self.handle
+    }
+}
+
+impl Drop for Swapchain {
+    fn drop(&mut self) {
+        for image in &self.images {
+            let _ = image.gpu_use().try_wait_idle();
+        }
+
+        let handles = Context::handles();
+        unsafe {
+            for &view in &self.views {
+                Context::cached_device().destroy_image_view(view, None);
+            }
+
+            if let Some(ref swapchain_fns) = handles.extensions.swapchain {
+                swapchain_fns.destroy_swapchain(self.handle, None);
+            }
+        }
+    }
+}
+
+impl Buildable for Swapchain {
+    type Builder<'a> = SwapchainBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+#[param(getters, snapshot)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapchainBuilder {
+    extent: Option<Extent2D>,
+    #[param(default = Format::B8G8R8A8_SRGB)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    format: Format,
+    #[param(default = ColorSpace::SRGB_NONLINEAR)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    color_space: ColorSpace,
+    #[param(default = PresentMode::FIFO)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    present_mode: PresentMode,
+    #[param(default = 3)]
+    image_count: u32,
+    #[param(default = ImageUsage::COLOR_ATTACHMENT)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    usage: ImageUsage,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl SwapchainBuilder {
+    fn create(&self, old_swapchain: vk::SwapchainKHR) -> crate::Result<Swapchain> {
+        profile_scope!("cvk::build_swapchain");
+
+        self.check_required()?;
+
+        let context = Context::try_get()?;
+        let device = context.device();
+        let swapchain_fns = Swapchain::fns(Context::try_handles()?)?;
+
+        let surface = context
+            .instance()
+            .surface
+            .as_ref()
+            .ok_or_else(|| crate::Error::Unsupported("Context was created without a window surface".to_string()))?;
+
+        let capabilities = unsafe {
+            surface
+                .fns
+                .get_physical_device_surface_capabilities(device.physical_device, surface.handle)
+        }?;
+
+        let formats =
+            unsafe { surface.fns.get_physical_device_surface_formats(device.physical_device, surface.handle) }?;
+
+        let present_modes = unsafe {
+            surface
+                .fns
+                .get_physical_device_surface_present_modes(device.physical_device, surface.handle)
+        }?;
+
+        let surface_format = formats
+            .iter()
+            .find(|format| format.format == self.format && format.color_space == self.color_space)
+            .or(formats.first())
+            .copied()
+            .ok_or_else(|| crate::Error::Unsupported("Surface does not report any formats".to_string()))?;
+
+        let present_mode = if present_modes.contains(&self.present_mode) {
+            self.present_mode
+        } else {
+            PresentMode::FIFO
+        };
+
+        let extent = if capabilities.current_extent.width != u32::MAX {
+            Extent2D::new(capabilities.current_extent.width, capabilities.current_extent.height)
+        } else {
+            let extent = self
+                .extent
+                .ok_or(crate::Error::InvalidParams("Surface extent is undefined and no swapchain extent was given"))?;
+
+            Extent2D::new(
+                extent
+                    .width
+                    .clamp(capabilities.min_image_extent.width, capabilities.max_image_extent.width),
+                extent
+                    .height
+                    .clamp(capabilities.min_image_extent.height, capabilities.max_image_extent.height),
+            )
+        };
+
+        let mut image_count = self.image_count.max(capabilities.min_image_count);
+        if capabilities.max_image_count > 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+
+        let sharing = Sharing::new(&[context.main_queue_family(), context.present_queue_family()]);
+        let family_indices = sharing.family_indices();
+
+        let swapchain_info = vk::SwapchainCreateInfoKHR::default()
+            .surface(surface.handle)
+            .min_image_count(image_count)
+            .image_format(surface_format.format)
+            .image_color_space(surface_format.color_space)
+            .image_extent(extent.to_vk())
+            .image_array_layers(1)
+            .image_usage(self.usage)
+            .image_sharing_mode(sharing.sharing_mode())
+            .queue_family_indices(&family_indices)
+            .pre_transform(capabilities.current_transform)
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .present_mode(present_mode)
+            .clipped(true)
+            .old_swapchain(old_swapchain);
+
+        let handle = unsafe { swapchain_fns.create_swapchain(&swapchain_info, None) }?;
+        let handle = utils::guard(handle, |handle| unsafe { swapchain_fns.destroy_swapchain(handle, None) });
+
+        let images = unsafe { swapchain_fns.get_swapchain_images(*handle) }?
+            .into_iter()
+            .map(|image| Image::from_swapchain(image, surface_format.format, extent, sharing.clone()))
+            .collect::<Vec<_>>();
+
+        let mut views = utils::guard(Vec::with_capacity(images.len()), |views: Vec<vk::ImageView>| unsafe {
+            for view in views {
+                device.device.destroy_image_view(view, None);
+            }
+        });
+
+        for image in &images {
+            let view_info = vk::ImageViewCreateInfo::default()
+                .image(image.handle())
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(surface_format.format)
+                .subresource_range(
+                    vk::ImageSubresourceRange::default()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .level_count(1)
+                        .layer_count(1),
+                );
+
+            views.push(unsafe { device.device.create_image_view(&view_info, None) }?);
+        }
+
+        let views = utils::ScopeGuard::into_inner(views);
+        let handle = utils::ScopeGuard::into_inner(handle);
+
+        Ok(Swapchain {
+            handle,
+            images,
+            views,
+
+            format: surface_format.format,
+            color_space: surface_format.color_space,
+            extent,
+            present_mode,
+
+            builder: self.clone(),
+        })
+    }
+}
+
+impl TryBuild for SwapchainBuilder {
+    type Target = Swapchain;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.create(vk::SwapchainKHR::null())
+    }
+}
@@ -0,0 +1,240 @@
+use ash::vk;
+
+use crate::{Context, GpuUse, PipelineStage, Recording, Result, VkHandle};
+
+// --------------------- Timestamp query pool ---------------------
+
+#[derive(cvk_macros::VkHandle, Debug)]
+pub struct TimestampQueryPool {
+    handle: vk::QueryPool,
+    capacity: u32,
+    period: f32,
+    gpu_use: GpuUse,
+}
+
+impl TimestampQueryPool {
+    pub fn new(capacity: u32) -> Self {
+        Self::try_new(capacity).unwrap_or_else(|error| panic!("Failed to create timestamp query pool: {error}"))
+    }
+
+    pub fn try_new(capacity: u32) -> Result<Self> {
+        if capacity == 0 {
+            return Err(crate::Error::InvalidParams("Timestamp query pool capacity needs to be greater than zero"));
+        }
+
+        let period = Context::try_get()?.timestamp_period().ok_or(crate::Error::Unsupported(String::from(
+            "Device does not support timestamp queries on graphics and compute queues",
+        )))?;
+
+        let info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(capacity);
+
+        let handle = unsafe { Context::cached_device().create_query_pool(&info, None) }?;
+
+        Ok(Self {
+            handle,
+            capacity,
+            period,
+            gpu_use: GpuUse::default(),
+        })
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+
+    #[inline]
+    pub fn ticks_to_ns(&self, ticks: u64) -> f64 {
+        ticks as f64 * self.period as f64
+    }
+
+    /// Returns `None` while any of the queries is still pending.
+    pub fn try_results(&self, first: u32, count: u32) -> Result<Option<Vec<u64>>> {
+        if first.checked_add(count).is_none_or(|end| end > self.capacity) {
+            return Err(crate::Error::InvalidParams("Timestamp query range exceeds the pool capacity"));
+        }
+
+        let mut results = vec![0u64; count as usize];
+
+        match unsafe {
+            Context::cached_device().get_query_pool_results(
+                self.handle,
+                first,
+                &mut results,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        } {
+            Ok(()) => Ok(Some(results)),
+            Err(vk::Result::NOT_READY) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl Drop for TimestampQueryPool {
+    fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        unsafe {
+            Context::cached_device().destroy_query_pool(self.handle, None);
+        }
+    }
+}
+
+// --------------------- GPU zones ---------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpuZone {
+    pub name: &'static str,
+    pub depth: u32,
+    pub start_ns: f64,
+    pub duration_ns: f64,
+}
+
+struct PendingZone {
+    name: &'static str,
+    depth: u32,
+    begin: u32,
+    end: Option<u32>,
+}
+
+/// Records named GPU zones as pairs of timestamps. Zones past the query capacity of a frame are dropped.
+pub struct GpuProfiler {
+    pool: TimestampQueryPool,
+    zones: Vec<PendingZone>,
+    open: Vec<usize>,
+    next_query: u32,
+}
+
+impl GpuProfiler {
+    pub fn new(max_zones: u32) -> Self {
+        Self::try_new(max_zones).unwrap_or_else(|error| panic!("Failed to create GPU profiler: {error}"))
+    }
+
+    pub fn try_new(max_zones: u32) -> Result<Self> {
+        let capacity = max_zones
+            .checked_mul(2)
+            .ok_or(crate::Error::InvalidParams("GPU profiler zone count is too large"))?;
+
+        Ok(Self {
+            pool: TimestampQueryPool::try_new(capacity)?,
+            zones: vec![],
+            open: vec![],
+            next_query: 0,
+        })
+    }
+
+    #[inline]
+    pub fn pool(&self) -> &TimestampQueryPool {
+        &self.pool
+    }
+
+    /// Resets the queries of the previous frame. Waits until the GPU has finished with them.
+    pub fn begin_frame(&mut self, recording: &mut Recording<'_>) -> Result<()> {
+        self.pool.gpu_use.try_wait_idle()?;
+
+        self.zones.clear();
+        self.open.clear();
+        self.next_query = 0;
+
+        unsafe {
+            Context::cached_device().cmd_reset_query_pool(recording.handle(), self.pool.handle, 0, self.pool.capacity);
+        }
+        recording.track(&self.pool.gpu_use);
+
+        Ok(())
+    }
+
+    pub fn begin_zone(&mut self, recording: &mut Recording<'_>, name: &'static str) {
+        let depth = self.open.len() as u32;
+
+        match self.write_timestamp(recording, PipelineStage::TOP_OF_PIPE) {
+            Some(begin) => {
+                self.open.push(self.zones.len());
+                self.zones.push(PendingZone {
+                    name,
+                    depth,
+                    begin,
+                    end: None,
+                });
+            }
+            None => self.open.push(usize::MAX),
+        }
+    }
+
+    pub fn end_zone(&mut self, recording: &mut Recording<'_>) {
+        let index = self.open.pop().expect("GPU zone ended without a matching begin_zone");
+
+        if index != usize::MAX {
+            self.zones[index].end = self.write_timestamp(recording, PipelineStage::BOTTOM_OF_PIPE);
+        }
+    }
+
+    fn write_timestamp(&mut self, recording: &mut Recording<'_>, stage: PipelineStage) -> Option<u32> {
+        if self.next_query >= self.pool.capacity {
+            return None;
+        }
+
+        let query = self.next_query;
+        self.next_query += 1;
+
+        unsafe {
+            Context::cached_device().cmd_write_timestamp(recording.handle(), stage, self.pool.handle, query);
+        }
+        recording.track(&self.pool.gpu_use);
+
+        Some(query)
+    }
+
+    /// Waits for the recorded frame and returns its zones. With the `profiling` feature each zone is
+    /// also emitted as a `cvk::gpu` tracing event.
+    pub fn try_collect(&self) -> Result<Vec<GpuZone>> {
+        if self.next_query == 0 {
+            return Ok(vec![]);
+        }
+
+        self.pool.gpu_use.try_wait_idle()?;
+
+        let timestamps = self
+            .pool
+            .try_results(0, self.next_query)?
+            .ok_or(crate::Error::InvalidParams("GPU zones were collected before the frame was submitted"))?;
+
+        let origin = timestamps[0];
+
+        let zones = self
+            .zones
+            .iter()
+            .filter_map(|zone| {
+                let begin = timestamps[zone.begin as usize];
+                let end = timestamps[zone.end? as usize];
+
+                Some(GpuZone {
+                    name: zone.name,
+                    depth: zone.depth,
+                    start_ns: self.pool.ticks_to_ns(begin.saturating_sub(origin)),
+                    duration_ns: self.pool.ticks_to_ns(end.saturating_sub(begin)),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        #[cfg(feature = "profiling")]
+        for zone in &zones {
+            ::tracing::info!(
+                target: "cvk::gpu",
+                zone = zone.name,
+                depth = zone.depth,
+                start_ns = zone.start_ns,
+                duration_ns = zone.duration_ns,
+            );
+        }
+
+        Ok(zones)
+    }
+}
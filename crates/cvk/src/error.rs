@@ -0,0 +1,99 @@
+use std::{fmt, io, path::PathBuf};
+
+use ash::vk;
+use raw_window_handle::HandleError;
+use utils::{MissingParamsError, SpanError};
+
+use crate::TimelineValue;
+
+#[derive(Debug)]
+pub enum Error {
+    Vulkan(vk::Result),
+    Allocation(vk::Result),
+    Loading(ash::LoadingError),
+    WindowHandle(HandleError),
+    Unsupported(String),
+    NotInitialized,
+    MissingParams(MissingParamsError),
+    InvalidParams(&'static str),
+    InvalidSpan(SpanError<vk::DeviceSize>),
+    ShaderCompilation(String),
+    Io(PathBuf, io::Error),
+    ResourceInUse(TimelineValue),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    #[inline]
+    pub fn is_out_of_date(&self) -> bool {
+        matches!(self, Error::Vulkan(vk::Result::ERROR_OUT_OF_DATE_KHR))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Vulkan(result) => write!(f, "Vulkan call failed with {result}"),
+            Error::Allocation(result) => write!(f, "Memory allocation failed with {result}"),
+            Error::Loading(error) => write!(f, "Failed to load Vulkan entry: {error}"),
+            Error::WindowHandle(error) => write!(f, "Failed to acquire window handle: {error}"),
+            Error::Unsupported(msg) => write!(f, "{msg}"),
+            Error::NotInitialized => write!(f, "Vulkan context is not initialized"),
+            Error::MissingParams(error) => write!(f, "{error}"),
+            Error::InvalidParams(msg) => write!(f, "{msg}"),
+            Error::InvalidSpan(error) => write!(f, "{error}"),
+            Error::ShaderCompilation(msg) => write!(f, "Failed to compile GLSL:\n{msg}"),
+            Error::Io(path, error) => {
+                write!(f, "Failed to read file '{}': {error}", path.to_string_lossy())
+            }
+            Error::ResourceInUse(value) => {
+                write!(f, "Resource is still in use by the GPU until timeline value {}", value.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Vulkan(result) | Error::Allocation(result) => Some(result),
+            Error::Loading(error) => Some(error),
+            Error::WindowHandle(error) => Some(error),
+            Error::MissingParams(error) => Some(error),
+            Error::InvalidSpan(error) => Some(error),
+            Error::Io(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<vk::Result> for Error {
+    fn from(result: vk::Result) -> Self {
+        Error::Vulkan(result)
+    }
+}
+
+impl From<ash::LoadingError> for Error {
+    fn from(error: ash::LoadingError) -> Self {
+        Error::Loading(error)
+    }
+}
+
+impl From<HandleError> for Error {
+    fn from(error: HandleError) -> Self {
+        Error::WindowHandle(error)
+    }
+}
+
+impl From<MissingParamsError> for Error {
+    fn from(error: MissingParamsError) -> Self {
+        Error::MissingParams(error)
+    }
+}
+
+impl From<SpanError<vk::DeviceSize>> for Error {
+    fn from(error: SpanError<vk::DeviceSize>) -> Self {
+        Error::InvalidSpan(error)
+    }
+}
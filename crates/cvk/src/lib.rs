@@ -1,10 +1,19 @@
+extern crate self as cvk;
 
 pub mod core;
+pub mod error;
+mod profiling;
 pub mod resource;
 pub mod sync;
 pub mod pipeline;
+#[cfg(feature = "serde")]
+pub mod serialize;
+
+#[cfg(test)]
+mod tests;
 
 pub use core::*;
+pub use error::{Error, Result};
 pub use resource::*;
 pub use sync::*;
 pub use pipeline::*;
\ No newline at end of file
@@ -1,3 +1,23 @@
-
+pub mod cache;
+pub mod compute;
+pub mod descriptor;
+pub mod gpu_struct;
+pub mod graphics;
+pub mod layout;
+pub mod permutation;
+pub mod reflection;
+pub mod rendering;
 pub mod shader;
-pub use shader::*;
\ No newline at end of file
+pub mod shader_debug;
+
+pub use cache::*;
+pub use compute::*;
+pub use descriptor::*;
+pub use gpu_struct::*;
+pub use graphics::*;
+pub use layout::*;
+pub use permutation::*;
+pub use reflection::*;
+pub use rendering::*;
+pub use shader::*;
+pub use shader_debug::*;
@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::{fs, io};
+
+use ash::vk;
+
+use crate::Context;
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct PipelineCache {
+    handle: vk::PipelineCache,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|error| panic!("Failed to create pipeline cache: {error}"))
+    }
+
+    pub fn try_new() -> crate::Result<Self> {
+        Self::try_from_data(&[])
+    }
+
+    pub fn from_data(data: &[u8]) -> Self {
+        Self::try_from_data(data).unwrap_or_else(|error| panic!("Failed to create pipeline cache: {error}"))
+    }
+
+    pub fn try_from_data(data: &[u8]) -> crate::Result<Self> {
+        let info = vk::PipelineCacheCreateInfo::default().initial_data(data);
+
+        let handle = unsafe { Context::cached_device().create_pipeline_cache(&info, None) }?;
+
+        Ok(Self { handle })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        Self::try_load(path)
+            .unwrap_or_else(|error| panic!("Failed to load pipeline cache from {}: {error}", path.display()))
+    }
+
+    /// Starts with an empty cache if the file does not exist yet. Data written by a different driver or
+    /// device is rejected by the driver's header check and also yields an empty cache.
+    pub fn try_load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+
+        match fs::read(path) {
+            Ok(data) => Self::try_from_data(&data),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::try_new(),
+            Err(error) => Err(crate::Error::Io(path.to_path_buf(), error)),
+        }
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        self.try_data()
+            .unwrap_or_else(|error| panic!("Failed to read pipeline cache data: {error}"))
+    }
+
+    pub fn try_data(&self) -> crate::Result<Vec<u8>> {
+        Ok(unsafe { Context::cached_device().get_pipeline_cache_data(self.handle) }?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        self.try_save(path)
+            .unwrap_or_else(|error| panic!("Failed to save pipeline cache to {}: {error}", path.display()))
+    }
+
+    pub fn try_save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+        let data = self.try_data()?;
+
+        fs::write(path, data).map_err(|error| crate::Error::Io(path.to_path_buf(), error))
+    }
+}
+
+impl Default for PipelineCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        unsafe {
+            Context::cached_device().destroy_pipeline_cache(self.handle, None);
+        }
+    }
+}
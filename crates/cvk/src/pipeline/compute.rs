@@ -0,0 +1,147 @@
+use ash::vk;
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
+
+use crate::profiling::profile_scope;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{Context, GpuUse, LayoutBinding, PipelineCache, PipelineLayout, PushConstantRange, Recording, Shader, ShaderReflection, ShaderStage, VkHandle};
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct ComputePipeline {
+    handle: vk::Pipeline,
+    layout: Shared<PipelineLayout>,
+    gpu_use: GpuUse,
+}
+
+impl ComputePipeline {
+    #[inline]
+    pub fn layout(&self) -> &Shared<PipelineLayout> {
+        &self.layout
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+}
+
+impl Drop for ComputePipeline {
+    fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        untrack_allocation(ResourceKind::Pipeline, vk::Handle::as_raw(self.handle));
+        unsafe {
+            Context::cached_device().destroy_pipeline(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for ComputePipeline {
+    type Builder<'a> = ComputePipelineBuilder<'a>;
+}
+
+#[derive(utils::Paramters, Clone, Debug, Default)]
+#[param(getters, snapshot)]
+pub struct ComputePipelineBuilder<'a> {
+    #[required]
+    shader: Option<&'a Shader>,
+    layout: Option<Shared<PipelineLayout>>,
+    pipeline_cache: Option<Shared<PipelineCache>>,
+    #[vec(push_constant_range)]
+    push_constant_ranges: Vec<PushConstantRange>,
+    #[vec(binding_override)]
+    binding_overrides: Vec<LayoutBinding>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl TryBuild for ComputePipelineBuilder<'_> {
+    type Target = ComputePipeline;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_compute_pipeline");
+
+        self.check_required()?;
+
+        let shader = self
+            .shader
+            .ok_or(crate::Error::InvalidParams("Compute pipeline needs a shader"))?;
+
+        if shader.stage() != ShaderStage::COMPUTE {
+            return Err(crate::Error::InvalidParams("Compute pipeline needs a shader with stage COMPUTE"));
+        }
+
+        if self.layout.is_some() && !self.push_constant_ranges.is_empty() {
+            return Err(crate::Error::InvalidParams(
+                "Push constant ranges need to be declared on the pipeline layout when a layout is given",
+            ));
+        }
+
+        if self.layout.is_some() && !self.binding_overrides.is_empty() {
+            return Err(crate::Error::InvalidParams(
+                "Binding overrides need to be declared on the pipeline layout when a layout is given",
+            ));
+        }
+
+        let layout = match self.layout {
+            Some(ref layout) => layout.clone(),
+            None => {
+                let mut reflection = ShaderReflection::merge([shader.reflection()])?;
+                reflection.apply_overrides(&self.binding_overrides, &self.push_constant_ranges);
+
+                Shared::new(PipelineLayout::try_from_reflection(&reflection, &self.push_constant_ranges)?)
+            }
+        };
+
+        let stage = vk::PipelineShaderStageCreateInfo::default()
+            .stage(ShaderStage::COMPUTE)
+            .module(shader.handle())
+            .name(c"main");
+
+        let pipeline_info = vk::ComputePipelineCreateInfo::default()
+            .stage(stage)
+            .layout(layout.handle());
+
+        let cache = self
+            .pipeline_cache
+            .as_ref()
+            .map_or(vk::PipelineCache::null(), |cache| cache.handle());
+
+        let handle = unsafe {
+            Context::cached_device().create_compute_pipelines(cache, &[pipeline_info], None)
+        }
+        .map_err(|(_, result)| crate::Error::Vulkan(result))?[0];
+
+        track_allocation(LiveAllocation::new(ResourceKind::Pipeline, vk::Handle::as_raw(handle), 0, None));
+
+        Ok(ComputePipeline {
+            handle,
+            layout,
+            gpu_use: GpuUse::default(),
+        })
+    }
+}
+
+// --------------------- Compute commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn bind_compute_pipeline(&mut self, pipeline: &'a ComputePipeline) {
+        self.track(&pipeline.gpu_use);
+
+        unsafe {
+            Context::cached_device().cmd_bind_pipeline(
+                self.handle(),
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.handle,
+            );
+        }
+
+        self.set_bound_layout(pipeline.layout.handle());
+    }
+
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        unsafe {
+            Context::cached_device().cmd_dispatch(self.handle(), x, y, z);
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use ash::vk;
+use parking_lot::Mutex;
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
+
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{
+    BufferRegion, BufferRegionLike, Context, GpuUse, ImageLayout, ImageView, PipelineLayout, Recording, Sampler, ShaderStage,
+    VkHandle,
+};
+
+pub use vk::{DescriptorType, PipelineBindPoint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DescriptorBinding {
+    pub binding: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    pub ty: DescriptorType,
+    pub count: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    pub stages: ShaderStage,
+}
+
+impl DescriptorBinding {
+    #[inline]
+    pub const fn new(binding: u32, ty: DescriptorType, stages: ShaderStage) -> Self {
+        Self {
+            binding,
+            ty,
+            count: 1,
+            stages,
+        }
+    }
+
+    #[inline]
+    pub const fn with_count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    fn to_vk(self) -> vk::DescriptorSetLayoutBinding<'static> {
+        vk::DescriptorSetLayoutBinding::default()
+            .binding(self.binding)
+            .descriptor_type(self.ty)
+            .descriptor_count(self.count)
+            .stage_flags(self.stages)
+    }
+}
+
+// --------------------- Descriptor set layout ---------------------
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct DescriptorSetLayout {
+    handle: vk::DescriptorSetLayout,
+    bindings: Vec<DescriptorBinding>,
+}
+
+impl DescriptorSetLayout {
+    #[inline]
+    pub fn bindings(&self) -> &[DescriptorBinding] {
+        &self.bindings
+    }
+
+    pub fn binding(&self, binding: u32) -> Option<&DescriptorBinding> {
+        self.bindings.iter().find(|desc| desc.binding == binding)
+    }
+}
+
+impl Drop for DescriptorSetLayout {
+    fn drop(&mut self) {
+        unsafe {
+            Context::cached_device().destroy_descriptor_set_layout(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for DescriptorSetLayout {
+    type Builder<'a> = DescriptorSetLayoutBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug, Default)]
+#[param(getters, snapshot)]
+pub struct DescriptorSetLayoutBuilder {
+    #[vec(binding)]
+    bindings: Vec<DescriptorBinding>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl TryBuild for DescriptorSetLayoutBuilder {
+    type Target = DescriptorSetLayout;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if self.bindings[..i].iter().any(|other| other.binding == binding.binding) {
+                return Err(crate::Error::InvalidParams("Descriptor set layout has duplicate bindings"));
+            }
+        }
+
+        let bindings = self.bindings.iter().map(|binding| binding.to_vk()).collect::<Vec<_>>();
+
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+
+        let handle = unsafe { Context::cached_device().create_descriptor_set_layout(&layout_info, None) }?;
+
+        Ok(DescriptorSetLayout {
+            handle,
+            bindings: self.bindings.clone(),
+        })
+    }
+}
+
+// --------------------- Descriptor pool ---------------------
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct DescriptorPool {
+    handle: vk::DescriptorPool,
+    free_sets: bool,
+    lock: Mutex<()>,
+}
+
+impl DescriptorPool {
+    pub fn allocate<'a>(self: &Shared<Self>, layout: &DescriptorSetLayout) -> DescriptorSet<'a> {
+        self.try_allocate(layout)
+            .unwrap_or_else(|error| panic!("Failed to allocate descriptor set: {error}"))
+    }
+
+    pub fn try_allocate<'a>(self: &Shared<Self>, layout: &DescriptorSetLayout) -> crate::Result<DescriptorSet<'a>> {
+        let set_layouts = [layout.handle];
+
+        let alloc_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(self.handle)
+            .set_layouts(&set_layouts);
+
+        let handle = {
+            let _lock = self.lock.lock();
+            unsafe { Context::cached_device().allocate_descriptor_sets(&alloc_info) }?[0]
+        };
+
+        track_allocation(LiveAllocation::new(ResourceKind::DescriptorSet, vk::Handle::as_raw(handle), 0, None));
+
+        Ok(DescriptorSet {
+            handle,
+            pool: self.clone(),
+            bindings: layout.bindings.clone(),
+            resources: vec![],
+            gpu_use: GpuUse::default(),
+        })
+    }
+}
+
+impl Drop for DescriptorPool {
+    fn drop(&mut self) {
+        unsafe {
+            Context::cached_device().destroy_descriptor_pool(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for DescriptorPool {
+    type Builder<'a> = DescriptorPoolBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug, Default)]
+#[param(getters, snapshot)]
+pub struct DescriptorPoolBuilder {
+    #[required]
+    max_sets: u32,
+    #[no_param]
+    pool_sizes: Vec<vk::DescriptorPoolSize>,
+    free_sets: bool,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl DescriptorPoolBuilder {
+    pub fn pool_size(mut self, ty: DescriptorType, count: u32) -> Self {
+        match self.pool_sizes.iter_mut().find(|size| size.ty == ty) {
+            Some(size) => size.descriptor_count += count,
+            None => self.pool_sizes.push(vk::DescriptorPoolSize { ty, descriptor_count: count }),
+        }
+        self
+    }
+
+    pub fn for_layout(mut self, layout: &DescriptorSetLayout, sets: u32) -> Self {
+        for binding in layout.bindings() {
+            self = self.pool_size(binding.ty, binding.count * sets);
+        }
+        self
+    }
+}
+
+impl TryBuild for DescriptorPoolBuilder {
+    type Target = DescriptorPool;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        if self.pool_sizes.is_empty() {
+            return Err(crate::Error::InvalidParams("Descriptor pool needs at least one pool size"));
+        }
+
+        let flags = if self.free_sets {
+            vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET
+        } else {
+            vk::DescriptorPoolCreateFlags::empty()
+        };
+
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .flags(flags)
+            .max_sets(self.max_sets)
+            .pool_sizes(&self.pool_sizes);
+
+        let handle = unsafe { Context::cached_device().create_descriptor_pool(&pool_info, None) }?;
+
+        Ok(DescriptorPool {
+            handle,
+            free_sets: self.free_sets,
+            lock: Mutex::new(()),
+        })
+    }
+}
+
+// --------------------- Descriptor set ---------------------
+
+#[derive(Clone, Copy, Debug)]
+pub enum DescriptorWrite<'a> {
+    Buffer {
+        binding: u32,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+        gpu_use: &'a GpuUse,
+    },
+    CombinedImageSampler {
+        binding: u32,
+        view: &'a ImageView,
+        sampler: &'a Sampler,
+    },
+    SampledImage {
+        binding: u32,
+        view: &'a ImageView,
+    },
+    StorageImage {
+        binding: u32,
+        view: &'a ImageView,
+    },
+}
+
+impl<'a> DescriptorWrite<'a> {
+    pub fn buffer<T: Copy + 'a>(binding: u32, region: impl Into<BufferRegion<'a, T>>) -> Self {
+        let region = region.into();
+
+        Self::Buffer {
+            binding,
+            buffer: region.buffer(),
+            offset: region.offset() * size_of::<T>() as vk::DeviceSize,
+            range: region.size(),
+            gpu_use: region.parent().gpu_use(),
+        }
+    }
+
+    pub fn image(binding: u32, view: &'a ImageView, sampler: &'a Sampler) -> Self {
+        Self::CombinedImageSampler { binding, view, sampler }
+    }
+
+    pub fn sampled_image(binding: u32, view: &'a ImageView) -> Self {
+        Self::SampledImage { binding, view }
+    }
+
+    pub fn storage_image(binding: u32, view: &'a ImageView) -> Self {
+        Self::StorageImage { binding, view }
+    }
+
+    #[inline]
+    pub const fn binding(&self) -> u32 {
+        match *self {
+            Self::Buffer { binding, .. }
+            | Self::CombinedImageSampler { binding, .. }
+            | Self::SampledImage { binding, .. }
+            | Self::StorageImage { binding, .. } => binding,
+        }
+    }
+
+    /// The usage of the buffer or image behind the descriptor.
+    pub fn gpu_use(&self) -> &'a GpuUse {
+        match *self {
+            Self::Buffer { gpu_use, .. } => gpu_use,
+            Self::CombinedImageSampler { view, .. } | Self::SampledImage { view, .. } | Self::StorageImage { view, .. } => {
+                view.image().gpu_use()
+            }
+        }
+    }
+
+    fn accepts(&self, ty: DescriptorType) -> bool {
+        match self {
+            Self::Buffer { .. } => matches!(
+                ty,
+                DescriptorType::UNIFORM_BUFFER
+                    | DescriptorType::STORAGE_BUFFER
+                    | DescriptorType::UNIFORM_BUFFER_DYNAMIC
+                    | DescriptorType::STORAGE_BUFFER_DYNAMIC
+            ),
+            Self::CombinedImageSampler { .. } => ty == DescriptorType::COMBINED_IMAGE_SAMPLER,
+            Self::SampledImage { .. } => ty == DescriptorType::SAMPLED_IMAGE,
+            Self::StorageImage { .. } => ty == DescriptorType::STORAGE_IMAGE,
+        }
+    }
+}
+
+/// Borrows every buffer, view and sampler written to it, so none of them can be dropped while the set is
+/// alive. Binding the set tracks it together with the written resources.
+#[derive(cvk_macros::VkHandle, Debug)]
+pub struct DescriptorSet<'a> {
+    handle: vk::DescriptorSet,
+    pool: Shared<DescriptorPool>,
+    bindings: Vec<DescriptorBinding>,
+    resources: Vec<DescriptorWrite<'a>>,
+    gpu_use: GpuUse,
+}
+
+impl<'a> DescriptorSet<'a> {
+    #[inline]
+    pub fn pool(&self) -> &Shared<DescriptorPool> {
+        &self.pool
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+
+    pub fn update(&mut self, writes: &[DescriptorWrite<'a>]) {
+        self.try_update(writes)
+            .unwrap_or_else(|error| panic!("Failed to update descriptor set: {error}"))
+    }
+
+    /// Fails with [`ResourceInUse`](crate::Error::ResourceInUse) while a submitted recording still uses the set.
+    pub fn try_update(&mut self, writes: &[DescriptorWrite<'a>]) -> crate::Result<()> {
+        self.gpu_use.check_idle()?;
+
+        let mut buffer_infos = Vec::with_capacity(writes.len());
+        let mut image_infos = Vec::with_capacity(writes.len());
+        let mut types = Vec::with_capacity(writes.len());
+
+        for write in writes {
+            let binding = self
+                .bindings
+                .iter()
+                .find(|binding| binding.binding == write.binding())
+                .ok_or(crate::Error::InvalidParams("Descriptor write targets a binding missing from the layout"))?;
+
+            if !write.accepts(binding.ty) {
+                return Err(crate::Error::InvalidParams(
+                    "Descriptor write does not match the descriptor type of its binding",
+                ));
+            }
+
+            types.push(binding.ty);
+
+            match *write {
+                DescriptorWrite::Buffer { buffer, offset, range, .. } => {
+                    buffer_infos.push(vk::DescriptorBufferInfo { buffer, offset, range });
+                }
+                DescriptorWrite::CombinedImageSampler { view, sampler, .. } => {
+                    image_infos.push(vk::DescriptorImageInfo {
+                        sampler: sampler.handle(),
+                        image_view: view.handle(),
+                        image_layout: ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    });
+                }
+                DescriptorWrite::SampledImage { view, .. } => {
+                    image_infos.push(vk::DescriptorImageInfo {
+                        sampler: vk::Sampler::null(),
+                        image_view: view.handle(),
+                        image_layout: ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    });
+                }
+                DescriptorWrite::StorageImage { view, .. } => {
+                    image_infos.push(vk::DescriptorImageInfo {
+                        sampler: vk::Sampler::null(),
+                        image_view: view.handle(),
+                        image_layout: ImageLayout::GENERAL,
+                    });
+                }
+            }
+        }
+
+        let (mut buffer_index, mut image_index) = (0, 0);
+
+        let vk_writes = writes
+            .iter()
+            .zip(types)
+            .map(|(write, ty)| {
+                let vk_write = vk::WriteDescriptorSet::default()
+                    .dst_set(self.handle)
+                    .dst_binding(write.binding())
+                    .descriptor_type(ty);
+
+                match write {
+                    DescriptorWrite::Buffer { .. } => {
+                        buffer_index += 1;
+                        vk_write.buffer_info(&buffer_infos[buffer_index - 1..buffer_index])
+                    }
+                    _ => {
+                        image_index += 1;
+                        vk_write.image_info(&image_infos[image_index - 1..image_index])
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        unsafe { Context::cached_device().update_descriptor_sets(&vk_writes, &[]) };
+
+        for write in writes {
+            self.resources.retain(|resource| resource.binding() != write.binding());
+            self.resources.push(*write);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for DescriptorSet<'_> {
+    fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        untrack_allocation(ResourceKind::DescriptorSet, vk::Handle::as_raw(self.handle));
+        if self.pool.free_sets {
+            let _lock = self.pool.lock.lock();
+            let _ = unsafe { Context::cached_device().free_descriptor_sets(self.pool.handle, &[self.handle]) };
+        }
+    }
+}
+
+impl<'b> Buildable for DescriptorSet<'b> {
+    type Builder<'a> = DescriptorSetBuilder<'b> where Self: 'a;
+}
+
+#[derive(utils::Paramters, Clone, Debug, Default)]
+#[param(getters, snapshot)]
+pub struct DescriptorSetBuilder<'a> {
+    #[required]
+    pool: Option<Shared<DescriptorPool>>,
+    #[required]
+    layout: Option<&'a DescriptorSetLayout>,
+    #[vec(write)]
+    writes: Vec<DescriptorWrite<'a>>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl<'a> DescriptorSetBuilder<'a> {
+    pub fn buffer<T: Copy + 'a>(self, binding: u32, region: impl Into<BufferRegion<'a, T>>) -> Self {
+        self.write(DescriptorWrite::buffer(binding, region))
+    }
+
+    pub fn image(self, binding: u32, view: &'a ImageView, sampler: &'a Sampler) -> Self {
+        self.write(DescriptorWrite::image(binding, view, sampler))
+    }
+
+    pub fn sampled_image(self, binding: u32, view: &'a ImageView) -> Self {
+        self.write(DescriptorWrite::sampled_image(binding, view))
+    }
+
+    pub fn storage_image(self, binding: u32, view: &'a ImageView) -> Self {
+        self.write(DescriptorWrite::storage_image(binding, view))
+    }
+}
+
+impl<'a> TryBuild for DescriptorSetBuilder<'a> {
+    type Target = DescriptorSet<'a>;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        let (Some(pool), Some(layout)) = (&self.pool, self.layout) else {
+            return Err(crate::Error::InvalidParams("Descriptor set needs a pool and a layout"));
+        };
+
+        let mut set = pool.try_allocate(layout)?;
+        set.try_update(&self.writes)?;
+
+        Ok(set)
+    }
+}
+
+// --------------------- Descriptor commands ---------------------
+
+impl<'a> Recording<'a> {
+    /// Tracks every set and the buffers and images written to it.
+    pub fn bind_descriptor_sets(
+        &mut self,
+        bind_point: PipelineBindPoint,
+        layout: &'a PipelineLayout,
+        first_set: u32,
+        sets: &[&'a DescriptorSet<'a>],
+    ) {
+        let handles = sets.iter().map(|set| set.handle).collect::<Vec<_>>();
+
+        unsafe {
+            Context::cached_device().cmd_bind_descriptor_sets(
+                self.handle(),
+                bind_point,
+                layout.handle(),
+                first_set,
+                &handles,
+                &[],
+            );
+        }
+
+        for set in sets {
+            self.track(&set.gpu_use);
+            for resource in &set.resources {
+                self.track(resource.gpu_use());
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+pub use cvk_macros::GpuStruct;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GpuLayout {
+    Std140,
+    Std430,
+}
+
+impl GpuLayout {
+    pub fn glsl_qualifier(&self) -> &'static str {
+        match self {
+            GpuLayout::Std140 => "std140",
+            GpuLayout::Std430 => "std430",
+        }
+    }
+}
+
+pub trait GpuStruct: Copy + 'static {
+    const LAYOUT: GpuLayout;
+    const SIZE: usize;
+    const GLSL_NAME: &'static str;
+    const GLSL: &'static str;
+}
@@ -0,0 +1,514 @@
+use ash::vk::{self, Format};
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
+
+use crate::profiling::profile_scope;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{BufferRegion, BufferRegionLike, Context, GpuUse, LayoutBinding, PipelineCache, PipelineLayout, PushConstantRange, Recording, Shader, ShaderReflection, ShaderStage, VkHandle};
+
+pub use vk::{
+    CompareOp, CullModeFlags as CullMode, DynamicState, FrontFace, PolygonMode, PrimitiveTopology, Rect2D,
+    SampleCountFlags as SampleCount, StencilOpState, VertexInputRate, Viewport,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexBinding {
+    pub binding: u32,
+    pub stride: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    pub input_rate: VertexInputRate,
+}
+
+impl VertexBinding {
+    #[inline]
+    pub const fn per_vertex<V>(binding: u32) -> Self {
+        Self {
+            binding,
+            stride: size_of::<V>() as u32,
+            input_rate: VertexInputRate::VERTEX,
+        }
+    }
+
+    #[inline]
+    pub const fn per_instance<V>(binding: u32) -> Self {
+        Self {
+            binding,
+            stride: size_of::<V>() as u32,
+            input_rate: VertexInputRate::INSTANCE,
+        }
+    }
+
+    fn to_vk(self) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding: self.binding,
+            stride: self.stride,
+            input_rate: self.input_rate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub binding: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    pub format: Format,
+    pub offset: u32,
+}
+
+impl VertexAttribute {
+    #[inline]
+    pub const fn new(location: u32, binding: u32, format: Format, offset: u32) -> Self {
+        Self {
+            location,
+            binding,
+            format,
+            offset,
+        }
+    }
+
+    fn to_vk(self) -> vk::VertexInputAttributeDescription {
+        vk::VertexInputAttributeDescription {
+            location: self.location,
+            binding: self.binding,
+            format: self.format,
+            offset: self.offset,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ColorBlend {
+    #[default]
+    Disabled,
+    Alpha,
+    PremultipliedAlpha,
+    Additive,
+    Custom(vk::PipelineColorBlendAttachmentState),
+}
+
+impl ColorBlend {
+    fn to_vk(self) -> vk::PipelineColorBlendAttachmentState {
+        let blend = |src_color, dst_color, src_alpha, dst_alpha| {
+            vk::PipelineColorBlendAttachmentState::default()
+                .blend_enable(true)
+                .src_color_blend_factor(src_color)
+                .dst_color_blend_factor(dst_color)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(src_alpha)
+                .dst_alpha_blend_factor(dst_alpha)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .color_write_mask(vk::ColorComponentFlags::RGBA)
+        };
+
+        match self {
+            ColorBlend::Disabled => vk::PipelineColorBlendAttachmentState::default()
+                .blend_enable(false)
+                .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ColorBlend::Alpha => blend(
+                vk::BlendFactor::SRC_ALPHA,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            ColorBlend::PremultipliedAlpha => blend(
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            ColorBlend::Additive => blend(
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+            ),
+            ColorBlend::Custom(state) => state,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthBias {
+    pub constant_factor: f32,
+    pub clamp: f32,
+    pub slope_factor: f32,
+}
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct GraphicsPipeline {
+    handle: vk::Pipeline,
+    layout: Shared<PipelineLayout>,
+    gpu_use: GpuUse,
+}
+
+impl GraphicsPipeline {
+    #[inline]
+    pub fn layout(&self) -> &Shared<PipelineLayout> {
+        &self.layout
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+}
+
+impl Drop for GraphicsPipeline {
+    fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        untrack_allocation(ResourceKind::Pipeline, vk::Handle::as_raw(self.handle));
+        unsafe {
+            Context::cached_device().destroy_pipeline(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for GraphicsPipeline {
+    type Builder<'a> = GraphicsPipelineBuilder<'a>;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+#[param(getters, snapshot)]
+pub struct GraphicsPipelineBuilder<'a> {
+    #[required]
+    #[vec(stage)]
+    stages: Vec<&'a Shader>,
+    #[vec(vertex_binding)]
+    vertex_bindings: Vec<VertexBinding>,
+    #[vec(vertex_attribute)]
+    vertex_attributes: Vec<VertexAttribute>,
+    #[param(default = PrimitiveTopology::TRIANGLE_LIST)]
+    topology: PrimitiveTopology,
+    primitive_restart: bool,
+
+    #[param(default = PolygonMode::FILL)]
+    polygon_mode: PolygonMode,
+    #[flag]
+    #[param(default = CullMode::NONE)]
+    cull_mode: CullMode,
+    #[param(default = FrontFace::COUNTER_CLOCKWISE)]
+    front_face: FrontFace,
+    #[param(default = 1.0)]
+    line_width: f32,
+    depth_clamp: bool,
+    depth_bias: Option<DepthBias>,
+    #[param(default = SampleCount::TYPE_1)]
+    samples: SampleCount,
+
+    depth_test: bool,
+    depth_write: bool,
+    #[param(default = CompareOp::LESS)]
+    depth_compare_op: CompareOp,
+    stencil: Option<StencilOpState>,
+
+    #[vec(color_blend)]
+    color_blends: Vec<ColorBlend>,
+    #[vec(dynamic_state)]
+    dynamic_states: Vec<DynamicState>,
+
+    #[vec(color_format)]
+    color_formats: Vec<Format>,
+    depth_format: Option<Format>,
+    stencil_format: Option<Format>,
+    render_pass: Option<vk::RenderPass>,
+    subpass: u32,
+
+    layout: Option<Shared<PipelineLayout>>,
+    pipeline_cache: Option<Shared<PipelineCache>>,
+    #[vec(push_constant_range)]
+    push_constant_ranges: Vec<PushConstantRange>,
+    #[vec(binding_override)]
+    binding_overrides: Vec<LayoutBinding>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl TryBuild for GraphicsPipelineBuilder<'_> {
+    type Target = GraphicsPipeline;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_graphics_pipeline");
+
+        self.check_required()?;
+
+        if !self.stages.iter().any(|shader| shader.stage().contains(ShaderStage::VERTEX)) {
+            return Err(crate::Error::InvalidParams("Graphics pipeline needs a vertex shader"));
+        }
+
+        if self.stages.iter().any(|shader| shader.stage().contains(ShaderStage::COMPUTE)) {
+            return Err(crate::Error::InvalidParams("Graphics pipeline cannot use a compute shader"));
+        }
+
+        if self.render_pass.is_none() && !Context::try_get()?.has_dynamic_rendering() {
+            return Err(crate::Error::Unsupported(String::from(
+                "Graphics pipeline without a render pass needs dynamic rendering support",
+            )));
+        }
+
+        if self.layout.is_some() && !self.push_constant_ranges.is_empty() {
+            return Err(crate::Error::InvalidParams(
+                "Push constant ranges need to be declared on the pipeline layout when a layout is given",
+            ));
+        }
+
+        if self.layout.is_some() && !self.binding_overrides.is_empty() {
+            return Err(crate::Error::InvalidParams(
+                "Binding overrides need to be declared on the pipeline layout when a layout is given",
+            ));
+        }
+
+        let layout = match self.layout {
+            Some(ref layout) => layout.clone(),
+            None => {
+                let mut reflection = ShaderReflection::merge(self.stages.iter().map(|shader| shader.reflection()))?;
+                reflection.apply_overrides(&self.binding_overrides, &self.push_constant_ranges);
+
+                Shared::new(PipelineLayout::try_from_reflection(&reflection, &self.push_constant_ranges)?)
+            }
+        };
+
+        let stages = self
+            .stages
+            .iter()
+            .map(|shader| {
+                vk::PipelineShaderStageCreateInfo::default()
+                    .stage(shader.stage())
+                    .module(shader.handle())
+                    .name(c"main")
+            })
+            .collect::<Vec<_>>();
+
+        let vertex_bindings = self.vertex_bindings.iter().map(|binding| binding.to_vk()).collect::<Vec<_>>();
+        let vertex_attributes = self
+            .vertex_attributes
+            .iter()
+            .map(|attribute| attribute.to_vk())
+            .collect::<Vec<_>>();
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&vertex_bindings)
+            .vertex_attribute_descriptions(&vertex_attributes);
+
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(self.topology)
+            .primitive_restart_enable(self.primitive_restart);
+
+        let viewport = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+
+        let depth_bias = self.depth_bias.unwrap_or_default();
+
+        let rasterization = vk::PipelineRasterizationStateCreateInfo::default()
+            .depth_clamp_enable(self.depth_clamp)
+            .polygon_mode(self.polygon_mode)
+            .cull_mode(self.cull_mode)
+            .front_face(self.front_face)
+            .line_width(self.line_width)
+            .depth_bias_enable(self.depth_bias.is_some())
+            .depth_bias_constant_factor(depth_bias.constant_factor)
+            .depth_bias_clamp(depth_bias.clamp)
+            .depth_bias_slope_factor(depth_bias.slope_factor);
+
+        let multisample = vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(self.samples);
+
+        let stencil = self.stencil.unwrap_or_default();
+
+        let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::default()
+            .depth_test_enable(self.depth_test)
+            .depth_write_enable(self.depth_write)
+            .depth_compare_op(self.depth_compare_op)
+            .stencil_test_enable(self.stencil.is_some())
+            .front(stencil)
+            .back(stencil);
+
+        let attachment_count = match self.render_pass {
+            Some(_) => self.color_blends.len(),
+            None => self.color_formats.len(),
+        };
+
+        let blend_attachments = (0..attachment_count)
+            .map(|i| self.color_blends.get(i).copied().unwrap_or_default().to_vk())
+            .collect::<Vec<_>>();
+
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default().attachments(&blend_attachments);
+
+        let mut dynamic_states = vec![DynamicState::VIEWPORT, DynamicState::SCISSOR];
+        for &state in &self.dynamic_states {
+            if !dynamic_states.contains(&state) {
+                dynamic_states.push(state);
+            }
+        }
+
+        let dynamic = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let mut rendering = vk::PipelineRenderingCreateInfo::default()
+            .color_attachment_formats(&self.color_formats)
+            .depth_attachment_format(self.depth_format.unwrap_or(Format::UNDEFINED))
+            .stencil_attachment_format(self.stencil_format.unwrap_or(Format::UNDEFINED));
+
+        let mut pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport)
+            .rasterization_state(&rasterization)
+            .multisample_state(&multisample)
+            .depth_stencil_state(&depth_stencil)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic)
+            .layout(layout.handle());
+
+        pipeline_info = match self.render_pass {
+            Some(render_pass) => pipeline_info.render_pass(render_pass).subpass(self.subpass),
+            None => pipeline_info.push_next(&mut rendering),
+        };
+
+        let cache = self
+            .pipeline_cache
+            .as_ref()
+            .map_or(vk::PipelineCache::null(), |cache| cache.handle());
+
+        let handle = unsafe {
+            Context::cached_device().create_graphics_pipelines(cache, &[pipeline_info], None)
+        }
+        .map_err(|(_, result)| crate::Error::Vulkan(result))?[0];
+
+        track_allocation(LiveAllocation::new(ResourceKind::Pipeline, vk::Handle::as_raw(handle), 0, None));
+
+        Ok(GraphicsPipeline {
+            handle,
+            layout,
+            gpu_use: GpuUse::default(),
+        })
+    }
+}
+
+pub trait IndexType: Copy {
+    const INDEX_TYPE: vk::IndexType;
+}
+
+impl IndexType for u16 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+}
+
+impl IndexType for u32 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT32;
+}
+
+/// Borrows the buffer of its region while it is bound, and the buffer waits for the submission before it is destroyed.
+#[derive(Clone, Copy, Debug)]
+pub struct VertexBuffer<'a> {
+    buffer: vk::Buffer,
+    offset: vk::DeviceSize,
+    gpu_use: &'a GpuUse,
+}
+
+impl<'a> VertexBuffer<'a> {
+    pub fn new<T: Copy + 'a>(region: impl Into<BufferRegion<'a, T>>) -> Self {
+        let region = region.into();
+
+        Self {
+            buffer: region.buffer(),
+            offset: region.offset() * size_of::<T>() as vk::DeviceSize,
+            gpu_use: region.parent().gpu_use(),
+        }
+    }
+}
+
+// --------------------- Graphics commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn bind_pipeline(&mut self, pipeline: &'a GraphicsPipeline) {
+        self.track(&pipeline.gpu_use);
+
+        unsafe {
+            Context::cached_device().cmd_bind_pipeline(
+                self.handle(),
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.handle,
+            );
+        }
+
+        self.set_bound_layout(pipeline.layout.handle());
+    }
+
+    pub fn bind_vertex_buffer<T: Copy + 'a>(&mut self, binding: u32, region: impl Into<BufferRegion<'a, T>>) {
+        self.bind_vertex_buffers(binding, &[VertexBuffer::new(region)]);
+    }
+
+    pub fn bind_vertex_buffers(&mut self, first_binding: u32, buffers: &[VertexBuffer<'a>]) {
+        for buffer in buffers {
+            self.track(buffer.gpu_use);
+        }
+
+        let handles = buffers.iter().map(|buffer| buffer.buffer).collect::<Vec<_>>();
+        let offsets = buffers.iter().map(|buffer| buffer.offset).collect::<Vec<_>>();
+
+        unsafe {
+            Context::cached_device().cmd_bind_vertex_buffers(self.handle(), first_binding, &handles, &offsets);
+        }
+    }
+
+    pub fn bind_index_buffer<I: IndexType>(&mut self, region: impl BufferRegionLike<I> + 'a) {
+        self.track(region.gpu_use());
+
+        unsafe {
+            Context::cached_device().cmd_bind_index_buffer(
+                self.handle(),
+                region.buffer(),
+                region.offset() * size_of::<I>() as vk::DeviceSize,
+                I::INDEX_TYPE,
+            );
+        }
+    }
+
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        unsafe {
+            Context::cached_device().cmd_draw(
+                self.handle(),
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            );
+        }
+    }
+
+    pub fn draw_indexed(
+        &mut self,
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) {
+        unsafe {
+            Context::cached_device().cmd_draw_indexed(
+                self.handle(),
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            );
+        }
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        unsafe {
+            Context::cached_device().cmd_set_viewport(self.handle(), 0, &[viewport]);
+        }
+    }
+
+    pub fn set_scissor(&mut self, scissor: Rect2D) {
+        unsafe {
+            Context::cached_device().cmd_set_scissor(self.handle(), 0, &[scissor]);
+        }
+    }
+}
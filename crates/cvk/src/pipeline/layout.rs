@@ -0,0 +1,170 @@
+use ash::vk;
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
+
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{Context, DescriptorSetLayout, Recording, ShaderReflection, ShaderStage, VkHandle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PushConstantRange {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    pub stages: ShaderStage,
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl PushConstantRange {
+    #[inline]
+    pub const fn new(stages: ShaderStage, offset: u32, size: u32) -> Self {
+        Self { stages, offset, size }
+    }
+
+    #[inline]
+    pub const fn of<T: Copy>(stages: ShaderStage, offset: u32) -> Self {
+        Self::new(stages, offset, size_of::<T>() as u32)
+    }
+
+    fn to_vk(self) -> vk::PushConstantRange {
+        vk::PushConstantRange {
+            stage_flags: self.stages,
+            offset: self.offset,
+            size: self.size,
+        }
+    }
+}
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct PipelineLayout {
+    handle: vk::PipelineLayout,
+    push_constant_ranges: Vec<PushConstantRange>,
+    set_layouts: Vec<Shared<DescriptorSetLayout>>,
+}
+
+impl PipelineLayout {
+    #[inline]
+    pub fn push_constant_ranges(&self) -> &[PushConstantRange] {
+        &self.push_constant_ranges
+    }
+
+    /// Set layouts owned by this layout, only filled when it was derived from shader reflection.
+    #[inline]
+    pub fn set_layouts(&self) -> &[Shared<DescriptorSetLayout>] {
+        &self.set_layouts
+    }
+
+    #[inline]
+    pub fn set_layout(&self, set: u32) -> Option<&Shared<DescriptorSetLayout>> {
+        self.set_layouts.get(set as usize)
+    }
+
+    pub(crate) fn try_from_reflection(
+        reflection: &ShaderReflection,
+        push_constant_ranges: &[PushConstantRange],
+    ) -> crate::Result<Self> {
+        let set_count = reflection.bindings().iter().map(|binding| binding.set + 1).max().unwrap_or(0);
+
+        let set_layouts = (0..set_count)
+            .map(|set| {
+                DescriptorSetLayout::builder()
+                    .bindings(
+                        reflection
+                            .bindings()
+                            .iter()
+                            .filter(|binding| binding.set == set)
+                            .map(|binding| binding.binding)
+                            .collect::<Vec<_>>(),
+                    )
+                    .try_build_shared()
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let push_constant_ranges = match reflection.push_constants() {
+            Some(range) if push_constant_ranges.is_empty() => vec![range],
+            _ => push_constant_ranges.to_vec(),
+        };
+
+        let mut layout = PipelineLayout::builder()
+            .set_layouts(set_layouts.iter().map(|layout| &**layout).collect::<Vec<_>>())
+            .push_constant_ranges(push_constant_ranges)
+            .try_build()?;
+        layout.set_layouts = set_layouts;
+
+        Ok(layout)
+    }
+}
+
+impl Drop for PipelineLayout {
+    fn drop(&mut self) {
+        untrack_allocation(ResourceKind::PipelineLayout, vk::Handle::as_raw(self.handle));
+        unsafe {
+            Context::cached_device().destroy_pipeline_layout(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for PipelineLayout {
+    type Builder<'a> = PipelineLayoutBuilder<'a>;
+}
+
+#[derive(utils::Paramters, Clone, Debug, Default)]
+#[param(getters, snapshot)]
+pub struct PipelineLayoutBuilder<'a> {
+    #[vec(set_layout)]
+    set_layouts: Vec<&'a DescriptorSetLayout>,
+    #[vec(push_constant_range)]
+    push_constant_ranges: Vec<PushConstantRange>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl TryBuild for PipelineLayoutBuilder<'_> {
+    type Target = PipelineLayout;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        let set_layouts = self.set_layouts.iter().map(|layout| layout.handle()).collect::<Vec<_>>();
+
+        let push_constant_ranges = self
+            .push_constant_ranges
+            .iter()
+            .map(|range| range.to_vk())
+            .collect::<Vec<_>>();
+
+        let layout_info = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(&set_layouts)
+            .push_constant_ranges(&push_constant_ranges);
+
+        let handle = unsafe { Context::cached_device().create_pipeline_layout(&layout_info, None) }?;
+
+        track_allocation(LiveAllocation::new(ResourceKind::PipelineLayout, vk::Handle::as_raw(handle), 0, None));
+
+        Ok(PipelineLayout {
+            handle,
+            push_constant_ranges: self.push_constant_ranges.clone(),
+            set_layouts: vec![],
+        })
+    }
+}
+
+// --------------------- Push constant commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn push_constants<T: Copy>(&mut self, stages: ShaderStage, offset: u32, value: &T) {
+        let layout = self
+            .bound_layout()
+            .expect("Push constants need a bound pipeline to take the layout from");
+
+        self.push_constants_to(layout, stages, offset, value);
+    }
+
+    pub fn push_constants_to<T: Copy>(&mut self, layout: vk::PipelineLayout, stages: ShaderStage, offset: u32, value: &T) {
+        let bytes = unsafe { std::slice::from_raw_parts((value as *const T).cast::<u8>(), size_of::<T>()) };
+
+        unsafe {
+            Context::cached_device().cmd_push_constants(self.handle(), layout, stages, offset, bytes);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use std::{fmt, fs, io, thread};
+
+use utils::{Shared, TryBuild};
+
+use crate::{Shader, ShaderBuilder, ShaderDefine};
+
+pub struct ShaderPermutations<'a> {
+    base: ShaderBuilder<'a>,
+    variants: HashMap<Vec<ShaderDefine>, Shared<Shader>>,
+}
+
+impl<'a> ShaderPermutations<'a> {
+    pub fn new(base: ShaderBuilder<'a>) -> Self {
+        Self {
+            base,
+            variants: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    pub fn get(&mut self, defines: impl IntoIterator<Item = impl Into<ShaderDefine>>) -> crate::Result<Shared<Shader>> {
+        let mut key: Vec<ShaderDefine> = defines.into_iter().map(Into::into).collect();
+        key.sort();
+        key.dedup();
+
+        if let Some(shader) = self.variants.get(&key) {
+            return Ok(shader.clone());
+        }
+
+        let mut all_defines = self.base.get_defines().clone();
+        all_defines.extend(key.iter().cloned());
+
+        let shader = self.base.clone().defines(all_defines).try_build_shared()?;
+        self.variants.insert(key, shader.clone());
+
+        Ok(shader)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &[ShaderDefine]> {
+        self.variants.keys().map(Vec::as_slice)
+    }
+
+    pub fn warm_up<'k>(&mut self, keys: impl IntoIterator<Item = &'k [ShaderDefine]>) -> Vec<crate::Error> {
+        let keys = keys
+            .into_iter()
+            .map(<[ShaderDefine]>::to_vec)
+            .filter(|key| !self.variants.contains_key(key))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            return vec![];
+        }
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = keys.len().div_ceil(threads);
+        let base = &self.base;
+
+        let results = thread::scope(|scope| {
+            let workers = keys
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|key| {
+                                let mut all_defines = base.get_defines().clone();
+                                all_defines.extend(key.iter().cloned());
+
+                                (key.clone(), base.clone().defines(all_defines).try_build_shared())
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("Shader warmup thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        let mut errors = vec![];
+        for (key, result) in results {
+            match result {
+                Ok(shader) => {
+                    self.variants.insert(key, shader);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+
+        errors
+    }
+
+    pub fn clear(&mut self) {
+        self.variants.clear();
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PermutationWarmup {
+    entries: BTreeMap<String, BTreeSet<Vec<ShaderDefine>>>,
+}
+
+impl PermutationWarmup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(crate::Error::Io(path.to_path_buf(), error)),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_string()).map_err(|error| crate::Error::Io(path.to_path_buf(), error))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut warmup = Self::default();
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split('\t');
+            let Some(name) = fields.next() else {
+                continue;
+            };
+
+            let mut key = fields
+                .map(|define| match define.split_once('=') {
+                    Some((name, value)) => ShaderDefine::new(name, Some(value)),
+                    None => ShaderDefine::new(define, None::<String>),
+                })
+                .collect::<Vec<_>>();
+            key.sort();
+            key.dedup();
+
+            warmup.entries.entry(name.to_string()).or_default().insert(key);
+        }
+
+        warmup
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn record(&mut self, name: impl Into<String>, permutations: &ShaderPermutations) {
+        self.entries
+            .entry(name.into())
+            .or_default()
+            .extend(permutations.keys().map(<[ShaderDefine]>::to_vec));
+    }
+
+    pub fn keys(&self, name: &str) -> impl Iterator<Item = &[ShaderDefine]> {
+        self.entries.get(name).into_iter().flatten().map(Vec::as_slice)
+    }
+
+    pub fn warm_up(&self, name: &str, permutations: &mut ShaderPermutations) -> Vec<crate::Error> {
+        permutations.warm_up(self.keys(name))
+    }
+}
+
+impl fmt::Display for PermutationWarmup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, keys) in &self.entries {
+            for key in keys {
+                write!(f, "{name}")?;
+                for define in key {
+                    match define.value {
+                        Some(ref value) => write!(f, "\t{}={value}", define.name)?,
+                        None => write!(f, "\t{}", define.name)?,
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use crate::{DescriptorBinding, DescriptorType, PushConstantRange, ShaderStage};
+
+const MAGIC: u32 = 0x0723_0203;
+const HEADER_LEN: usize = 5;
+
+mod op {
+    pub const DECORATE: u32 = 71;
+    pub const MEMBER_DECORATE: u32 = 72;
+    pub const TYPE_INT: u32 = 21;
+    pub const TYPE_FLOAT: u32 = 22;
+    pub const TYPE_VECTOR: u32 = 23;
+    pub const TYPE_MATRIX: u32 = 24;
+    pub const TYPE_IMAGE: u32 = 25;
+    pub const TYPE_SAMPLER: u32 = 26;
+    pub const TYPE_SAMPLED_IMAGE: u32 = 27;
+    pub const TYPE_ARRAY: u32 = 28;
+    pub const TYPE_RUNTIME_ARRAY: u32 = 29;
+    pub const TYPE_STRUCT: u32 = 30;
+    pub const TYPE_POINTER: u32 = 32;
+    pub const CONSTANT: u32 = 43;
+    pub const VARIABLE: u32 = 59;
+    pub const TYPE_ACCELERATION_STRUCTURE: u32 = 5341;
+}
+
+mod decoration {
+    pub const BUFFER_BLOCK: u32 = 3;
+    pub const ARRAY_STRIDE: u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BINDING: u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET: u32 = 35;
+}
+
+mod storage {
+    pub const UNIFORM: u32 = 2;
+    pub const PUSH_CONSTANT: u32 = 9;
+    pub const STORAGE_BUFFER: u32 = 12;
+}
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutBinding {
+    pub set: u32,
+    pub binding: DescriptorBinding,
+}
+
+impl LayoutBinding {
+    #[inline]
+    pub const fn new(set: u32, binding: DescriptorBinding) -> Self {
+        Self { set, binding }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderReflection {
+    bindings: Vec<LayoutBinding>,
+    push_constants: Option<PushConstantRange>,
+}
+
+impl ShaderReflection {
+    #[inline]
+    pub fn bindings(&self) -> &[LayoutBinding] {
+        &self.bindings
+    }
+
+    #[inline]
+    pub fn push_constants(&self) -> Option<PushConstantRange> {
+        self.push_constants
+    }
+
+    pub fn parse(words: &[u32], stages: ShaderStage) -> crate::Result<Self> {
+        if words.len() < HEADER_LEN || words[0] != MAGIC {
+            return Err(crate::Error::InvalidParams("Shader code is not valid SPIR-V"));
+        }
+
+        let mut module = Module::default();
+
+        let mut words = &words[HEADER_LEN..];
+        while let Some(&first) = words.first() {
+            let (count, opcode) = ((first >> 16) as usize, first & 0xffff);
+            if count == 0 || count > words.len() {
+                return Err(crate::Error::InvalidParams("Shader code contains a malformed SPIR-V instruction"));
+            }
+
+            module.read(opcode, &words[1..count]);
+            words = &words[count..];
+        }
+
+        Ok(module.reflect(stages))
+    }
+
+    pub(crate) fn merge<'a>(reflections: impl IntoIterator<Item = &'a ShaderReflection>) -> crate::Result<Self> {
+        let mut merged = ShaderReflection::default();
+
+        for reflection in reflections {
+            for binding in &reflection.bindings {
+                let existing = merged
+                    .bindings
+                    .iter_mut()
+                    .find(|other| other.set == binding.set && other.binding.binding == binding.binding.binding);
+
+                match existing {
+                    Some(existing) if existing.binding.ty != binding.binding.ty => {
+                        return Err(crate::Error::InvalidParams(
+                            "Shader stages declare different descriptor types for the same binding",
+                        ));
+                    }
+                    Some(existing) => {
+                        existing.binding.stages |= binding.binding.stages;
+                        existing.binding.count = existing.binding.count.max(binding.binding.count);
+                    }
+                    None => merged.bindings.push(*binding),
+                }
+            }
+
+            if let Some(range) = reflection.push_constants {
+                merged.push_constants = Some(match merged.push_constants {
+                    Some(existing) => {
+                        let offset = existing.offset.min(range.offset);
+                        let end = (existing.offset + existing.size).max(range.offset + range.size);
+                        PushConstantRange::new(existing.stages | range.stages, offset, end - offset)
+                    }
+                    None => range,
+                });
+            }
+        }
+
+        Ok(merged)
+    }
+
+    pub(crate) fn apply_overrides(&mut self, overrides: &[LayoutBinding], push_constant_ranges: &[PushConstantRange]) {
+        for binding in overrides {
+            match self
+                .bindings
+                .iter_mut()
+                .find(|other| other.set == binding.set && other.binding.binding == binding.binding.binding)
+            {
+                Some(existing) => *existing = *binding,
+                None => self.bindings.push(*binding),
+            }
+        }
+
+        self.bindings.sort_by_key(|binding| (binding.set, binding.binding.binding));
+
+        if !push_constant_ranges.is_empty() {
+            self.push_constants = None;
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Type {
+    Scalar(u32),
+    Vector(u32, u32),
+    Matrix(u32, u32),
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array(u32, u32),
+    RuntimeArray(u32),
+    Struct(Vec<u32>),
+    Pointer(u32),
+    AccelerationStructure,
+}
+
+#[derive(Debug, Default)]
+struct Decorations {
+    set: Option<u32>,
+    binding: Option<u32>,
+    buffer_block: bool,
+    array_stride: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+struct MemberDecorations {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+struct Module {
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), MemberDecorations>,
+    variables: Vec<(u32, u32, u32)>,
+}
+
+impl Module {
+    fn read(&mut self, opcode: u32, operands: &[u32]) {
+        let operand = |index: usize| operands.get(index).copied().unwrap_or(0);
+
+        match opcode {
+            op::DECORATE => {
+                let decorations = self.decorations.entry(operand(0)).or_default();
+                match operand(1) {
+                    decoration::DESCRIPTOR_SET => decorations.set = Some(operand(2)),
+                    decoration::BINDING => decorations.binding = Some(operand(2)),
+                    decoration::BUFFER_BLOCK => decorations.buffer_block = true,
+                    decoration::ARRAY_STRIDE => decorations.array_stride = Some(operand(2)),
+                    _ => (),
+                }
+            }
+            op::MEMBER_DECORATE => {
+                let decorations = self.member_decorations.entry((operand(0), operand(1))).or_default();
+                match operand(2) {
+                    decoration::OFFSET => decorations.offset = Some(operand(3)),
+                    decoration::MATRIX_STRIDE => decorations.matrix_stride = Some(operand(3)),
+                    _ => (),
+                }
+            }
+            op::TYPE_INT | op::TYPE_FLOAT => {
+                self.types.insert(operand(0), Type::Scalar(operand(1) / 8));
+            }
+            op::TYPE_VECTOR => {
+                self.types.insert(operand(0), Type::Vector(operand(1), operand(2)));
+            }
+            op::TYPE_MATRIX => {
+                self.types.insert(operand(0), Type::Matrix(operand(1), operand(2)));
+            }
+            op::TYPE_IMAGE => {
+                self.types.insert(
+                    operand(0),
+                    Type::Image {
+                        dim: operand(2),
+                        sampled: operand(6),
+                    },
+                );
+            }
+            op::TYPE_SAMPLER => {
+                self.types.insert(operand(0), Type::Sampler);
+            }
+            op::TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operand(0), Type::SampledImage);
+            }
+            op::TYPE_ARRAY => {
+                self.types.insert(operand(0), Type::Array(operand(1), operand(2)));
+            }
+            op::TYPE_RUNTIME_ARRAY => {
+                self.types.insert(operand(0), Type::RuntimeArray(operand(1)));
+            }
+            op::TYPE_STRUCT => {
+                self.types.insert(operand(0), Type::Struct(operands.get(1..).unwrap_or_default().to_vec()));
+            }
+            op::TYPE_POINTER => {
+                self.types.insert(operand(0), Type::Pointer(operand(2)));
+            }
+            op::TYPE_ACCELERATION_STRUCTURE => {
+                self.types.insert(operand(0), Type::AccelerationStructure);
+            }
+            op::CONSTANT => {
+                self.constants.insert(operand(1), operand(2));
+            }
+            op::VARIABLE => self.variables.push((operand(0), operand(1), operand(2))),
+            _ => (),
+        }
+    }
+
+    fn reflect(&self, stages: ShaderStage) -> ShaderReflection {
+        let mut reflection = ShaderReflection::default();
+
+        for &(pointer, id, storage_class) in &self.variables {
+            let Some(&Type::Pointer(pointee)) = self.types.get(&pointer) else {
+                continue;
+            };
+
+            if storage_class == storage::PUSH_CONSTANT {
+                let offset = match self.types.get(&pointee) {
+                    Some(Type::Struct(members)) => (0..members.len() as u32)
+                        .filter_map(|member| self.member_decorations.get(&(pointee, member))?.offset)
+                        .min()
+                        .unwrap_or(0),
+                    _ => 0,
+                };
+                let end = self.size_of(pointee, None);
+
+                if end > offset {
+                    reflection.push_constants = Some(PushConstantRange::new(stages, offset, end - offset));
+                }
+                continue;
+            }
+
+            let Some(decorations) = self.decorations.get(&id) else {
+                continue;
+            };
+            let (Some(set), Some(binding)) = (decorations.set, decorations.binding) else {
+                continue;
+            };
+
+            if let Some((ty, count)) = self.descriptor_type(pointee, storage_class) {
+                reflection.bindings.push(LayoutBinding::new(
+                    set,
+                    DescriptorBinding::new(binding, ty, stages).with_count(count),
+                ));
+            }
+        }
+
+        reflection.bindings.sort_by_key(|binding| (binding.set, binding.binding.binding));
+        reflection
+    }
+
+    fn descriptor_type(&self, mut id: u32, storage_class: u32) -> Option<(DescriptorType, u32)> {
+        let mut count = 1;
+
+        loop {
+            match self.types.get(&id)? {
+                Type::Array(element, length) => {
+                    count *= self.constants.get(length).copied().unwrap_or(1);
+                    id = *element;
+                }
+                Type::RuntimeArray(element) => id = *element,
+                _ => break,
+            }
+        }
+
+        let ty = match self.types.get(&id)? {
+            Type::Sampler => DescriptorType::SAMPLER,
+            Type::SampledImage => DescriptorType::COMBINED_IMAGE_SAMPLER,
+            Type::Image { dim: DIM_SUBPASS_DATA, .. } => DescriptorType::INPUT_ATTACHMENT,
+            Type::Image { dim: DIM_BUFFER, sampled: 2 } => DescriptorType::STORAGE_TEXEL_BUFFER,
+            Type::Image { dim: DIM_BUFFER, .. } => DescriptorType::UNIFORM_TEXEL_BUFFER,
+            Type::Image { sampled: 2, .. } => DescriptorType::STORAGE_IMAGE,
+            Type::Image { .. } => DescriptorType::SAMPLED_IMAGE,
+            Type::AccelerationStructure => DescriptorType::ACCELERATION_STRUCTURE_KHR,
+            Type::Struct(_) if storage_class == storage::STORAGE_BUFFER => DescriptorType::STORAGE_BUFFER,
+            Type::Struct(_) if storage_class == storage::UNIFORM => {
+                if self.decorations.get(&id).is_some_and(|decorations| decorations.buffer_block) {
+                    DescriptorType::STORAGE_BUFFER
+                } else {
+                    DescriptorType::UNIFORM_BUFFER
+                }
+            }
+            _ => return None,
+        };
+
+        Some((ty, count))
+    }
+
+    fn size_of(&self, id: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&id) {
+            Some(&Type::Scalar(size)) => size,
+            Some(&Type::Vector(component, count)) => self.size_of(component, None) * count,
+            Some(&Type::Matrix(column, count)) => matrix_stride.unwrap_or_else(|| self.size_of(column, None)) * count,
+            Some(&Type::Array(element, length)) => {
+                let length = self.constants.get(&length).copied().unwrap_or(0);
+                let stride = self
+                    .decorations
+                    .get(&id)
+                    .and_then(|decorations| decorations.array_stride)
+                    .unwrap_or_else(|| self.size_of(element, matrix_stride));
+                stride * length
+            }
+            Some(Type::Struct(members)) => members
+                .iter()
+                .enumerate()
+                .map(|(index, &member)| {
+                    let decorations = self.member_decorations.get(&(id, index as u32));
+                    let offset = decorations.and_then(|decorations| decorations.offset).unwrap_or(0);
+                    offset + self.size_of(member, decorations.and_then(|decorations| decorations.matrix_stride))
+                })
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use ash::vk;
+
+use crate::{Access, BarrierBuilder, Context, Image, ImageLayout, ImageView, Recording, VkHandle};
+
+pub use vk::{AttachmentLoadOp as LoadOp, AttachmentStoreOp as StoreOp, ClearValue};
+
+#[derive(Clone, Copy)]
+pub struct Attachment<'a> {
+    image: &'a Image,
+    view: vk::ImageView,
+    load_op: LoadOp,
+    store_op: StoreOp,
+    clear_value: ClearValue,
+}
+
+impl<'a> Attachment<'a> {
+    pub fn new(view: &'a ImageView) -> Self {
+        Self::from_raw(view.image(), view.handle())
+    }
+
+    pub(crate) fn from_raw(image: &'a Image, view: vk::ImageView) -> Self {
+        Self {
+            image,
+            view,
+            load_op: LoadOp::LOAD,
+            store_op: StoreOp::STORE,
+            clear_value: ClearValue::default(),
+        }
+    }
+
+    pub fn clear_color(mut self, color: [f32; 4]) -> Self {
+        self.load_op = LoadOp::CLEAR;
+        self.clear_value = ClearValue {
+            color: vk::ClearColorValue { float32: color },
+        };
+        self
+    }
+
+    pub fn clear_depth_stencil(mut self, depth: f32, stencil: u32) -> Self {
+        self.load_op = LoadOp::CLEAR;
+        self.clear_value = ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue { depth, stencil },
+        };
+        self
+    }
+
+    pub fn load_op(mut self, load_op: LoadOp) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    pub fn store_op(mut self, store_op: StoreOp) -> Self {
+        self.store_op = store_op;
+        self
+    }
+
+    #[inline]
+    pub const fn image(&self) -> &'a Image {
+        self.image
+    }
+
+    fn info(&self, layout: ImageLayout) -> vk::RenderingAttachmentInfo<'static> {
+        vk::RenderingAttachmentInfo::default()
+            .image_view(self.view)
+            .image_layout(layout)
+            .load_op(self.load_op)
+            .store_op(self.store_op)
+            .clear_value(self.clear_value)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RenderingAttachments<'a> {
+    colors: Vec<Attachment<'a>>,
+    depth: Option<Attachment<'a>>,
+    stencil: Option<Attachment<'a>>,
+    render_area: Option<vk::Rect2D>,
+}
+
+impl<'a> RenderingAttachments<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn color(mut self, attachment: Attachment<'a>) -> Self {
+        self.colors.push(attachment);
+        self
+    }
+
+    pub fn depth(mut self, attachment: Attachment<'a>) -> Self {
+        self.depth = Some(attachment);
+        self
+    }
+
+    pub fn stencil(mut self, attachment: Attachment<'a>) -> Self {
+        self.stencil = Some(attachment);
+        self
+    }
+
+    pub fn render_area(mut self, render_area: vk::Rect2D) -> Self {
+        self.render_area = Some(render_area);
+        self
+    }
+
+    fn first(&self) -> Option<&Attachment<'a>> {
+        self.colors.first().or(self.depth.as_ref()).or(self.stencil.as_ref())
+    }
+}
+
+// --------------------- Rendering commands ---------------------
+
+impl<'a> Recording<'a> {
+    pub fn begin_rendering(&mut self, attachments: RenderingAttachments<'a>) {
+        self.try_begin_rendering(attachments)
+            .unwrap_or_else(|error| panic!("Failed to begin rendering: {error}"))
+    }
+
+    pub fn try_begin_rendering(&mut self, attachments: RenderingAttachments<'a>) -> crate::Result<()> {
+        let dynamic_rendering = Context::try_handles()?
+            .extensions
+            .dynamic_rendering
+            .as_ref()
+            .ok_or_else(|| crate::Error::Unsupported("Device does not support dynamic rendering".to_string()))?;
+
+        let render_area = match (attachments.render_area, attachments.first()) {
+            (Some(render_area), _) => render_area,
+            (None, Some(attachment)) => vk::Rect2D::default().extent(attachment.image.extent().to_vk()),
+            (None, None) => return Err(crate::Error::InvalidParams("Rendering needs at least one attachment")),
+        };
+
+        let color_layout = ImageLayout::COLOR_ATTACHMENT_OPTIMAL;
+        let depth_layout = ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL;
+
+        let mut barriers = BarrierBuilder::new();
+        for attachment in &attachments.colors {
+            barriers = Self::attachment_barrier(barriers, attachment, Access::COLOR_ATTACHMENT_WRITE, color_layout);
+        }
+        for attachment in attachments.depth.iter().chain(attachments.stencil.iter()) {
+            barriers = Self::attachment_barrier(barriers, attachment, Access::DEPTH_ATTACHMENT_WRITE, depth_layout);
+        }
+        self.pipeline_barrier(barriers);
+
+        for attachment in attachments.colors.iter().chain(attachments.depth.iter()).chain(attachments.stencil.iter()) {
+            self.track(attachment.image.gpu_use());
+        }
+
+        let colors = attachments
+            .colors
+            .iter()
+            .map(|attachment| attachment.info(color_layout))
+            .collect::<Vec<_>>();
+        let depth = attachments.depth.map(|attachment| attachment.info(depth_layout));
+        let stencil = attachments.stencil.map(|attachment| attachment.info(depth_layout));
+
+        let mut rendering_info = vk::RenderingInfo::default()
+            .render_area(render_area)
+            .layer_count(1)
+            .color_attachments(&colors);
+        if let Some(ref depth) = depth {
+            rendering_info = rendering_info.depth_attachment(depth);
+        }
+        if let Some(ref stencil) = stencil {
+            rendering_info = rendering_info.stencil_attachment(stencil);
+        }
+
+        unsafe { dynamic_rendering.cmd_begin_rendering(self.handle(), &rendering_info) };
+
+        Ok(())
+    }
+
+    pub fn end_rendering(&mut self) {
+        let dynamic_rendering = Context::handles()
+            .extensions
+            .dynamic_rendering
+            .as_ref()
+            .expect("Device does not support dynamic rendering");
+
+        unsafe { dynamic_rendering.cmd_end_rendering(self.handle()) };
+    }
+
+    fn attachment_barrier(
+        barriers: BarrierBuilder<'a>,
+        attachment: &Attachment<'a>,
+        dst: Access,
+        layout: ImageLayout,
+    ) -> BarrierBuilder<'a> {
+        let image = attachment.image;
+        if image.layout() == layout {
+            return barriers;
+        }
+
+        let old_layout = match attachment.load_op {
+            LoadOp::LOAD => image.layout(),
+            _ => ImageLayout::UNDEFINED,
+        };
+
+        barriers.image_from(image, Access::ALL, dst, old_layout, layout)
+    }
+}
@@ -1,34 +1,42 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use ash::vk;
 
-use utils::{Build, Buildable};
+use utils::{Buildable, ParamTracker, TryBuild};
+
+use crate::profiling::profile_scope;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
 pub use vk::ShaderStageFlags as ShaderStage;
 
-fn to_shader_kind(stage: ShaderStage) -> shaderc::ShaderKind {
+pub use cvk_macros::include_spv;
+#[cfg(feature = "embedded-shaders")]
+pub use cvk_macros::compile_glsl;
+
+fn to_shader_kind(stage: ShaderStage) -> Option<shaderc::ShaderKind> {
     if stage.contains(ShaderStage::VERTEX) {
-        shaderc::ShaderKind::Vertex
+        Some(shaderc::ShaderKind::Vertex)
     } else if stage.contains(ShaderStage::FRAGMENT) {
-        shaderc::ShaderKind::Fragment
+        Some(shaderc::ShaderKind::Fragment)
     } else if stage.contains(ShaderStage::COMPUTE) {
-        shaderc::ShaderKind::Compute
+        Some(shaderc::ShaderKind::Compute)
     } else if stage.contains(ShaderStage::GEOMETRY) {
-        shaderc::ShaderKind::Geometry
+        Some(shaderc::ShaderKind::Geometry)
     } else if stage.contains(ShaderStage::TESSELLATION_CONTROL) {
-        shaderc::ShaderKind::TessControl
+        Some(shaderc::ShaderKind::TessControl)
     } else if stage.contains(ShaderStage::TESSELLATION_EVALUATION) {
-        shaderc::ShaderKind::TessEvaluation
+        Some(shaderc::ShaderKind::TessEvaluation)
     } else {
-        panic!("Unsupported shader stage specified");
+        None
     }
 }
 
-use crate::Context;
+use crate::{Context, ShaderReflection};
 
 #[derive(cvk_macros::VkHandle, utils::Share, Debug)]
 pub struct Shader {
     handle: vk::ShaderModule,
     stage: ShaderStage,
+    reflection: ShaderReflection,
 }
 
 impl Shader {
@@ -36,12 +44,18 @@ impl Shader {
     pub const fn stage(&self) -> ShaderStage {
         self.stage
     }
+
+    #[inline]
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
 }
 
 impl Drop for Shader {
     fn drop(&mut self) {
+        untrack_allocation(ResourceKind::Shader, self.raw_handle());
         unsafe {
-            Context::get_device().destroy_shader_module(self.handle, None);
+            Context::cached_device().destroy_shader_module(self.handle, None);
         }
     }
 }
@@ -58,10 +72,55 @@ pub enum ShaderCode<'a> {
     StrGLSL(&'a str),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShaderDefine {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl ShaderDefine {
+    pub fn new(name: impl Into<String>, value: Option<impl Into<String>>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.map(Into::into),
+        }
+    }
+}
+
+impl From<&str> for ShaderDefine {
+    fn from(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+        }
+    }
+}
+
+impl From<(&str, &str)> for ShaderDefine {
+    fn from((name, value): (&str, &str)) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value.into()),
+        }
+    }
+}
+
 #[derive(utils::Paramters, Debug, Clone)]
+#[param(getters, snapshot)]
 pub struct ShaderBuilder<'a> {
+    #[required]
+    #[param(default = ShaderStage::empty())]
     stage: ShaderStage,
+    #[param(default = ShaderCode::BufSPV(&[]))]
     code: ShaderCode<'a>,
+    #[vec(define)]
+    defines: Vec<ShaderDefine>,
+    #[vec(include_dir)]
+    include_dirs: Vec<PathBuf>,
+    debug_name: Option<String>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
 }
 
 impl<'a> ShaderBuilder<'a> {
@@ -86,23 +145,47 @@ impl<'a> ShaderBuilder<'a> {
     }
 }
 
-impl Default for ShaderBuilder<'_> {
-    fn default() -> Self {
-        Self {
-            stage: ShaderStage::empty(),
-            code: ShaderCode::BufSPV(&[]),
-        }
+impl<'a> ShaderBuilder<'a> {
+    fn resolve_include(
+        &self,
+        name: &str,
+        include_type: shaderc::IncludeType,
+        requesting_source: &str,
+    ) -> shaderc::IncludeCallbackResult {
+        let relative_dir = match include_type {
+            shaderc::IncludeType::Relative => Path::new(requesting_source).parent(),
+            shaderc::IncludeType::Standard => None,
+        };
+
+        let path = relative_dir
+            .into_iter()
+            .chain(self.include_dirs.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| format!("Failed to find include '{name}'"))?;
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|error| format!("Failed to read include '{}': {error}", path.display()))?;
+
+        Ok(shaderc::ResolvedInclude {
+            resolved_name: path.to_string_lossy().into(),
+            content,
+        })
     }
 }
 
-impl<'a> Build for ShaderBuilder<'a> {
+impl<'a> TryBuild for ShaderBuilder<'a> {
     type Target = Shader;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_shader");
 
-    fn build(&self) -> Self::Target {
-        assert!(
-            !self.stage.is_empty(),
-            "No shader stage specified in shader builder"
-        );
+        self.check_required()?;
+
+        if self.stage.is_empty() {
+            return Err(crate::Error::InvalidParams("Shader stage cannot be empty"));
+        }
 
         enum CodeData<'a> {
             GLSL(&'a str),
@@ -119,7 +202,7 @@ impl<'a> Build for ShaderBuilder<'a> {
                 file_path = path_buf.as_os_str().to_string_lossy().into();
 
                 let data = std::fs::read(path_buf)
-                    .expect(&format!("Failed to read shader in file '{}'", file_path));
+                    .map_err(|error| crate::Error::Io(path_buf.clone(), error))?;
 
                 spirv_vec = data
                     .chunks_exact(size_of::<u32>())
@@ -132,7 +215,7 @@ impl<'a> Build for ShaderBuilder<'a> {
                 file_path = path_buf.as_os_str().to_string_lossy().into();
 
                 glsl_str = std::fs::read_to_string(path_buf)
-                    .expect(&format!("Failed to read shader in file '{}'", file_path));
+                    .map_err(|error| crate::Error::Io(path_buf.clone(), error))?;
 
                 CodeData::GLSL(&glsl_str)
             }
@@ -144,35 +227,62 @@ impl<'a> Build for ShaderBuilder<'a> {
 
         let spv_data = match code_data {
             CodeData::GLSL(glsl_str) => {
-                let mut options = shaderc::CompileOptions::new().unwrap();
+                let mut options = shaderc::CompileOptions::new()
+                    .map_err(|error| crate::Error::ShaderCompilation(error.to_string()))?;
                 options.set_optimization_level(shaderc::OptimizationLevel::Performance);
 
-                let compile_result = Context::get().glsl_compiler().compile_into_spirv(
+                for define in &self.defines {
+                    options.add_macro_definition(&define.name, define.value.as_deref());
+                }
+
+                options.set_include_callback(|name, include_type, requesting_source, _depth| {
+                    self.resolve_include(name, include_type, requesting_source)
+                });
+
+                let shader_kind = to_shader_kind(self.stage)
+                    .ok_or(crate::Error::InvalidParams("Unsupported shader stage specified"))?;
+
+                let compile_result = Context::try_get()?.glsl_compiler().compile_into_spirv(
                     glsl_str,
-                    to_shader_kind(self.stage),
+                    shader_kind,
                     &file_path,
                     "main",
                     Some(&options),
                 );
 
-                compiler_artifact = match compile_result {
-                    Ok(value) => value,
-                    Err(error) => panic!("Failed to compile GLSL:\n{error}"),
-                };
+                compiler_artifact = compile_result
+                    .map_err(|error| crate::Error::ShaderCompilation(error.to_string()))?;
 
                 compiler_artifact.as_binary()
             }
+            CodeData::SPV(_) if !self.defines.is_empty() => {
+                return Err(crate::Error::InvalidParams("Shader defines need GLSL source code"));
+            }
             CodeData::SPV(spv_data) => spv_data,
         };
 
+        let reflection = ShaderReflection::parse(spv_data, self.stage)?;
+
         let info = vk::ShaderModuleCreateInfo::default().code(spv_data);
 
-        let handle = unsafe { Context::get_device().create_shader_module(&info, None) }
-            .expect("Failed to create shader");
+        let handle = unsafe { Context::cached_device().create_shader_module(&info, None) }?;
+
+        let name = self.debug_name.clone().or(match self.code {
+            ShaderCode::FileSPV(_) | ShaderCode::FileGLSL(_) => Some(file_path),
+            _ => None,
+        });
 
-        Shader {
+        track_allocation(LiveAllocation::new(
+            ResourceKind::Shader,
+            vk::Handle::as_raw(handle),
+            size_of_val(spv_data) as vk::DeviceSize,
+            name,
+        ));
+
+        Ok(Shader {
             handle,
             stage: self.stage,
-        }
+            reflection,
+        })
     }
 }
@@ -0,0 +1,186 @@
+use ash::vk;
+use parking_lot::Mutex;
+use utils::{Buildable, TryBuild};
+
+use crate::{Access, BarrierBuilder, Buffer, BufferUsage, Context, MemoryUsage, Recording, VkHandle};
+
+static PRINTF_CAPTURE: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+pub struct DebugPrintf;
+
+impl DebugPrintf {
+    pub fn start_capture() {
+        PRINTF_CAPTURE.lock().get_or_insert_with(Vec::new);
+    }
+
+    pub fn stop_capture() -> Vec<String> {
+        PRINTF_CAPTURE.lock().take().unwrap_or_default()
+    }
+
+    pub fn is_capturing() -> bool {
+        PRINTF_CAPTURE.lock().is_some()
+    }
+
+    pub fn take() -> Vec<String> {
+        PRINTF_CAPTURE
+            .lock()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn capture(message: &str) -> bool {
+        match PRINTF_CAPTURE.lock().as_mut() {
+            Some(messages) => {
+                messages.push(message.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShaderAssertFailure {
+    pub id: u32,
+    pub line: u32,
+    pub values: [u32; 2],
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShaderAssertReport {
+    pub failures: Vec<ShaderAssertFailure>,
+    pub total: u32,
+}
+
+impl ShaderAssertReport {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    #[inline]
+    pub fn dropped(&self) -> u32 {
+        self.total - self.failures.len() as u32
+    }
+}
+
+pub struct ShaderAssertBuffer {
+    buffer: Buffer<u32>,
+    capacity: u32,
+}
+
+impl ShaderAssertBuffer {
+    const HEADER_WORDS: usize = 2;
+    const RECORD_WORDS: usize = 4;
+
+    pub fn new(capacity: u32) -> Self {
+        Self::try_new(capacity).unwrap_or_else(|error| panic!("Failed to create shader assert buffer: {error}"))
+    }
+
+    pub fn try_new(capacity: u32) -> crate::Result<Self> {
+        let count = Self::HEADER_WORDS + capacity as usize * Self::RECORD_WORDS;
+
+        let buffer = Buffer::<u32>::builder()
+            .count(count as u64)
+            .usage(BufferUsage::STORAGE_BUFFER | BufferUsage::TRANSFER_DST)
+            .memory_usage(MemoryUsage::Readback)
+            .data_with_fill(&[0, capacity], 0)
+            .debug_name("shader assert buffer")
+            .try_build()?;
+
+        Ok(Self { buffer, capacity })
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &Buffer<u32> {
+        &self.buffer
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn take_failures(&mut self) -> ShaderAssertReport {
+        self.try_take_failures()
+            .unwrap_or_else(|error| panic!("Failed to read shader assert buffer: {error}"))
+    }
+
+    /// Waits until the GPU has finished with the buffer, then reads and resets the recorded failures.
+    pub fn try_take_failures(&mut self) -> crate::Result<ShaderAssertReport> {
+        self.buffer.gpu_use().try_wait_idle()?;
+        self.buffer.try_invalidate()?;
+
+        let Some(words) = self.buffer.try_mapped_mut()? else {
+            return Ok(ShaderAssertReport::default());
+        };
+
+        let report = Self::parse(words, self.capacity);
+
+        words[0] = 0;
+        self.buffer.try_flush()?;
+
+        Ok(report)
+    }
+
+    pub fn read_failures(&self) -> ShaderAssertReport {
+        self.try_read_failures()
+            .unwrap_or_else(|error| panic!("Failed to read shader assert buffer: {error}"))
+    }
+
+    /// Like [`try_take_failures`](Self::try_take_failures), but leaves the buffer as it is. Reset it on the GPU
+    /// with [`Recording::reset_shader_asserts`] before it is written again.
+    pub fn try_read_failures(&self) -> crate::Result<ShaderAssertReport> {
+        self.buffer.gpu_use().try_wait_idle()?;
+        self.buffer.try_invalidate()?;
+
+        Ok(self
+            .buffer
+            .mapped()
+            .map_or_else(ShaderAssertReport::default, |words| Self::parse(words, self.capacity)))
+    }
+
+    fn parse(words: &[u32], capacity: u32) -> ShaderAssertReport {
+        let total = words[0];
+
+        let failures = words[Self::HEADER_WORDS..]
+            .chunks_exact(Self::RECORD_WORDS)
+            .take((total as usize).min(capacity as usize))
+            .map(|record| ShaderAssertFailure {
+                id: record[0],
+                line: record[1],
+                values: [record[2], record[3]],
+            })
+            .collect();
+
+        ShaderAssertReport { failures, total }
+    }
+}
+
+impl Default for ShaderAssertBuffer {
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+// --------------------- Shader debug commands ---------------------
+
+impl<'a> Recording<'a> {
+    /// Clears the failure count of `asserts` on the GPU, so the shaders of this recording start from an empty buffer.
+    pub fn reset_shader_asserts(&mut self, asserts: &'a ShaderAssertBuffer) {
+        self.track(asserts.buffer.gpu_use());
+
+        unsafe {
+            Context::cached_device().cmd_fill_buffer(
+                self.handle(),
+                asserts.buffer.handle(),
+                0,
+                size_of::<u32>() as vk::DeviceSize,
+                0,
+            );
+        }
+
+        self.pipeline_barrier(BarrierBuilder::new().memory(Access::TRANSFER_WRITE, Access::ALL));
+    }
+}
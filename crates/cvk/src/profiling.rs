@@ -0,0 +1,8 @@
+macro_rules! profile_scope {
+    ($name:literal $(, $($field:tt)*)?) => {
+        #[cfg(feature = "profiling")]
+        let _profile_span = ::tracing::info_span!($name $(, $($field)*)?).entered();
+    };
+}
+
+pub(crate) use profile_scope;
@@ -1,7 +1,21 @@
+pub mod atlas;
 pub mod buffer;
+pub mod frame_allocator;
 pub mod image;
+pub mod image_view;
 pub mod memory;
+pub mod pool;
+pub mod readback;
+pub mod report;
+pub mod sampler;
 
+pub use atlas::*;
 pub use buffer::*;
+pub use frame_allocator::*;
 pub use image::*;
+pub use image_view::*;
 pub use memory::*;
+pub use pool::*;
+pub use readback::*;
+pub use sampler::*;
+pub use report::{AllocationReport, LiveAllocation, ResourceKind, ResourceStats};
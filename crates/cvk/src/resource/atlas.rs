@@ -0,0 +1,345 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ash::vk::{self, Format};
+use utils::{Buildable, ParamTracker, TryBuild};
+
+use crate::profiling::profile_scope;
+use crate::resource::image::format_size;
+use crate::{Buffer, CommandBuffer, Extent2D, Image, ImageLayout, ImageUsage, MemoryUsage};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvTransform {
+    pub offset: [f32; 2],
+    pub scale: [f32; 2],
+}
+
+impl UvTransform {
+    #[inline]
+    pub fn apply(&self, uv: [f32; 2]) -> [f32; 2] {
+        [
+            self.offset[0] + uv[0] * self.scale[0],
+            self.offset[1] + uv[1] * self.scale[1],
+        ]
+    }
+}
+
+static NEXT_ATLAS_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Owns its space in the atlas until it is passed back to [`AtlasImage::free`]. Regions of another atlas
+/// or of a shelf that was released in the meantime are rejected.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AtlasRegion {
+    offset: [u32; 2],
+    extent: Extent2D,
+    uv: UvTransform,
+    atlas: u64,
+    shelf: ShelfSlot,
+    width: u32,
+}
+
+impl AtlasRegion {
+    #[inline]
+    pub const fn offset(&self) -> [u32; 2] {
+        self.offset
+    }
+
+    #[inline]
+    pub const fn extent(&self) -> Extent2D {
+        self.extent
+    }
+
+    #[inline]
+    pub const fn uv_transform(&self) -> UvTransform {
+        self.uv
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub(crate) struct ShelfSlot {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    x: u32,
+    width: u32,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    free: Vec<Span>,
+    used: u32,
+    generation: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShelfPacker {
+    extent: Extent2D,
+    shelves: Vec<Shelf>,
+    next_generation: u64,
+}
+
+impl ShelfPacker {
+    pub(crate) fn new(extent: Extent2D) -> Self {
+        Self {
+            extent,
+            shelves: vec![],
+            next_generation: 0,
+        }
+    }
+
+    pub(crate) fn allocate(&mut self, width: u32, height: u32) -> Option<(ShelfSlot, u32, u32)> {
+        if width == 0 || height == 0 || width > self.extent.width || height > self.extent.height {
+            return None;
+        }
+
+        let best = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| shelf.height >= height && shelf.height <= height + height / 2)
+            .filter_map(|(index, shelf)| {
+                let span = shelf.free.iter().position(|span| span.width >= width)?;
+                Some((index, span, shelf.height))
+            })
+            .min_by_key(|&(_, _, shelf_height)| shelf_height);
+
+        let (index, span) = match best {
+            Some((index, span, _)) => (index, span),
+            None => {
+                let y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+                if y + height > self.extent.height {
+                    return None;
+                }
+
+                self.shelves.push(Shelf {
+                    y,
+                    height,
+                    free: vec![Span {
+                        x: 0,
+                        width: self.extent.width,
+                    }],
+                    used: 0,
+                    generation: self.next_generation,
+                });
+                self.next_generation += 1;
+                (self.shelves.len() - 1, 0)
+            }
+        };
+
+        let shelf = &mut self.shelves[index];
+        let x = shelf.free[span].x;
+
+        if shelf.free[span].width == width {
+            shelf.free.remove(span);
+        } else {
+            shelf.free[span].x += width;
+            shelf.free[span].width -= width;
+        }
+        shelf.used += 1;
+
+        let slot = ShelfSlot {
+            index,
+            generation: shelf.generation,
+        };
+        Some((slot, x, shelf.y))
+    }
+
+    pub(crate) fn free(&mut self, slot: ShelfSlot, x: u32, width: u32) -> crate::Result<()> {
+        let shelf = self
+            .shelves
+            .get_mut(slot.index)
+            .filter(|shelf| shelf.generation == slot.generation && shelf.used > 0)
+            .ok_or(crate::Error::InvalidParams("Atlas region belongs to a shelf that was already released"))?;
+
+        let position = shelf.free.partition_point(|span| span.x < x);
+        let overlaps_previous = position > 0 && shelf.free[position - 1].x + shelf.free[position - 1].width > x;
+        let overlaps_next = shelf.free.get(position).is_some_and(|span| span.x < x + width);
+        if overlaps_previous || overlaps_next || x + width > self.extent.width {
+            return Err(crate::Error::InvalidParams("Atlas region was already freed"));
+        }
+
+        shelf.free.insert(position, Span { x, width });
+
+        if position + 1 < shelf.free.len() && shelf.free[position].x + shelf.free[position].width == shelf.free[position + 1].x {
+            shelf.free[position].width += shelf.free[position + 1].width;
+            shelf.free.remove(position + 1);
+        }
+        if position > 0 && shelf.free[position - 1].x + shelf.free[position - 1].width == shelf.free[position].x {
+            shelf.free[position - 1].width += shelf.free[position].width;
+            shelf.free.remove(position);
+        }
+
+        shelf.used -= 1;
+
+        while self.shelves.last().is_some_and(|shelf| shelf.used == 0) {
+            self.shelves.pop();
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+pub struct AtlasImage {
+    id: u64,
+    image: Image,
+    packer: ShelfPacker,
+    padding: u32,
+    allocated: usize,
+}
+
+impl AtlasImage {
+    #[inline]
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    #[inline]
+    pub fn extent(&self) -> Extent2D {
+        self.image.extent()
+    }
+
+    #[inline]
+    pub fn allocated_regions(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn allocate(&mut self, extent: impl Into<Extent2D>) -> Option<AtlasRegion> {
+        let extent = extent.into();
+        let width = extent.width + self.padding;
+        let height = extent.height + self.padding;
+
+        let (shelf, x, y) = self.packer.allocate(width, height)?;
+        self.allocated += 1;
+
+        let atlas_extent = self.image.extent();
+        let uv = UvTransform {
+            offset: [
+                x as f32 / atlas_extent.width as f32,
+                y as f32 / atlas_extent.height as f32,
+            ],
+            scale: [
+                extent.width as f32 / atlas_extent.width as f32,
+                extent.height as f32 / atlas_extent.height as f32,
+            ],
+        };
+
+        Some(AtlasRegion {
+            offset: [x, y],
+            extent,
+            uv,
+            atlas: self.id,
+            shelf,
+            width,
+        })
+    }
+
+    pub fn free(&mut self, region: AtlasRegion) {
+        self.try_free(region)
+            .unwrap_or_else(|error| panic!("Failed to free atlas region: {error}"))
+    }
+
+    /// Fails for regions of another atlas and for regions allocated before the last [`clear`](Self::clear).
+    pub fn try_free(&mut self, region: AtlasRegion) -> crate::Result<()> {
+        if region.atlas != self.id {
+            return Err(crate::Error::InvalidParams("Atlas region was allocated from a different atlas"));
+        }
+
+        self.packer.free(region.shelf, region.offset[0], region.width)?;
+        self.allocated -= 1;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.packer.clear();
+        self.allocated = 0;
+    }
+
+    pub fn write(&self, region: &AtlasRegion, data: &[u8]) {
+        self.try_write(region, data)
+            .unwrap_or_else(|error| panic!("Failed to write atlas region: {error}"))
+    }
+
+    pub fn try_write(&self, region: &AtlasRegion, data: &[u8]) -> crate::Result<()> {
+        profile_scope!("cvk::write_atlas_region", len = data.len());
+
+        let texel_size = format_size(self.image.format())
+            .ok_or(crate::Error::InvalidParams("Atlas format is not supported for uploads"))?;
+
+        let size = region.extent.width as vk::DeviceSize * region.extent.height as vk::DeviceSize * texel_size;
+        if data.len() as vk::DeviceSize != size {
+            return Err(crate::Error::InvalidParams("Atlas data does not match the region extent and format"));
+        }
+
+        crate::core::stats::record_staging_allocation();
+        let staging_buffer = Buffer::<u8>::builder().staging_buffer().data(data).try_build()?;
+
+        CommandBuffer::try_run_single_use(|recording| {
+            recording.copy_buffer_to_image_rect(&staging_buffer, &self.image, region.offset, region.extent);
+            recording.transition_image_layout(&self.image, ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        })
+    }
+}
+
+impl Buildable for AtlasImage {
+    type Builder<'a> = AtlasImageBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+#[param(getters, snapshot)]
+pub struct AtlasImageBuilder {
+    #[required]
+    #[param(default = vk::Format::UNDEFINED)]
+    format: Format,
+    #[param(default = Extent2D::new(2048, 2048))]
+    extent: Extent2D,
+    #[flag]
+    #[param(default = ImageUsage::SAMPLED)]
+    usage: ImageUsage,
+    #[param(default = MemoryUsage::PreferDevice)]
+    memory_usage: MemoryUsage,
+    #[param(default = 1)]
+    padding: u32,
+    debug_name: Option<String>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl TryBuild for AtlasImageBuilder {
+    type Target = AtlasImage;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        let mut builder = Image::builder()
+            .format(self.format)
+            .extent(self.extent)
+            .usage(self.usage | ImageUsage::TRANSFER_DST)
+            .memory_usage(self.memory_usage)
+            .final_layout(ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+        if let Some(ref debug_name) = self.debug_name {
+            builder = builder.debug_name(debug_name.clone());
+        }
+
+        Ok(AtlasImage {
+            id: NEXT_ATLAS_ID.fetch_add(1, Ordering::Relaxed),
+            image: builder.try_build()?,
+            packer: ShelfPacker::new(self.extent),
+            padding: self.padding,
+            allocated: 0,
+        })
+    }
+}
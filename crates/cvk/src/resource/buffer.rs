@@ -1,14 +1,15 @@
-use std::{
-    num::NonZero,
-    ptr::{NonNull, copy_nonoverlapping, slice_from_raw_parts, slice_from_raw_parts_mut},
-};
+use std::marker::PhantomData;
+use std::ptr::{NonNull, copy_nonoverlapping, slice_from_raw_parts, slice_from_raw_parts_mut, write_bytes};
 
-use crate::{CommandBuffer, Context, MemoryUsage, Recording, VkHandle};
+use crate::profiling::profile_scope;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{CommandBuffer, Context, GpuUse, MemoryPool, MemoryUsage, QueueFamily, Recording, Sharing, TimelineValue, VkHandle};
 use ash::vk;
-use utils::{AnyRange, Build, Buildable, Span, ToSpan};
+use utils::{AnyRange, Buildable, ParamTracker, Shared, Span, ToSpan, TryBuild, TryToSpan};
 use vk_mem::Alloc;
 
 type DeviceSpan = utils::Span<vk::DeviceSize>;
+type DeviceSpanError = utils::SpanError<vk::DeviceSize>;
 pub type BufferUsage = vk::BufferUsageFlags;
 
 #[macro_export]
@@ -24,6 +25,12 @@ pub trait BufferRegionLike<T: Copy> where Self: Sized {
     fn buffer(&self) -> vk::Buffer;
     fn span(&self) -> DeviceSpan;
     fn mapped_data_ptr(&self) -> Option<NonNull<T>>;
+    fn gpu_use(&self) -> &GpuUse;
+
+    #[inline]
+    fn last_gpu_use(&self) -> TimelineValue {
+        self.gpu_use().last()
+    }
 
     #[inline]
     fn offset(&self) -> vk::DeviceSize {
@@ -61,11 +68,35 @@ pub trait BufferRegionLike<T: Copy> where Self: Sized {
             recording.copy_buffer_regions(self, dst, ranges);
         });
     }
+
+    fn try_copy<'a>(self, dst: impl BufferRegionLike<T> + 'a) -> Result<(), DeviceSpanError> where Self: 'a {
+        if self.count() > dst.count() {
+            return Err(DeviceSpanError::new(0, self.count(), dst.count()));
+        }
+
+        self.copy(dst);
+        Ok(())
+    }
+
+    fn try_copy_regions<'a>(
+        self,
+        dst: impl BufferRegionLike<T> + 'a,
+        ranges: &[BufferCopyRange],
+    ) -> Result<(), DeviceSpanError> {
+        for range in ranges {
+            let _ = range.try_to_vk::<T>(self.span(), dst.span())?;
+        }
+
+        self.copy_regions(dst, ranges);
+        Ok(())
+    }
 }
 
 pub trait BufferRegionLikeMut<T: Copy>: BufferRegionLike<T> {
     #[inline]
     fn mapped_mut<'a>(self) -> Option<&'a mut [T]> where Self: 'a {
+        self.gpu_use().wait_idle();
+
         Some(unsafe {
             &mut *slice_from_raw_parts_mut(
                 self.mapped_data_ptr()?.as_ptr().add(self.offset() as usize),
@@ -73,6 +104,14 @@ pub trait BufferRegionLikeMut<T: Copy>: BufferRegionLike<T> {
             )
         })
     }
+
+    fn try_mapped_mut<'a>(self) -> crate::Result<Option<&'a mut [T]>> where Self: 'a {
+        self.gpu_use().check_idle()?;
+
+        Ok(self.mapped_data_ptr().map(|ptr| unsafe {
+            &mut *slice_from_raw_parts_mut(ptr.as_ptr().add(self.offset() as usize), self.count() as usize)
+        }))
+    }
 }
 
 pub trait GetBufferRegion<T: Copy>
@@ -82,6 +121,18 @@ where
     fn region<'a>(self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegion<'a, T>
     where
         Self: 'a;
+
+    fn try_region<'a>(
+        self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegion<'a, T>, DeviceSpanError>
+    where
+        Self: BufferRegionLike<T> + 'a,
+    {
+        let base = self.span();
+        let span = span.try_to_span(base)?;
+        Ok(self.region(DeviceSpan::new(span.offset - base.offset, span.count)))
+    }
 }
 
 pub trait GetBufferRegionMut<T: Copy>
@@ -91,6 +142,18 @@ where
     fn region_mut<'a>(self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegionMut<'a, T>
     where
         Self: 'a;
+
+    fn try_region_mut<'a>(
+        self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegionMut<'a, T>, DeviceSpanError>
+    where
+        Self: BufferRegionLike<T> + 'a,
+    {
+        let base = self.span();
+        let span = span.try_to_span(base)?;
+        Ok(self.region_mut(DeviceSpan::new(span.offset - base.offset, span.count)))
+    }
 }
 
 // --------------------- Buffer ---------------------
@@ -102,6 +165,10 @@ pub struct Buffer<T: Copy = u8> {
 
     count: vk::DeviceSize,
     mapped_data: Option<NonNull<T>>,
+
+    pool: Option<Shared<MemoryPool>>,
+    sharing: Sharing,
+    gpu_use: GpuUse,
 }
 
 impl<T: Copy> Buffer<T> {
@@ -110,6 +177,26 @@ impl<T: Copy> Buffer<T> {
         self.count
     }
 
+    #[inline]
+    pub fn sharing(&self) -> &Sharing {
+        &self.sharing
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+
+    #[inline]
+    pub fn last_gpu_use(&self) -> TimelineValue {
+        self.gpu_use.last()
+    }
+
+    #[inline]
+    pub fn pool(&self) -> Option<&Shared<MemoryPool>> {
+        self.pool.as_ref()
+    }
+
     #[inline]
     pub const fn size(&self) -> vk::DeviceSize {
         self.count * size_of::<T>() as vk::DeviceSize
@@ -125,6 +212,38 @@ impl<T: Copy> Buffer<T> {
         <&mut Self as BufferRegionLikeMut<T>>::mapped_mut(self)
     }
 
+    #[inline]
+    pub fn try_mapped_mut(&mut self) -> crate::Result<Option<&mut [T]>> {
+        <&mut Self as BufferRegionLikeMut<T>>::try_mapped_mut(self)
+    }
+
+    /// Makes GPU writes visible through [`mapped`](Self::mapped) on memory that is not host-coherent.
+    pub fn invalidate(&self) {
+        self.try_invalidate()
+            .unwrap_or_else(|error| panic!("Failed to invalidate buffer memory: {error}"))
+    }
+
+    pub fn try_invalidate(&self) -> crate::Result<()> {
+        Context::try_get()?
+            .allocator()
+            .invalidate_allocation(&self.allocation, 0, vk::WHOLE_SIZE)?;
+        Ok(())
+    }
+
+    /// Makes host writes through [`mapped_mut`](Self::mapped_mut) visible to the GPU on memory that is not
+    /// host-coherent.
+    pub fn flush(&self) {
+        self.try_flush()
+            .unwrap_or_else(|error| panic!("Failed to flush buffer memory: {error}"))
+    }
+
+    pub fn try_flush(&self) -> crate::Result<()> {
+        Context::try_get()?
+            .allocator()
+            .flush_allocation(&self.allocation, 0, vk::WHOLE_SIZE)?;
+        Ok(())
+    }
+
     pub fn copy<'a>(&'a self, dst: impl BufferRegionLike<T> + 'a) {
         <&Self as BufferRegionLike<T>>::copy(self, dst)
     }
@@ -144,10 +263,38 @@ impl<T: Copy> Buffer<T> {
     pub fn region_mut(&'_ mut self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegionMut<'_, T> {
         <&mut Self as GetBufferRegionMut<T>>::region_mut(self, span)
     }
+
+    pub fn try_copy<'a>(&'a self, dst: impl BufferRegionLike<T> + 'a) -> Result<(), DeviceSpanError> {
+        <&Self as BufferRegionLike<T>>::try_copy(self, dst)
+    }
+
+    pub fn try_copy_regions<'a>(
+        &'a self,
+        dst: impl BufferRegionLike<T> + 'a,
+        ranges: &[BufferCopyRange],
+    ) -> Result<(), DeviceSpanError> {
+        <&Self as BufferRegionLike<T>>::try_copy_regions(self, dst, ranges)
+    }
+
+    pub fn try_region(
+        &'_ self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegion<'_, T>, DeviceSpanError> {
+        <&Self as GetBufferRegion<T>>::try_region(self, span)
+    }
+
+    pub fn try_region_mut(
+        &'_ mut self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegionMut<'_, T>, DeviceSpanError> {
+        <&mut Self as GetBufferRegionMut<T>>::try_region_mut(self, span)
+    }
 }
 
 impl<T: Copy> Drop for Buffer<T> {
     fn drop(&mut self) {
+        let _ = self.gpu_use.try_wait_idle();
+        untrack_allocation(ResourceKind::Buffer, self.raw_handle());
         unsafe {
             Context::get()
                 .allocator()
@@ -178,6 +325,11 @@ impl<T: Copy> BufferRegionLike<T> for &Buffer<T> {
     fn mapped_data_ptr(&self) -> Option<NonNull<T>> {
         self.mapped_data
     }
+
+    #[inline]
+    fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
 }
 
 impl<T: Copy> BufferRegionLike<T> for &mut Buffer<T> {
@@ -195,6 +347,11 @@ impl<T: Copy> BufferRegionLike<T> for &mut Buffer<T> {
     fn mapped_data_ptr(&self) -> Option<NonNull<T>> {
         self.mapped_data
     }
+
+    #[inline]
+    fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
 }
 
 impl<T: Copy> BufferRegionLikeMut<T> for &mut Buffer<T> {}
@@ -239,6 +396,11 @@ impl<'a, T: Copy> BufferRegion<'a, T> {
         buffer_region.region(span)
     }
 
+    #[inline]
+    pub const fn parent(&self) -> &'a Buffer<T> {
+        self.buffer
+    }
+
     #[inline]
     pub const fn span(&self) -> DeviceSpan {
         self.span
@@ -275,6 +437,25 @@ impl<'a, T: Copy> BufferRegion<'a, T> {
     pub fn region(self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegion<'a, T> {
         <Self as GetBufferRegion<T>>::region(self, span)
     }
+
+    pub fn try_copy(self, dst: impl BufferRegionLike<T> + 'a) -> Result<(), DeviceSpanError> {
+        <Self as BufferRegionLike<T>>::try_copy(self, dst)
+    }
+
+    pub fn try_copy_regions(
+        self,
+        dst: impl BufferRegionLike<T> + 'a,
+        ranges: &[BufferCopyRange],
+    ) -> Result<(), DeviceSpanError> {
+        <Self as BufferRegionLike<T>>::try_copy_regions(self, dst, ranges)
+    }
+
+    pub fn try_region(
+        self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegion<'a, T>, DeviceSpanError> {
+        <Self as GetBufferRegion<T>>::try_region(self, span)
+    }
 }
 
 impl<T: Copy> BufferRegionLike<T> for BufferRegion<'_, T> {
@@ -292,6 +473,11 @@ impl<T: Copy> BufferRegionLike<T> for BufferRegion<'_, T> {
     fn mapped_data_ptr(&self) -> Option<NonNull<T>> {
         self.buffer.mapped_data
     }
+
+    #[inline]
+    fn gpu_use(&self) -> &GpuUse {
+        &self.buffer.gpu_use
+    }
 }
 
 impl<'a, T: Copy> GetBufferRegion<T> for BufferRegion<'a, T> {
@@ -356,6 +542,10 @@ impl<'a, T: Copy> BufferRegionMut<'a, T> {
         <Self as BufferRegionLikeMut<T>>::mapped_mut(self)
     }
 
+    pub fn try_mapped_mut(self) -> crate::Result<Option<&'a mut [T]>> {
+        <Self as BufferRegionLikeMut<T>>::try_mapped_mut(self)
+    }
+
     pub fn region(self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegion<'a, T> {
         <Self as GetBufferRegion<T>>::region(self, span)
     }
@@ -363,6 +553,20 @@ impl<'a, T: Copy> BufferRegionMut<'a, T> {
     pub fn region_mut(self, span: impl ToSpan<vk::DeviceSize>) -> BufferRegionMut<'a, T> {
         <Self as GetBufferRegionMut<T>>::region_mut(self, span)
     }
+
+    pub fn try_region(
+        self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegion<'a, T>, DeviceSpanError> {
+        <Self as GetBufferRegion<T>>::try_region(self, span)
+    }
+
+    pub fn try_region_mut(
+        self,
+        span: impl TryToSpan<vk::DeviceSize>,
+    ) -> Result<BufferRegionMut<'a, T>, DeviceSpanError> {
+        <Self as GetBufferRegionMut<T>>::try_region_mut(self, span)
+    }
 }
 
 impl<T: Copy> BufferRegionLike<T> for BufferRegionMut<'_, T> {
@@ -380,6 +584,11 @@ impl<T: Copy> BufferRegionLike<T> for BufferRegionMut<'_, T> {
     fn mapped_data_ptr(&self) -> Option<NonNull<T>> {
         self.buffer.mapped_data
     }
+
+    #[inline]
+    fn gpu_use(&self) -> &GpuUse {
+        &self.buffer.gpu_use
+    }
 }
 
 impl<T: Copy> BufferRegionLikeMut<T> for BufferRegionMut<'_, T> {}
@@ -416,21 +625,76 @@ impl<'a, T: Copy> From<&'a mut Buffer<T>> for BufferRegionMut<'a, T> {
 
 // --------------------- Buffer builder ---------------------
 
+/// Proof that the all-zero byte pattern is a valid `T`, required by [`FillPolicy::Zero`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZeroValid<T>(PhantomData<T>);
+
+impl<T> ZeroValid<T> {
+    /// # Safety
+    ///
+    /// An all-zero byte pattern must be a valid value of `T`.
+    #[inline]
+    pub const unsafe fn assume() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillPolicy<T> {
+    #[default]
+    Exact,
+    Value(T),
+    Zero(ZeroValid<T>),
+}
+
+impl<T: Copy> FillPolicy<T> {
+    unsafe fn fill(&self, dst: *mut T, count: usize) {
+        match *self {
+            FillPolicy::Zero(_) => unsafe { write_bytes(dst, 0, count) },
+            FillPolicy::Value(value) => {
+                for i in 0..count {
+                    unsafe { dst.add(i).write(value) };
+                }
+            }
+            FillPolicy::Exact => {}
+        }
+    }
+}
+
 #[derive(Clone, Debug, utils::Paramters)]
+#[param(getters)]
 pub struct BufferBuilder<'a, T: Copy = u8> {
     #[no_param]
-    count: NonZero<vk::DeviceSize>,
+    #[param(default = 1)]
+    count: vk::DeviceSize,
     #[no_param]
     data: Option<&'a [T]>,
+    #[param(no_into)]
+    fill_policy: FillPolicy<T>,
+    #[required]
     #[flag]
+    #[param(default = BufferUsage::empty())]
     usage: BufferUsage,
     memory_usage: MemoryUsage,
     mapped_data: bool,
+    pool: Option<Shared<MemoryPool>>,
+    dedicated: bool,
+    debug_name: Option<String>,
+    #[no_param]
+    sharing: Sharing,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
 }
 
 impl<'a, T: Copy> BufferBuilder<'a, T> {
+    pub fn sharing(mut self, families: &[QueueFamily]) -> Self {
+        self.sharing = Sharing::new(families);
+        self
+    }
+
     pub fn count(mut self, size: impl Into<vk::DeviceSize>) -> Self {
-        self.count = NonZero::new(size.into()).expect("Buffer size needs to be greater than zero");
+        self.count = size.into();
         self
     }
 
@@ -439,103 +703,178 @@ impl<'a, T: Copy> BufferBuilder<'a, T> {
         self
     }
 
+    pub fn data_with_fill(self, data: &'a [T], fill_value: T) -> Self {
+        self.data(data).fill_policy(FillPolicy::Value(fill_value))
+    }
+
+    /// # Safety
+    ///
+    /// An all-zero byte pattern must be a valid value of `T`.
+    pub unsafe fn data_zero_filled(self, data: &'a [T]) -> Self {
+        self.data(data).fill_policy(FillPolicy::Zero(unsafe { ZeroValid::assume() }))
+    }
+
     pub fn staging_buffer(self) -> Self {
         self.usage(BufferUsage::TRANSFER_SRC)
-            .memory_usage(MemoryUsage::PreferHost)
+            .memory_usage(MemoryUsage::Upload)
             .mapped_data(true)
     }
 }
 
-impl<T: Copy> Default for BufferBuilder<'_, T> {
-    fn default() -> Self {
-        Self {
-            count: unsafe { NonZero::new_unchecked(1) },
-            data: None,
-            usage: BufferUsage::empty(),
-            memory_usage: MemoryUsage::Auto,
-            mapped_data: false,
+impl<'a, T: Copy> TryBuild for BufferBuilder<'a, T> {
+    type Target = Buffer<T>;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_buffer", count = self.count);
+
+        self.check_required()?;
+
+        if self.usage.is_empty() {
+            return Err(crate::Error::InvalidParams("Buffer usage cannot be empty"));
         }
-    }
-}
 
-impl<'a, T: Copy> Build for BufferBuilder<'a, T> {
-    type Target = Buffer<T>;
+        let direct_upload = matches!(self.memory_usage, MemoryUsage::PreferDeviceMapped);
+        let requires_mapping = self.mapped_data || self.memory_usage.is_host_mapped();
+
+        let usage = if self.data.is_some() && direct_upload {
+            self.usage | BufferUsage::TRANSFER_DST
+        } else {
+            self.usage
+        };
 
-    fn build(&self) -> Self::Target {
-        assert!(!self.usage.is_empty(), "Buffer usage cannot be empty");
+        if self.data.is_some() && !requires_mapping && !usage.contains(BufferUsage::TRANSFER_DST) {
+            return Err(crate::Error::InvalidParams(
+                "Building buffer with data and unmapped memory needs usage TRANSFER_DST",
+            ));
+        }
 
         let count = match self.data {
-            Some(data) => (data.len() as vk::DeviceSize).max(self.count.get()),
-            None => self.count.get(),
+            Some(data) => (data.len() as vk::DeviceSize).max(self.count),
+            None => self.count,
         };
 
+        if count == 0 {
+            return Err(crate::Error::InvalidParams("Buffer size needs to be greater than zero"));
+        }
+
+        if let Some(data) = self.data
+            && matches!(self.fill_policy, FillPolicy::Exact)
+            && (data.len() as vk::DeviceSize) != count
+        {
+            return Err(crate::Error::InvalidParams(
+                "Buffer data does not match the buffer count with fill policy Exact",
+            ));
+        }
+
+        let family_indices = self.sharing.family_indices();
+
         let buffer_info = vk::BufferCreateInfo::default()
             .size(count * size_of::<T>() as vk::DeviceSize)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE)
-            .usage(self.usage);
+            .sharing_mode(self.sharing.sharing_mode())
+            .queue_family_indices(&family_indices)
+            .usage(usage);
 
-        let flags = if self.mapped_data {
+        let mut flags = if self.mapped_data && !self.memory_usage.is_host_mapped() {
             vk_mem::AllocationCreateFlags::HOST_ACCESS_RANDOM
                 | vk_mem::AllocationCreateFlags::MAPPED
         } else {
-            vk_mem::AllocationCreateFlags::empty()
+            self.memory_usage.vma_flags()
         };
 
+        if self.dedicated {
+            flags |= vk_mem::AllocationCreateFlags::DEDICATED_MEMORY;
+        }
+
         let alloc_info = vk_mem::AllocationCreateInfo {
             usage: self.memory_usage.as_vma(),
             flags,
+            required_flags: self.memory_usage.required_flags(),
             ..Default::default()
         };
 
-        let (buffer, allocation) = unsafe {
-            Context::get().allocator().create_buffer_with_alignment(
-                &buffer_info,
-                &alloc_info,
-                align_of::<T>() as vk::DeviceSize,
-            )
+        let alignment = align_of::<T>() as vk::DeviceSize;
+
+        let created = unsafe {
+            match self.pool {
+                Some(ref pool) => pool.alloc().create_buffer_with_alignment(&buffer_info, &alloc_info, alignment),
+                None => Context::try_get()?.allocator().create_buffer_with_alignment(&buffer_info, &alloc_info, alignment),
+            }
         }
-        .expect("Failed to create buffer");
+        .map_err(crate::Error::Allocation)?;
 
-        let mapped_data = if self.mapped_data {
+        let created = utils::guard(created, |(buffer, mut allocation)| unsafe {
+            Context::get()
+                .allocator()
+                .destroy_buffer(buffer, &mut allocation);
+        });
+
+        let mapped_data = if requires_mapping {
             let mapped_data_ptr = Context::get()
                 .allocator()
-                .get_allocation_info(&allocation)
+                .get_allocation_info(&created.1)
                 .mapped_data as *mut T;
 
-            if !mapped_data_ptr.is_null() {
-                Some(unsafe { NonNull::new_unchecked(mapped_data_ptr) })
-            } else {
-                None
-            }
+            Some(NonNull::new(mapped_data_ptr).ok_or(crate::Error::InvalidParams(
+                "Buffer memory could not be mapped",
+            ))?)
+        } else if direct_upload {
+            let mapped_data_ptr = Context::get()
+                .allocator()
+                .get_allocation_info(&created.1)
+                .mapped_data as *mut T;
+
+            NonNull::new(mapped_data_ptr)
         } else {
             None
         };
 
+        let size = Context::get().allocator().get_allocation_info(&created.1).size;
+
+        let (buffer, allocation) = utils::ScopeGuard::into_inner(created);
+
+        track_allocation(
+            LiveAllocation::new(ResourceKind::Buffer, vk::Handle::as_raw(buffer), size, self.debug_name.clone())
+                .with_pool(self.pool.as_deref()),
+        );
+
         let buffer = Buffer {
             handle: buffer,
             allocation,
 
             count,
             mapped_data,
+
+            pool: self.pool.clone(),
+            sharing: self.sharing.clone(),
+            gpu_use: GpuUse::default(),
         };
 
         if let Some(data) = self.data {
+            profile_scope!("cvk::upload_buffer", len = data.len());
+
             if let Some(mapped_data) = buffer.mapped_data {
-                unsafe { copy_nonoverlapping(data.as_ptr(), mapped_data.as_ptr(), count as usize) };
+                unsafe {
+                    copy_nonoverlapping(data.as_ptr(), mapped_data.as_ptr(), data.len());
+                    self.fill_policy
+                        .fill(mapped_data.as_ptr().add(data.len()), count as usize - data.len());
+                }
             } else {
-                assert!(
-                    self.usage.contains(BufferUsage::TRANSFER_DST),
-                    "Building buffer with data and unmapped memory needs usage TRANSFER_DST"
-                );
-
-                let staging_buffer = Self::default().staging_buffer().data(data).build();
-                CommandBuffer::run_single_use(|recording| {
+                crate::core::stats::record_staging_allocation();
+
+                let staging_buffer = Self::default()
+                    .staging_buffer()
+                    .count(count)
+                    .data(data)
+                    .fill_policy(self.fill_policy)
+                    .try_build()?;
+                CommandBuffer::try_run_single_use(|recording| {
                     recording.copy_buffer(&staging_buffer, &buffer)
-                });
+                })?;
             }
         }
 
-        buffer
+        Ok(buffer)
     }
 }
 
@@ -567,6 +906,28 @@ impl BufferCopyRange {
             .src_offset(src.offset * size_of::<T>() as vk::DeviceSize)
             .dst_offset(dst.offset * size_of::<T>() as vk::DeviceSize)
     }
+
+    pub fn try_to_vk<T: Copy>(
+        &self,
+        src_span: Span<vk::DeviceSize>,
+        dst_span: Span<vk::DeviceSize>,
+    ) -> Result<vk::BufferCopy, DeviceSpanError> {
+        let src = self.0.clone().try_to_span(src_span)?;
+        let dst = self.1.clone().try_to_span(dst_span)?;
+
+        if src.count > dst.count {
+            return Err(DeviceSpanError::new(
+                dst.offset - dst_span.offset,
+                dst.offset - dst_span.offset + src.count,
+                dst_span.count,
+            ));
+        }
+
+        Ok(vk::BufferCopy::default()
+            .size(src.count * size_of::<T>() as vk::DeviceSize)
+            .src_offset(src.offset * size_of::<T>() as vk::DeviceSize)
+            .dst_offset(dst.offset * size_of::<T>() as vk::DeviceSize))
+    }
 }
 
 impl<T: Into<AnyRange<vk::DeviceSize>>, U: Into<AnyRange<vk::DeviceSize>>> From<(T, U)>
@@ -595,13 +956,16 @@ impl<'a> Recording<'a> {
 
         let size = src_count.min(dst_count) * size_of::<T>() as vk::DeviceSize;
 
+        self.track(src_region.gpu_use());
+        self.track(dst_region.gpu_use());
+
         let raw_region = vk::BufferCopy::default()
             .size(size)
             .src_offset(src_offset * size_of::<T>() as vk::DeviceSize)
             .dst_offset(dst_offset * size_of::<T>() as vk::DeviceSize);
 
         unsafe {
-            Context::get_device().cmd_copy_buffer(
+            Context::cached_device().cmd_copy_buffer(
                 self.handle(),
                 src_region.buffer(),
                 dst_region.buffer(),
@@ -621,8 +985,11 @@ impl<'a> Recording<'a> {
             .map(|copy_range| copy_range.to_vk::<T>(src_region.span(), dst_region.span()))
             .collect();
 
+        self.track(src_region.gpu_use());
+        self.track(dst_region.gpu_use());
+
         unsafe {
-            Context::get_device().cmd_copy_buffer(
+            Context::cached_device().cmd_copy_buffer(
                 self.handle(),
                 src_region.buffer(),
                 dst_region.buffer(),
@@ -630,4 +997,33 @@ impl<'a> Recording<'a> {
             );
         }
     }
+
+    pub fn transfer_buffer_ownership<T: Copy>(&mut self, buffer: &'a Buffer<T>, src: QueueFamily, dst: QueueFamily) {
+        if buffer.sharing.is_concurrent() || src == dst {
+            return;
+        }
+
+        self.track(&buffer.gpu_use);
+
+        let barrier = vk::BufferMemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+            .dst_access_mask(vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE)
+            .src_queue_family_index(src.0)
+            .dst_queue_family_index(dst.0)
+            .buffer(buffer.handle)
+            .offset(0)
+            .size(vk::WHOLE_SIZE);
+
+        unsafe {
+            Context::cached_device().cmd_pipeline_barrier(
+                self.handle(),
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier],
+                &[],
+            );
+        }
+    }
 }
@@ -0,0 +1,150 @@
+use std::{marker::PhantomData, num::NonZero};
+
+use ash::vk;
+use utils::{Buildable, ParamTracker, Span, TryBuild};
+
+use crate::{Buffer, BufferRegionMut, BufferUsage, Fence, MemoryUsage};
+
+type DeviceSpan = Span<vk::DeviceSize>;
+
+pub struct FrameAllocator<T: Copy = u8> {
+    blocks: Vec<Buffer<T>>,
+    current: usize,
+    offset: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+}
+
+impl<T: Copy> FrameAllocator<T> {
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    #[inline]
+    pub const fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> vk::DeviceSize {
+        self.blocks[self.current].count()
+    }
+
+    #[inline]
+    pub const fn used(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.blocks[self.current]
+    }
+
+    pub fn begin_frame(&mut self, fence: &Fence) -> crate::Result<()> {
+        fence.try_wait_with_timeout(u64::MAX)?;
+
+        self.current = (self.current + 1) % self.blocks.len();
+        self.offset = 0;
+
+        Ok(())
+    }
+
+    pub fn try_allocate(&mut self, count: vk::DeviceSize) -> Option<(DeviceSpan, BufferRegionMut<'_, T>)> {
+        let offset = self.offset.next_multiple_of(self.alignment);
+
+        if count == 0 || offset.checked_add(count)? > self.capacity() {
+            return None;
+        }
+
+        let span = DeviceSpan::new(offset, count);
+        let region = self.blocks[self.current].try_region_mut(span).ok()?;
+
+        self.offset = offset + count;
+
+        Some((span, region))
+    }
+
+    pub fn allocate(&mut self, count: vk::DeviceSize) -> (DeviceSpan, BufferRegionMut<'_, T>) {
+        let capacity = self.capacity();
+        let used = self.offset;
+
+        self.try_allocate(count).unwrap_or_else(|| {
+            panic!("Frame allocator cannot fit {count} elements ({used} of {capacity} used)")
+        })
+    }
+
+    pub fn push(&mut self, data: &[T]) -> Option<DeviceSpan> {
+        let (span, region) = self.try_allocate(data.len() as vk::DeviceSize)?;
+        region.mapped_mut()?.copy_from_slice(data);
+        Some(span)
+    }
+}
+
+impl<T: Copy> Buildable for FrameAllocator<T> {
+    type Builder<'a>
+        = FrameAllocatorBuilder<T>
+    where
+        T: 'a;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+pub struct FrameAllocatorBuilder<T: Copy = u8> {
+    #[param(default = 2)]
+    frames: usize,
+    #[param(default = 1024 * 1024)]
+    capacity: vk::DeviceSize,
+    #[required]
+    #[flag]
+    #[param(default = BufferUsage::empty())]
+    usage: BufferUsage,
+    #[param(default = 1)]
+    alignment: vk::DeviceSize,
+
+    #[no_param]
+    _marker: PhantomData<T>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl<T: Copy> TryBuild for FrameAllocatorBuilder<T> {
+    type Target = FrameAllocator<T>;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        if self.usage.is_empty() {
+            return Err(crate::Error::InvalidParams("Frame allocator usage cannot be empty"));
+        }
+
+        if self.frames == 0 {
+            return Err(crate::Error::InvalidParams("Frame allocator needs at least one frame"));
+        }
+
+        let capacity = NonZero::new(self.capacity)
+            .ok_or(crate::Error::InvalidParams("Frame allocator capacity needs to be greater than zero"))?;
+
+        if self.alignment == 0 {
+            return Err(crate::Error::InvalidParams("Frame allocator alignment needs to be greater than zero"));
+        }
+
+        let blocks = (0..self.frames)
+            .map(|_| {
+                Buffer::<T>::builder()
+                    .count(capacity.get())
+                    .usage(self.usage)
+                    .memory_usage(MemoryUsage::Upload)
+                    .mapped_data(true)
+                    .try_build()
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(FrameAllocator {
+            blocks,
+            current: 0,
+            offset: 0,
+            alignment: self.alignment,
+        })
+    }
+}
@@ -1,18 +1,31 @@
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+
 use ash::vk::{self, Format};
-use utils::{Build, Buildable};
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
 use vk_mem::Alloc;
 
-use crate::{Context, Extent2D, MemoryUsage};
+use crate::profiling::profile_scope;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+use crate::{
+    Access, BarrierBuilder, Buffer, BufferRegionLike, CommandBuffer, Context, Extent2D, GpuUse, MemoryPool, MemoryUsage, QueueFamily,
+    Recording, Sharing, TimelineValue, VkHandle,
+};
 
 pub use vk::{ImageLayout, ImageTiling, ImageUsageFlags as ImageUsage};
 
 #[derive(cvk_macros::VkHandle, utils::Share, Debug)]
 pub struct Image {
     handle: vk::Image,
-    allocation: vk_mem::Allocation,
+    allocation: Option<vk_mem::Allocation>,
 
     format: Format,
     extent: Extent2D,
+    layout: AtomicI32,
+    layout_recording: AtomicU64,
+
+    pool: Option<Shared<MemoryPool>>,
+    sharing: Sharing,
+    gpu_use: GpuUse,
 }
 
 impl Image {
@@ -25,89 +38,473 @@ impl Image {
     pub const fn extent(&self) -> Extent2D {
         self.extent
     }
+
+    #[inline]
+    pub fn layout(&self) -> ImageLayout {
+        ImageLayout::from_raw(self.layout.load(Ordering::Acquire))
+    }
+
+    pub(crate) fn from_swapchain(handle: vk::Image, format: Format, extent: Extent2D, sharing: Sharing) -> Self {
+        Self {
+            handle,
+            allocation: None,
+
+            format,
+            extent,
+            layout: AtomicI32::new(ImageLayout::UNDEFINED.as_raw()),
+            layout_recording: AtomicU64::new(0),
+
+            pool: None,
+            sharing,
+            gpu_use: GpuUse::default(),
+        }
+    }
+
+    /// The tracked layout changes when a transition is recorded, not when it executes. Only one unsubmitted
+    /// recording may change it at a time, so recordings that transition the same image are submitted in the
+    /// order they were recorded.
+    pub(crate) fn check_layout_recording(&self, recording: &Recording<'_>) -> crate::Result<()> {
+        let previous = self.layout_recording.load(Ordering::Acquire);
+
+        if previous != 0 && previous != recording.raw_handle() {
+            return Err(crate::Error::InvalidParams(
+                "Image layout was changed by another recording that has not been submitted yet",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn try_record_layout<'a>(&'a self, recording: &mut Recording<'a>, layout: ImageLayout) -> crate::Result<()> {
+        let id = recording.raw_handle();
+
+        match self.layout_recording.compare_exchange(0, id, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => recording.pending_layouts.push(self, self.layout()),
+            Err(previous) if previous == id => {}
+            Err(_) => {
+                return Err(crate::Error::InvalidParams(
+                    "Image layout was changed by another recording that has not been submitted yet",
+                ));
+            }
+        }
+
+        self.layout.store(layout.as_raw(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Ends the recording's claim on the tracked layout, restoring `layout` if the recording was abandoned.
+    #[inline]
+    pub(crate) fn finish_layout_recording(&self, restore: Option<ImageLayout>) {
+        if let Some(layout) = restore {
+            self.layout.store(layout.as_raw(), Ordering::Release);
+        }
+        self.layout_recording.store(0, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn pool(&self) -> Option<&Shared<MemoryPool>> {
+        self.pool.as_ref()
+    }
+
+    #[inline]
+    pub fn sharing(&self) -> &Sharing {
+        &self.sharing
+    }
+
+    #[inline]
+    pub fn gpu_use(&self) -> &GpuUse {
+        &self.gpu_use
+    }
+
+    #[inline]
+    pub fn last_gpu_use(&self) -> TimelineValue {
+        self.gpu_use.last()
+    }
+
+    pub fn aspect_mask(&self) -> vk::ImageAspectFlags {
+        match self.format {
+            Format::D16_UNORM | Format::X8_D24_UNORM_PACK32 | Format::D32_SFLOAT => vk::ImageAspectFlags::DEPTH,
+            Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+            Format::D16_UNORM_S8_UINT | Format::D24_UNORM_S8_UINT | Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::COLOR,
+        }
+    }
+
+    pub fn size_bytes(&self) -> Option<vk::DeviceSize> {
+        Some(self.extent.width as vk::DeviceSize * self.extent.height as vk::DeviceSize * format_size(self.format)?)
+    }
+}
+
+pub(crate) fn format_size(format: Format) -> Option<vk::DeviceSize> {
+    let size = match format {
+        Format::R8_UNORM | Format::R8_SRGB | Format::R8_UINT | Format::S8_UINT => 1,
+        Format::R8G8_UNORM | Format::R16_SFLOAT | Format::R16_UINT | Format::D16_UNORM => 2,
+        Format::R8G8B8A8_UNORM
+        | Format::R8G8B8A8_SRGB
+        | Format::B8G8R8A8_UNORM
+        | Format::B8G8R8A8_SRGB
+        | Format::A2B10G10R10_UNORM_PACK32
+        | Format::B10G11R11_UFLOAT_PACK32
+        | Format::R16G16_SFLOAT
+        | Format::R32_SFLOAT
+        | Format::R32_UINT
+        | Format::D32_SFLOAT
+        | Format::X8_D24_UNORM_PACK32 => 4,
+        Format::R16G16B16A16_SFLOAT | Format::R32G32_SFLOAT => 8,
+        Format::R32G32B32A32_SFLOAT | Format::R32G32B32A32_UINT => 16,
+        _ => return None,
+    };
+
+    Some(size)
 }
 
 impl Drop for Image {
     fn drop(&mut self) {
-        unsafe {
-            Context::get()
-                .allocator()
-                .destroy_image(self.handle, &mut self.allocation);
+        let _ = self.gpu_use.try_wait_idle();
+
+        if let Some(ref mut allocation) = self.allocation {
+            untrack_allocation(ResourceKind::Image, vk::Handle::as_raw(self.handle));
+            unsafe {
+                Context::get().allocator().destroy_image(self.handle, allocation);
+            }
         }
     }
 }
 
 impl Buildable for Image {
-    type Builder<'a> = ImageBuilder;
+    type Builder<'a> = ImageBuilder<'a>;
 }
 
 #[derive(utils::Paramters, Clone, Debug)]
-pub struct ImageBuilder {
+#[param(getters, snapshot)]
+pub struct ImageBuilder<'a> {
+    #[required]
+    #[param(default = vk::Format::UNDEFINED)]
     format: Format,
+    #[param(default = Extent2D::new(1, 1))]
     extent: Extent2D,
+    #[param(default = ImageTiling::OPTIMAL)]
     tiling: ImageTiling,
 
+    #[required]
     #[flag]
+    #[param(default = ImageUsage::empty())]
     usage: ImageUsage,
     memory_usage: MemoryUsage,
+    pool: Option<Shared<MemoryPool>>,
+    dedicated: bool,
+    debug_name: Option<String>,
+    #[param(default = ImageLayout::UNDEFINED)]
+    initial_layout: ImageLayout,
+    final_layout: Option<ImageLayout>,
+    #[no_param]
+    sharing: Sharing,
+    #[no_param]
+    data: Option<&'a [u8]>,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
 }
 
-impl Default for ImageBuilder {
-    fn default() -> Self {
-        Self {
-            format: vk::Format::UNDEFINED,
-            extent: Extent2D {
-                width: 1,
-                height: 1,
-            },
-            tiling: ImageTiling::OPTIMAL,
+impl<'a> ImageBuilder<'a> {
+    pub fn sharing(mut self, families: &[QueueFamily]) -> Self {
+        self.sharing = Sharing::new(families);
+        self
+    }
 
-            usage: ImageUsage::empty(),
-            memory_usage: MemoryUsage::Auto,
-        }
+    pub fn data(mut self, data: &'a [u8]) -> Self {
+        self.data = Some(data);
+        self
     }
 }
 
-impl Build for ImageBuilder {
+impl TryBuild for ImageBuilder<'_> {
     type Target = Image;
+    type Error = crate::Error;
 
-    fn build(&self) -> Self::Target {
-        assert!(!self.usage.is_empty(), "Image usage connot be empty");
-        assert_ne!(
-            self.format,
-            vk::Format::UNDEFINED,
-            "Image format connot be UNDEFINED"
-        );
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_image");
+
+        self.check_required()?;
+
+        if self.usage.is_empty() {
+            return Err(crate::Error::InvalidParams("Image usage cannot be empty"));
+        }
+
+        if self.format == Format::UNDEFINED {
+            return Err(crate::Error::InvalidParams("Image format cannot be UNDEFINED"));
+        }
+
+        if self.initial_layout != ImageLayout::UNDEFINED && self.initial_layout != ImageLayout::PREINITIALIZED {
+            return Err(crate::Error::InvalidParams(
+                "Image initial layout needs to be UNDEFINED or PREINITIALIZED",
+            ));
+        }
+
+        if let Some(data) = self.data {
+            let texel_size = format_size(self.format)
+                .ok_or(crate::Error::InvalidParams("Image format is not supported for uploads"))?;
+
+            let size = self.extent.width as vk::DeviceSize * self.extent.height as vk::DeviceSize * texel_size;
+
+            if data.len() as vk::DeviceSize != size {
+                return Err(crate::Error::InvalidParams("Image data does not match the image extent and format"));
+            }
+        }
+
+        let usage = if self.data.is_some() {
+            self.usage | ImageUsage::TRANSFER_DST
+        } else {
+            self.usage
+        };
+
+        let family_indices = self.sharing.family_indices();
 
         let image_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
             .format(self.format)
             .extent(self.extent.to_vk_3d())
             .tiling(self.tiling)
-            .usage(self.usage)
+            .usage(usage)
             .samples(vk::SampleCountFlags::TYPE_1)
             .mip_levels(1)
             .array_layers(1)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+            .sharing_mode(self.sharing.sharing_mode())
+            .queue_family_indices(&family_indices)
+            .initial_layout(self.initial_layout);
+
+        let mut flags = self.memory_usage.vma_flags();
+        if self.dedicated {
+            flags |= vk_mem::AllocationCreateFlags::DEDICATED_MEMORY;
+        }
 
         let alloc_info = vk_mem::AllocationCreateInfo {
             usage: self.memory_usage.as_vma(),
+            flags,
+            required_flags: self.memory_usage.required_flags(),
             ..Default::default()
         };
 
-        let (handle, allocation) = unsafe {
-            Context::get()
-                .allocator()
-                .create_image(&image_info, &alloc_info)
+        let created = unsafe {
+            match self.pool {
+                Some(ref pool) => pool.alloc().create_image(&image_info, &alloc_info),
+                None => Context::try_get()?.allocator().create_image(&image_info, &alloc_info),
+            }
         }
-        .expect("Failed to create image");
+        .map_err(crate::Error::Allocation)?;
+
+        let created = utils::guard(created, |(image, mut allocation)| unsafe {
+            Context::get().allocator().destroy_image(image, &mut allocation);
+        });
+
+        let size = Context::get().allocator().get_allocation_info(&created.1).size;
+
+        let (handle, allocation) = utils::ScopeGuard::into_inner(created);
+
+        track_allocation(
+            LiveAllocation::new(ResourceKind::Image, vk::Handle::as_raw(handle), size, self.debug_name.clone())
+                .with_pool(self.pool.as_deref()),
+        );
 
-        Image {
+        let image = Image {
             handle,
-            allocation,
+            allocation: Some(allocation),
 
             format: self.format,
             extent: self.extent,
+            layout: AtomicI32::new(self.initial_layout.as_raw()),
+            layout_recording: AtomicU64::new(0),
+
+            pool: self.pool.clone(),
+            sharing: self.sharing.clone(),
+            gpu_use: GpuUse::default(),
+        };
+
+        if let Some(data) = self.data {
+            profile_scope!("cvk::upload_image", len = data.len());
+            crate::core::stats::record_staging_allocation();
+
+            let staging_buffer = Buffer::<u8>::builder().staging_buffer().data(data).try_build()?;
+
+            CommandBuffer::try_run_single_use(|recording| {
+                recording.copy_buffer_to_image(&staging_buffer, &image);
+
+                if let Some(final_layout) = self.final_layout {
+                    recording.transition_image_layout(&image, final_layout);
+                }
+            })?;
+        } else if let Some(final_layout) = self.final_layout {
+            CommandBuffer::try_run_single_use(|recording| {
+                recording.transition_image_layout(&image, final_layout)
+            })?;
+        }
+
+        Ok(image)
+    }
+}
+
+// --------------------- Image commands ---------------------
+
+fn full_image_copy<T>(image: &Image, buffer_offset: vk::DeviceSize) -> vk::BufferImageCopy {
+    image_rect_copy::<T>(image, buffer_offset, [0, 0], image.extent)
+}
+
+fn image_rect_copy<T>(
+    image: &Image,
+    buffer_offset: vk::DeviceSize,
+    offset: [u32; 2],
+    extent: Extent2D,
+) -> vk::BufferImageCopy {
+    vk::BufferImageCopy::default()
+        .buffer_offset(buffer_offset * size_of::<T>() as vk::DeviceSize)
+        .image_subresource(
+            vk::ImageSubresourceLayers::default()
+                .aspect_mask(image.aspect_mask())
+                .mip_level(0)
+                .base_array_layer(0)
+                .layer_count(1),
+        )
+        .image_offset(vk::Offset3D {
+            x: offset[0] as i32,
+            y: offset[1] as i32,
+            z: 0,
+        })
+        .image_extent(extent.to_vk_3d())
+}
+
+impl<'a> Recording<'a> {
+    pub fn copy_buffer_to_image<T: Copy>(&mut self, src: impl BufferRegionLike<T> + 'a, image: &'a Image) {
+        debug_assert!(image.size_bytes().is_none_or(|size| src.size() >= size));
+
+        if !matches!(image.layout(), ImageLayout::TRANSFER_DST_OPTIMAL | ImageLayout::GENERAL) {
+            self.transition_image_layout(image, ImageLayout::TRANSFER_DST_OPTIMAL);
+        }
+
+        self.track(src.gpu_use());
+        self.track(&image.gpu_use);
+
+        let region = full_image_copy::<T>(image, src.offset());
+
+        unsafe {
+            Context::cached_device().cmd_copy_buffer_to_image(
+                self.handle(),
+                src.buffer(),
+                image.handle,
+                image.layout(),
+                &[region],
+            );
+        }
+    }
+
+    pub fn copy_buffer_to_image_rect<T: Copy>(
+        &mut self,
+        src: impl BufferRegionLike<T> + 'a,
+        image: &'a Image,
+        offset: [u32; 2],
+        extent: Extent2D,
+    ) {
+        debug_assert!(offset[0] + extent.width <= image.extent.width && offset[1] + extent.height <= image.extent.height);
+
+        if !matches!(image.layout(), ImageLayout::TRANSFER_DST_OPTIMAL | ImageLayout::GENERAL) {
+            self.transition_image_layout(image, ImageLayout::TRANSFER_DST_OPTIMAL);
+        }
+
+        self.track(src.gpu_use());
+        self.track(&image.gpu_use);
+
+        let region = image_rect_copy::<T>(image, src.offset(), offset, extent);
+
+        unsafe {
+            Context::cached_device().cmd_copy_buffer_to_image(
+                self.handle(),
+                src.buffer(),
+                image.handle,
+                image.layout(),
+                &[region],
+            );
+        }
+    }
+
+    pub fn copy_image_to_buffer<T: Copy>(&mut self, image: &'a Image, dst: impl BufferRegionLike<T> + 'a) {
+        debug_assert!(image.size_bytes().is_none_or(|size| dst.size() >= size));
+
+        if !matches!(image.layout(), ImageLayout::TRANSFER_SRC_OPTIMAL | ImageLayout::GENERAL) {
+            self.transition_image_layout(image, ImageLayout::TRANSFER_SRC_OPTIMAL);
+        }
+
+        self.track(&image.gpu_use);
+        self.track(dst.gpu_use());
+
+        let region = full_image_copy::<T>(image, dst.offset());
+
+        unsafe {
+            Context::cached_device().cmd_copy_image_to_buffer(
+                self.handle(),
+                image.handle,
+                image.layout(),
+                dst.buffer(),
+                &[region],
+            );
+        }
+    }
+
+    /// Records a full-image layout transition with stage and access masks derived from both layouts.
+    ///
+    /// The image's tracked layout is updated immediately, so recordings that transition the same image
+    /// must be submitted in the order they were recorded. Transitioning an image that another unsubmitted
+    /// recording already transitioned fails.
+    pub fn transition_image_layout(&mut self, image: &'a Image, new_layout: ImageLayout) {
+        self.try_transition_image_layout(image, new_layout)
+            .unwrap_or_else(|error| panic!("Failed to transition image layout: {error}"))
+    }
+
+    pub fn try_transition_image_layout(&mut self, image: &'a Image, new_layout: ImageLayout) -> crate::Result<()> {
+        let old_layout = image.layout();
+
+        self.try_pipeline_barrier(BarrierBuilder::new().image(
+            image,
+            Access::for_layout(old_layout).writes(),
+            Access::for_layout(new_layout),
+            new_layout,
+        ))
+    }
+
+    pub fn transfer_image_ownership(&mut self, image: &'a Image, src: QueueFamily, dst: QueueFamily) {
+        if image.sharing.is_concurrent() || src == dst {
+            return;
+        }
+
+        self.track(&image.gpu_use);
+
+        let range = vk::ImageSubresourceRange::default()
+            .aspect_mask(image.aspect_mask())
+            .base_mip_level(0)
+            .level_count(vk::REMAINING_MIP_LEVELS)
+            .base_array_layer(0)
+            .layer_count(vk::REMAINING_ARRAY_LAYERS);
+
+        let barrier = vk::ImageMemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+            .dst_access_mask(vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE)
+            .old_layout(image.layout())
+            .new_layout(image.layout())
+            .src_queue_family_index(src.0)
+            .dst_queue_family_index(dst.0)
+            .image(image.handle)
+            .subresource_range(range);
+
+        unsafe {
+            Context::cached_device().cmd_pipeline_barrier(
+                self.handle(),
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier],
+            );
         }
     }
 }
@@ -0,0 +1,147 @@
+use ash::vk::{self, Format};
+use utils::{Buildable, ParamTracker, Shared, TryBuild};
+
+use crate::{Context, Image};
+
+pub use vk::{ComponentMapping, ComponentSwizzle, ImageAspectFlags as ImageAspect, ImageViewType};
+
+#[derive(cvk_macros::VkHandle, Debug)]
+pub struct ImageView {
+    handle: vk::ImageView,
+    image: Shared<Image>,
+
+    view_type: ImageViewType,
+    format: Format,
+    range: vk::ImageSubresourceRange,
+}
+
+impl ImageView {
+    #[inline]
+    pub fn image(&self) -> &Shared<Image> {
+        &self.image
+    }
+
+    #[inline]
+    pub const fn view_type(&self) -> ImageViewType {
+        self.view_type
+    }
+
+    #[inline]
+    pub const fn format(&self) -> Format {
+        self.format
+    }
+
+    #[inline]
+    pub const fn aspect(&self) -> ImageAspect {
+        self.range.aspect_mask
+    }
+
+    #[inline]
+    pub const fn subresource_range(&self) -> vk::ImageSubresourceRange {
+        self.range
+    }
+}
+
+impl Drop for ImageView {
+    fn drop(&mut self) {
+        let _ = self.image.gpu_use().try_wait_idle();
+        unsafe {
+            Context::cached_device().destroy_image_view(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for ImageView {
+    type Builder<'a> = ImageViewBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+#[param(getters, snapshot)]
+pub struct ImageViewBuilder {
+    #[required]
+    image: Option<Shared<Image>>,
+    #[param(default = ImageViewType::TYPE_2D)]
+    view_type: ImageViewType,
+    format: Option<Format>,
+    #[flag]
+    #[param(default = ImageAspect::empty())]
+    aspect: ImageAspect,
+    base_mip_level: u32,
+    #[param(default = vk::REMAINING_MIP_LEVELS)]
+    mip_level_count: u32,
+    base_array_layer: u32,
+    #[param(default = vk::REMAINING_ARRAY_LAYERS)]
+    array_layer_count: u32,
+    components: ComponentMapping,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl ImageViewBuilder {
+    pub fn swizzle(mut self, r: ComponentSwizzle, g: ComponentSwizzle, b: ComponentSwizzle, a: ComponentSwizzle) -> Self {
+        self.components = ComponentMapping { r, g, b, a };
+        self
+    }
+}
+
+impl TryBuild for ImageViewBuilder {
+    type Target = ImageView;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        let image = self.image.as_ref().ok_or(crate::Error::InvalidParams("Image view needs an image"))?;
+
+        let format = self.format.unwrap_or(image.format());
+
+        let aspect = if self.aspect.is_empty() {
+            image.aspect_mask()
+        } else {
+            self.aspect
+        };
+
+        if !image.aspect_mask().contains(aspect) {
+            return Err(crate::Error::InvalidParams(
+                "Image view aspect is not part of the image format",
+            ));
+        }
+
+        let range = vk::ImageSubresourceRange::default()
+            .aspect_mask(aspect)
+            .base_mip_level(self.base_mip_level)
+            .level_count(self.mip_level_count)
+            .base_array_layer(self.base_array_layer)
+            .layer_count(self.array_layer_count);
+
+        let view_info = vk::ImageViewCreateInfo::default()
+            .image(image.handle())
+            .view_type(self.view_type)
+            .format(format)
+            .components(self.components)
+            .subresource_range(range);
+
+        let handle = unsafe { Context::cached_device().create_image_view(&view_info, None) }?;
+
+        Ok(ImageView {
+            handle,
+            image: image.clone(),
+
+            view_type: self.view_type,
+            format,
+            range,
+        })
+    }
+}
+
+impl Image {
+    pub fn view(self: &Shared<Self>) -> ImageView {
+        self.try_view()
+            .unwrap_or_else(|error| panic!("Failed to create image view: {error}"))
+    }
+
+    pub fn try_view(self: &Shared<Self>) -> crate::Result<ImageView> {
+        ImageView::builder().image(self.clone()).try_build()
+    }
+}
@@ -1,11 +1,22 @@
+use ash::vk;
 
 #[repr(u32)]
 #[derive(Copy, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MemoryUsage {
     #[default]
     Auto,
     PreferDevice,
     PreferHost,
+    /// Device-local memory that is mapped when the device exposes host-visible device memory, e.g. with
+    /// resizable BAR. Otherwise falls back to unmapped device memory, and initial data goes through a
+    /// staging buffer.
+    PreferDeviceMapped,
+    Upload,
+    Readback,
+    /// Memory that is always both device-local and host-visible, with no fallback. Building fails on
+    /// devices without such a memory type.
+    DeviceLocalMapped,
 }
 
 impl MemoryUsage {
@@ -14,6 +25,87 @@ impl MemoryUsage {
             MemoryUsage::Auto => vk_mem::MemoryUsage::Auto,
             MemoryUsage::PreferDevice => vk_mem::MemoryUsage::AutoPreferDevice,
             MemoryUsage::PreferHost => vk_mem::MemoryUsage::AutoPreferHost,
+            MemoryUsage::PreferDeviceMapped => vk_mem::MemoryUsage::AutoPreferDevice,
+            MemoryUsage::Upload => vk_mem::MemoryUsage::AutoPreferHost,
+            MemoryUsage::Readback => vk_mem::MemoryUsage::AutoPreferHost,
+            MemoryUsage::DeviceLocalMapped => vk_mem::MemoryUsage::AutoPreferDevice,
         }
     }
-}
\ No newline at end of file
+
+    pub(crate) fn is_host_mapped(&self) -> bool {
+        matches!(
+            *self,
+            MemoryUsage::Upload | MemoryUsage::Readback | MemoryUsage::DeviceLocalMapped
+        )
+    }
+
+    pub(crate) fn required_flags(&self) -> vk::MemoryPropertyFlags {
+        match *self {
+            MemoryUsage::DeviceLocalMapped => {
+                vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE
+            }
+            _ => vk::MemoryPropertyFlags::empty(),
+        }
+    }
+
+    pub(crate) fn vma_flags(&self) -> vk_mem::AllocationCreateFlags {
+        match *self {
+            MemoryUsage::PreferDeviceMapped => {
+                vk_mem::AllocationCreateFlags::HOST_ACCESS_SEQUENTIAL_WRITE
+                    | vk_mem::AllocationCreateFlags::HOST_ACCESS_ALLOW_TRANSFER_INSTEAD
+                    | vk_mem::AllocationCreateFlags::MAPPED
+            }
+            MemoryUsage::Upload | MemoryUsage::DeviceLocalMapped => {
+                vk_mem::AllocationCreateFlags::HOST_ACCESS_SEQUENTIAL_WRITE
+                    | vk_mem::AllocationCreateFlags::MAPPED
+            }
+            MemoryUsage::Readback => {
+                vk_mem::AllocationCreateFlags::HOST_ACCESS_RANDOM | vk_mem::AllocationCreateFlags::MAPPED
+            }
+            _ => vk_mem::AllocationCreateFlags::empty(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QueueFamily(pub u32);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Sharing {
+    #[default]
+    Exclusive,
+    Concurrent(Vec<QueueFamily>),
+}
+
+impl Sharing {
+    pub fn new(families: &[QueueFamily]) -> Self {
+        let mut families = families.to_vec();
+        families.sort();
+        families.dedup();
+
+        if families.len() > 1 {
+            Sharing::Concurrent(families)
+        } else {
+            Sharing::Exclusive
+        }
+    }
+
+    #[inline]
+    pub fn is_concurrent(&self) -> bool {
+        matches!(self, Sharing::Concurrent(_))
+    }
+
+    pub(crate) fn sharing_mode(&self) -> vk::SharingMode {
+        match self {
+            Sharing::Exclusive => vk::SharingMode::EXCLUSIVE,
+            Sharing::Concurrent(_) => vk::SharingMode::CONCURRENT,
+        }
+    }
+
+    pub(crate) fn family_indices(&self) -> Vec<u32> {
+        match self {
+            Sharing::Exclusive => vec![],
+            Sharing::Concurrent(families) => families.iter().map(|family| family.0).collect(),
+        }
+    }
+}
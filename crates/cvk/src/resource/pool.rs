@@ -0,0 +1,168 @@
+use std::{ffi::CString, fmt};
+
+use ash::vk;
+use utils::{Buildable, TryBuild};
+use vk_mem::Alloc;
+
+use crate::profiling::profile_scope;
+use crate::{Context, MemoryUsage};
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum PoolAlgorithm {
+    #[default]
+    Default,
+    Linear,
+    Ring,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PoolStatistics {
+    pub block_count: u32,
+    pub allocation_count: u32,
+    pub block_bytes: vk::DeviceSize,
+    pub allocation_bytes: vk::DeviceSize,
+}
+
+#[derive(utils::Share)]
+pub struct MemoryPool {
+    pool: vk_mem::AllocatorPool,
+    name: Option<CString>,
+    algorithm: PoolAlgorithm,
+}
+
+impl MemoryPool {
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().and_then(|name| name.to_str().ok())
+    }
+
+    #[inline]
+    pub const fn algorithm(&self) -> PoolAlgorithm {
+        self.algorithm
+    }
+
+    pub fn statistics(&self) -> crate::Result<PoolStatistics> {
+        let stats = self.pool.get_statistics()?;
+
+        Ok(PoolStatistics {
+            block_count: stats.blockCount,
+            allocation_count: stats.allocationCount,
+            block_bytes: stats.blockBytes,
+            allocation_bytes: stats.allocationBytes,
+        })
+    }
+
+    pub(crate) fn alloc(&self) -> &vk_mem::AllocatorPool {
+        &self.pool
+    }
+}
+
+impl fmt::Debug for MemoryPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryPool")
+            .field("name", &self.name)
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
+
+impl Buildable for MemoryPool {
+    type Builder<'a> = MemoryPoolBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Default, Debug)]
+#[param(getters)]
+pub struct MemoryPoolBuilder {
+    name: Option<String>,
+    memory_usage: MemoryUsage,
+    algorithm: PoolAlgorithm,
+    block_size: vk::DeviceSize,
+    min_block_count: usize,
+    max_block_count: usize,
+    lazily_allocated: bool,
+    mapped_data: bool,
+}
+
+impl TryBuild for MemoryPoolBuilder {
+    type Target = MemoryPool;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        profile_scope!("cvk::build_memory_pool");
+
+        let name = self
+            .name
+            .as_ref()
+            .map(|name| CString::new(name.as_str()))
+            .transpose()
+            .map_err(|_| crate::Error::InvalidParams("Memory pool name must not contain a nul byte"))?;
+
+        let flags = if self.mapped_data && !self.memory_usage.is_host_mapped() {
+            vk_mem::AllocationCreateFlags::HOST_ACCESS_RANDOM | vk_mem::AllocationCreateFlags::MAPPED
+        } else {
+            self.memory_usage.vma_flags()
+        };
+
+        let (usage, required_flags) = if self.lazily_allocated {
+            (vk_mem::MemoryUsage::GpuLazy, vk::MemoryPropertyFlags::LAZILY_ALLOCATED)
+        } else {
+            (self.memory_usage.as_vma(), self.memory_usage.required_flags())
+        };
+
+        let alloc_info = vk_mem::AllocationCreateInfo {
+            usage,
+            flags,
+            required_flags,
+            ..Default::default()
+        };
+
+        let context = Context::try_get()?;
+
+        let memory_type_index = unsafe {
+            context
+                .allocator()
+                .find_memory_type_index(u32::MAX, &alloc_info)
+        }
+        .map_err(|_| crate::Error::Unsupported(String::from("No memory type matches the memory pool requirements")))?;
+
+        let (pool_flags, min_block_count, max_block_count) = match self.algorithm {
+            PoolAlgorithm::Default => (
+                vk_mem::AllocatorPoolCreateFlags::empty(),
+                self.min_block_count,
+                self.max_block_count,
+            ),
+            PoolAlgorithm::Linear => (
+                vk_mem::AllocatorPoolCreateFlags::LINEAR_ALGORITHM,
+                self.min_block_count,
+                self.max_block_count,
+            ),
+            PoolAlgorithm::Ring => (vk_mem::AllocatorPoolCreateFlags::LINEAR_ALGORITHM, 1, 1),
+        };
+
+        if self.algorithm == PoolAlgorithm::Ring && self.block_size == 0 {
+            return Err(crate::Error::InvalidParams("Ring memory pools need an explicit block size"));
+        }
+
+        let pool_info = vk_mem::PoolCreateInfo {
+            memory_type_index,
+            flags: pool_flags,
+            block_size: self.block_size,
+            min_block_count,
+            max_block_count,
+            ..Default::default()
+        };
+
+        let pool = context
+            .shared_allocator()
+            .create_pool(&pool_info)
+            .map_err(crate::Error::Allocation)?;
+
+        pool.set_name(name.as_deref());
+
+        Ok(MemoryPool {
+            pool,
+            name,
+            algorithm: self.algorithm,
+        })
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::{HashMap, HashSet};
+
+use ash::vk;
+use parking_lot::Mutex;
+use utils::{Buildable, Shared, TryBuild, WeakShared};
+
+use crate::{
+    Buffer, BufferRegionLike, BufferUsage, Context, GpuUse, Image, ImageLayout, MemoryUsage, Recording,
+    TimelineValue, VkHandle,
+};
+
+type ReadbackCallback = Box<dyn FnOnce(&[u8]) + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReadbackHandle(u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReadbackStatus {
+    Recorded,
+    Submitted,
+    Complete,
+    Abandoned,
+}
+
+/// Tracks whether the recording a readback was scheduled in got submitted. The recording retains the
+/// token, so once it is gone without the buffer having been used by a submission, the readback can never
+/// complete.
+pub(crate) struct ReadbackTicket {
+    gpu_use: GpuUse,
+    recorded_after: TimelineValue,
+    recording: WeakShared<()>,
+}
+
+impl ReadbackTicket {
+    pub(crate) fn new(gpu_use: &GpuUse, recording: &Shared<()>) -> Self {
+        Self {
+            gpu_use: gpu_use.clone(),
+            recorded_after: gpu_use.last(),
+            recording: Shared::downgrade(recording),
+        }
+    }
+
+    pub(crate) fn status(&self) -> ReadbackStatus {
+        let last_use = self.gpu_use.last();
+
+        if last_use <= self.recorded_after {
+            match self.recording.strong_count() {
+                0 => ReadbackStatus::Abandoned,
+                _ => ReadbackStatus::Recorded,
+            }
+        } else if last_use.is_complete() {
+            ReadbackStatus::Complete
+        } else {
+            ReadbackStatus::Submitted
+        }
+    }
+}
+
+/// Picks the smallest buffer that fits `size`.
+pub(crate) fn best_fit(capacities: impl IntoIterator<Item = vk::DeviceSize>, size: vk::DeviceSize) -> Option<usize> {
+    capacities
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, capacity)| capacity >= size)
+        .min_by_key(|&(_, capacity)| capacity)
+        .map(|(i, _)| i)
+}
+
+struct PendingReadback {
+    id: u64,
+    buffer: Buffer<u8>,
+    size: vk::DeviceSize,
+    ticket: ReadbackTicket,
+    callback: Option<ReadbackCallback>,
+}
+
+#[derive(Default)]
+struct ReadbackState {
+    next_id: u64,
+    free: Vec<Buffer<u8>>,
+    pending: Vec<PendingReadback>,
+    completed: HashMap<u64, Vec<u8>>,
+    abandoned: HashSet<u64>,
+}
+
+#[derive(Default)]
+pub struct ReadbackQueue {
+    state: Mutex<ReadbackState>,
+}
+
+impl ReadbackQueue {
+    const MIN_BUFFER_SIZE: vk::DeviceSize = 64 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+
+    pub fn pooled_bytes(&self) -> vk::DeviceSize {
+        self.state.lock().free.iter().map(|buffer| buffer.size()).sum()
+    }
+
+    pub fn trim(&self) {
+        self.state.lock().free.clear();
+    }
+
+    pub fn read_buffer<'a, T: Copy>(
+        &'a self,
+        recording: &mut Recording<'a>,
+        src: impl BufferRegionLike<T> + 'a,
+    ) -> crate::Result<ReadbackHandle> {
+        self.schedule_buffer(recording, src, None)
+    }
+
+    pub fn read_buffer_with<'a, T: Copy>(
+        &'a self,
+        recording: &mut Recording<'a>,
+        src: impl BufferRegionLike<T> + 'a,
+        callback: impl FnOnce(&[u8]) + Send + 'static,
+    ) -> crate::Result<ReadbackHandle> {
+        self.schedule_buffer(recording, src, Some(Box::new(callback)))
+    }
+
+    pub fn read_image<'a>(&'a self, recording: &mut Recording<'a>, image: &'a Image) -> crate::Result<ReadbackHandle> {
+        self.schedule_image(recording, image, None)
+    }
+
+    pub fn read_image_with<'a>(
+        &'a self,
+        recording: &mut Recording<'a>,
+        image: &'a Image,
+        callback: impl FnOnce(&[u8]) + Send + 'static,
+    ) -> crate::Result<ReadbackHandle> {
+        self.schedule_image(recording, image, Some(Box::new(callback)))
+    }
+
+    /// Delivers completed readbacks. Readbacks whose recording was dropped without being submitted are
+    /// discarded, and waiting on them returns an error.
+    pub fn poll(&self) {
+        let callbacks = {
+            let mut state = self.state.lock();
+            let mut callbacks = vec![];
+            let mut i = 0;
+
+            while i < state.pending.len() {
+                match state.pending[i].ticket.status() {
+                    ReadbackStatus::Recorded | ReadbackStatus::Submitted => {
+                        i += 1;
+                        continue;
+                    }
+                    ReadbackStatus::Abandoned => {
+                        let readback = state.pending.swap_remove(i);
+                        state.abandoned.insert(readback.id);
+                        state.free.push(readback.buffer);
+                        continue;
+                    }
+                    ReadbackStatus::Complete => {}
+                }
+
+                let readback = state.pending.swap_remove(i);
+                let data = match readback.buffer.try_invalidate() {
+                    Ok(()) => readback
+                        .buffer
+                        .mapped()
+                        .map(|data| data[..readback.size as usize].to_vec())
+                        .unwrap_or_default(),
+                    Err(_) => vec![],
+                };
+
+                match readback.callback {
+                    Some(callback) => callbacks.push((callback, data)),
+                    None => {
+                        state.completed.insert(readback.id, data);
+                    }
+                }
+
+                state.free.push(readback.buffer);
+            }
+
+            callbacks
+        };
+
+        for (callback, data) in callbacks {
+            callback(&data);
+        }
+    }
+
+    pub fn is_ready(&self, handle: ReadbackHandle) -> bool {
+        self.poll();
+        self.state.lock().completed.contains_key(&handle.0)
+    }
+
+    pub fn try_take(&self, handle: ReadbackHandle) -> Option<Vec<u8>> {
+        self.poll();
+        self.state.lock().completed.remove(&handle.0)
+    }
+
+    pub fn wait(&self, handle: ReadbackHandle) -> Vec<u8> {
+        self.try_wait(handle)
+            .unwrap_or_else(|error| panic!("Failed to wait for readback: {error}"))
+    }
+
+    pub fn try_wait(&self, handle: ReadbackHandle) -> crate::Result<Vec<u8>> {
+        self.poll();
+
+        let value = {
+            let mut state = self.state.lock();
+
+            if state.abandoned.remove(&handle.0) {
+                return Err(crate::Error::InvalidParams("Readback was recorded but its recording was never submitted"));
+            }
+
+            match state.pending.iter().find(|readback| readback.id == handle.0) {
+                Some(readback) if readback.ticket.status() == ReadbackStatus::Recorded => {
+                    return Err(crate::Error::InvalidParams("Readback has not been submitted yet"));
+                }
+                Some(readback) => Some(readback.buffer.last_gpu_use()),
+                None => None,
+            }
+        };
+
+        if let Some(value) = value {
+            value.try_wait()?;
+        }
+
+        self.try_take(handle)
+            .ok_or(crate::Error::InvalidParams("Readback handle is unknown or was delivered to a callback"))
+    }
+
+    fn acquire_buffer(&self, size: vk::DeviceSize) -> crate::Result<Buffer<u8>> {
+        let mut state = self.state.lock();
+
+        if let Some(i) = best_fit(state.free.iter().map(Buffer::count), size) {
+            return Ok(state.free.swap_remove(i));
+        }
+
+        drop(state);
+
+        crate::core::stats::record_staging_allocation();
+
+        Buffer::<u8>::builder()
+            .count(size.next_power_of_two().max(Self::MIN_BUFFER_SIZE))
+            .usage(BufferUsage::TRANSFER_DST)
+            .memory_usage(MemoryUsage::Readback)
+            .debug_name("readback buffer")
+            .try_build()
+    }
+
+    fn push_pending(
+        &self,
+        recording: &mut Recording<'_>,
+        buffer: Buffer<u8>,
+        size: vk::DeviceSize,
+        callback: Option<ReadbackCallback>,
+    ) -> ReadbackHandle {
+        let token = Shared::new(());
+        recording.retain(&token);
+
+        let mut state = self.state.lock();
+
+        let id = state.next_id;
+        state.next_id += 1;
+
+        state.pending.push(PendingReadback {
+            id,
+            ticket: ReadbackTicket::new(buffer.gpu_use(), &token),
+            buffer,
+            size,
+            callback,
+        });
+
+        ReadbackHandle(id)
+    }
+
+    fn schedule_buffer<'a, T: Copy>(
+        &'a self,
+        recording: &mut Recording<'a>,
+        src: impl BufferRegionLike<T> + 'a,
+        callback: Option<ReadbackCallback>,
+    ) -> crate::Result<ReadbackHandle> {
+        let size = src.size();
+        let buffer = self.acquire_buffer(size)?;
+
+        let region = vk::BufferCopy::default()
+            .src_offset(src.offset() * size_of::<T>() as vk::DeviceSize)
+            .dst_offset(0)
+            .size(size);
+
+        unsafe {
+            Context::cached_device().cmd_copy_buffer(recording.handle(), src.buffer(), buffer.handle(), &[region]);
+        }
+
+        recording.track(src.gpu_use());
+        recording.track(buffer.gpu_use());
+
+        Ok(self.push_pending(recording, buffer, size, callback))
+    }
+
+    fn schedule_image<'a>(
+        &'a self,
+        recording: &mut Recording<'a>,
+        image: &'a Image,
+        callback: Option<ReadbackCallback>,
+    ) -> crate::Result<ReadbackHandle> {
+        let size = image
+            .size_bytes()
+            .ok_or(crate::Error::InvalidParams("Image format is not supported for readback"))?;
+        let buffer = self.acquire_buffer(size)?;
+
+        let old_layout = image.layout();
+        if old_layout != ImageLayout::TRANSFER_SRC_OPTIMAL {
+            recording.transition_image_layout(image, ImageLayout::TRANSFER_SRC_OPTIMAL);
+        }
+
+        let region = vk::BufferImageCopy::default()
+            .buffer_offset(0)
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(image.aspect_mask())
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(1),
+            )
+            .image_extent(image.extent().to_vk_3d());
+
+        unsafe {
+            Context::cached_device().cmd_copy_image_to_buffer(
+                recording.handle(),
+                image.handle(),
+                ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.handle(),
+                &[region],
+            );
+        }
+
+        if old_layout != ImageLayout::TRANSFER_SRC_OPTIMAL && old_layout != ImageLayout::UNDEFINED {
+            recording.transition_image_layout(image, old_layout);
+        }
+
+        recording.track(buffer.gpu_use());
+
+        Ok(self.push_pending(recording, buffer, size, callback))
+    }
+}
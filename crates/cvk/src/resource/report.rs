@@ -0,0 +1,254 @@
+use std::{backtrace::{Backtrace, BacktraceStatus}, collections::BTreeMap, fmt, fmt::Write, path::Path, sync::Arc};
+
+use ash::vk;
+use parking_lot::Mutex;
+
+use crate::MemoryPool;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Buffer,
+    Image,
+    Shader,
+    Pipeline,
+    PipelineLayout,
+    Sampler,
+    DescriptorSet,
+}
+
+#[derive(Clone, Debug)]
+pub struct LiveAllocation {
+    pub kind: ResourceKind,
+    pub handle: u64,
+    pub size: vk::DeviceSize,
+    pub name: Option<String>,
+    pub pool: Option<String>,
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+impl LiveAllocation {
+    pub(crate) fn new(kind: ResourceKind, handle: u64, size: vk::DeviceSize, name: Option<String>) -> Self {
+        // Only captured when RUST_BACKTRACE or RUST_LIB_BACKTRACE opts in, since capturing on every
+        // allocation is too slow for debug builds.
+        let backtrace = Backtrace::capture();
+
+        Self {
+            kind,
+            handle,
+            size,
+            name,
+            pool: None,
+            backtrace: (backtrace.status() == BacktraceStatus::Captured).then(|| Arc::new(backtrace)),
+        }
+    }
+
+    pub(crate) fn with_pool(mut self, pool: Option<&MemoryPool>) -> Self {
+        self.pool = pool.map(|pool| match pool.name() {
+            Some(name) => name.to_string(),
+            None => format!("pool@{:p}", pool),
+        });
+        self
+    }
+
+    fn node_id(&self) -> String {
+        format!("{:?}_{:x}", self.kind, self.handle)
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct ResourceStats {
+    pub count: usize,
+    pub bytes: vk::DeviceSize,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct AllocationReport {
+    pub buffers: ResourceStats,
+    pub images: ResourceStats,
+    pub shaders: ResourceStats,
+    pub pipelines: ResourceStats,
+    pub pipeline_layouts: ResourceStats,
+    pub samplers: ResourceStats,
+    pub descriptor_sets: ResourceStats,
+    pub live: Vec<LiveAllocation>,
+}
+
+impl AllocationReport {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cvk_objects {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        let pools = self
+            .live
+            .iter()
+            .filter_map(|allocation| allocation.pool.as_deref())
+            .collect::<std::collections::BTreeSet<_>>();
+
+        for pool in &pools {
+            let _ = writeln!(dot, "    \"{}\" [shape=cylinder];", escape(pool));
+        }
+
+        for allocation in &self.live {
+            let tooltip = allocation
+                .backtrace
+                .as_ref()
+                .map(|backtrace| backtrace.to_string())
+                .unwrap_or_default();
+
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{:?}\\n{}\\n{} bytes\", tooltip=\"{}\"];",
+                allocation.node_id(),
+                allocation.kind,
+                escape(allocation.name.as_deref().unwrap_or("<unnamed>")),
+                allocation.size,
+                escape(&tooltip),
+            );
+
+            if let Some(pool) = &allocation.pool {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\";", allocation.node_id(), escape(pool));
+            }
+        }
+
+        dot += "}\n";
+        dot
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+
+        for (key, stats) in [
+            ("buffers", self.buffers),
+            ("images", self.images),
+            ("shaders", self.shaders),
+            ("pipelines", self.pipelines),
+            ("pipeline_layouts", self.pipeline_layouts),
+            ("samplers", self.samplers),
+            ("descriptor_sets", self.descriptor_sets),
+        ] {
+            let _ = writeln!(json, "  \"{key}\": {{ \"count\": {}, \"bytes\": {} }},", stats.count, stats.bytes);
+        }
+
+        json += "  \"live\": [";
+
+        for (i, allocation) in self.live.iter().enumerate() {
+            let optional = |value: Option<String>| match value {
+                Some(value) => format!("\"{}\"", escape(&value)),
+                None => String::from("null"),
+            };
+
+            let _ = write!(
+                json,
+                "{}\n    {{ \"kind\": \"{:?}\", \"handle\": \"0x{:x}\", \"name\": {}, \"size\": {}, \"pool\": {}, \"backtrace\": {} }}",
+                if i == 0 { "" } else { "," },
+                allocation.kind,
+                allocation.handle,
+                optional(allocation.name.clone()),
+                allocation.size,
+                optional(allocation.pool.clone()),
+                optional(allocation.backtrace.as_ref().map(|backtrace| backtrace.to_string())),
+            );
+        }
+
+        json += "\n  ]\n}\n";
+        json
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+
+        let contents = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dot") | Some("gv") => self.to_dot(),
+            _ => self.to_json(),
+        };
+
+        std::fs::write(path, contents).map_err(|error| crate::Error::Io(path.to_path_buf(), error))
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => {}
+            '\t' => escaped += "\\t",
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+impl fmt::Display for AllocationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Buffers: {} ({} bytes)", self.buffers.count, self.buffers.bytes)?;
+        writeln!(f, "Images: {} ({} bytes)", self.images.count, self.images.bytes)?;
+        writeln!(f, "Shaders: {} ({} bytes)", self.shaders.count, self.shaders.bytes)?;
+        writeln!(f, "Pipelines: {}", self.pipelines.count)?;
+        writeln!(f, "Pipeline layouts: {}", self.pipeline_layouts.count)?;
+        writeln!(f, "Samplers: {}", self.samplers.count)?;
+        writeln!(f, "Descriptor sets: {}", self.descriptor_sets.count)?;
+
+        for allocation in &self.live {
+            writeln!(
+                f,
+                "  {:?} 0x{:x} '{}': {} bytes",
+                allocation.kind,
+                allocation.handle,
+                allocation.name.as_deref().unwrap_or("<unnamed>"),
+                allocation.size
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+static LIVE_ALLOCATIONS: Mutex<BTreeMap<(ResourceKind, u64), LiveAllocation>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn track_allocation(allocation: LiveAllocation) {
+    LIVE_ALLOCATIONS
+        .lock()
+        .insert((allocation.kind, allocation.handle), allocation);
+}
+
+pub(crate) fn untrack_allocation(kind: ResourceKind, handle: u64) {
+    LIVE_ALLOCATIONS.lock().remove(&(kind, handle));
+}
+
+pub(crate) fn allocation_report() -> AllocationReport {
+    let live_allocations = LIVE_ALLOCATIONS.lock();
+
+    let mut report = AllocationReport {
+        live: live_allocations.values().cloned().collect(),
+        ..Default::default()
+    };
+
+    for allocation in &report.live {
+        let stats = match allocation.kind {
+            ResourceKind::Buffer => &mut report.buffers,
+            ResourceKind::Image => &mut report.images,
+            ResourceKind::Shader => &mut report.shaders,
+            ResourceKind::Pipeline => &mut report.pipelines,
+            ResourceKind::PipelineLayout => &mut report.pipeline_layouts,
+            ResourceKind::Sampler => &mut report.samplers,
+            ResourceKind::DescriptorSet => &mut report.descriptor_sets,
+        };
+
+        stats.count += 1;
+        stats.bytes += allocation.size;
+    }
+
+    report
+}
@@ -0,0 +1,140 @@
+use ash::vk;
+use utils::{Buildable, ParamTracker, TryBuild};
+
+use crate::Context;
+use crate::resource::report::{LiveAllocation, ResourceKind, track_allocation, untrack_allocation};
+
+pub use vk::{BorderColor, CompareOp, Filter, SamplerAddressMode, SamplerMipmapMode};
+
+#[derive(cvk_macros::VkHandle, utils::Share, Debug)]
+pub struct Sampler {
+    handle: vk::Sampler,
+    anisotropy: Option<f32>,
+    compare_op: Option<CompareOp>,
+}
+
+impl Sampler {
+    #[inline]
+    pub const fn anisotropy(&self) -> Option<f32> {
+        self.anisotropy
+    }
+
+    #[inline]
+    pub const fn compare_op(&self) -> Option<CompareOp> {
+        self.compare_op
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        untrack_allocation(ResourceKind::Sampler, vk::Handle::as_raw(self.handle));
+        unsafe {
+            Context::cached_device().destroy_sampler(self.handle, None);
+        }
+    }
+}
+
+impl Buildable for Sampler {
+    type Builder<'a> = SamplerBuilder;
+}
+
+#[derive(utils::Paramters, Clone, Debug)]
+#[param(getters, snapshot)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SamplerBuilder {
+    #[param(default = Filter::LINEAR)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    mag_filter: Filter,
+    #[param(default = Filter::LINEAR)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    min_filter: Filter,
+    #[param(default = SamplerMipmapMode::LINEAR)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    mipmap_mode: SamplerMipmapMode,
+    #[param(default = SamplerAddressMode::REPEAT)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    address_mode_u: SamplerAddressMode,
+    #[param(default = SamplerAddressMode::REPEAT)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    address_mode_v: SamplerAddressMode,
+    #[param(default = SamplerAddressMode::REPEAT)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    address_mode_w: SamplerAddressMode,
+    anisotropy: Option<f32>,
+    mip_lod_bias: f32,
+    min_lod: f32,
+    #[param(default = vk::LOD_CLAMP_NONE)]
+    max_lod: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw_option"))]
+    compare_op: Option<CompareOp>,
+    #[param(default = BorderColor::FLOAT_TRANSPARENT_BLACK)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::raw"))]
+    border_color: BorderColor,
+    unnormalized_coordinates: bool,
+
+    #[param(tracker)]
+    tracker: ParamTracker,
+}
+
+impl SamplerBuilder {
+    pub fn filter(self, filter: Filter) -> Self {
+        self.mag_filter(filter).min_filter(filter)
+    }
+
+    pub fn address_mode(self, address_mode: SamplerAddressMode) -> Self {
+        self.address_mode_u(address_mode)
+            .address_mode_v(address_mode)
+            .address_mode_w(address_mode)
+    }
+}
+
+impl TryBuild for SamplerBuilder {
+    type Target = Sampler;
+    type Error = crate::Error;
+
+    fn try_build(&self) -> crate::Result<Self::Target> {
+        self.check_required()?;
+
+        let anisotropy = match self.anisotropy {
+            Some(anisotropy) => {
+                let max_anisotropy = Context::try_get()?.max_sampler_anisotropy().ok_or(crate::Error::Unsupported(
+                    String::from("Sampler anisotropy is not supported by the device"),
+                ))?;
+
+                Some(anisotropy.clamp(1.0, max_anisotropy))
+            }
+            None => None,
+        };
+
+        if self.min_lod > self.max_lod {
+            return Err(crate::Error::InvalidParams("Sampler min LOD is greater than max LOD"));
+        }
+
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_mode(self.mipmap_mode)
+            .address_mode_u(self.address_mode_u)
+            .address_mode_v(self.address_mode_v)
+            .address_mode_w(self.address_mode_w)
+            .anisotropy_enable(anisotropy.is_some())
+            .max_anisotropy(anisotropy.unwrap_or(1.0))
+            .mip_lod_bias(self.mip_lod_bias)
+            .min_lod(self.min_lod)
+            .max_lod(self.max_lod)
+            .compare_enable(self.compare_op.is_some())
+            .compare_op(self.compare_op.unwrap_or(CompareOp::ALWAYS))
+            .border_color(self.border_color)
+            .unnormalized_coordinates(self.unnormalized_coordinates);
+
+        let handle = unsafe { Context::cached_device().create_sampler(&sampler_info, None) }?;
+
+        track_allocation(LiveAllocation::new(ResourceKind::Sampler, vk::Handle::as_raw(handle), 0, None));
+
+        Ok(Sampler {
+            handle,
+            anisotropy,
+            compare_op: self.compare_op,
+        })
+    }
+}
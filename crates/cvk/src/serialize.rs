@@ -0,0 +1,69 @@
+use ash::vk;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub trait RawVk: Copy {
+    type Raw: Serialize + for<'de> Deserialize<'de>;
+
+    fn to_raw(self) -> Self::Raw;
+    fn from_raw(raw: Self::Raw) -> Self;
+}
+
+macro_rules! impl_raw_vk {
+    ($raw:ty => $($ty:ty),* $(,)?) => {
+        $(
+            impl RawVk for $ty {
+                type Raw = $raw;
+
+                #[inline]
+                fn to_raw(self) -> $raw {
+                    self.as_raw()
+                }
+
+                #[inline]
+                fn from_raw(raw: $raw) -> Self {
+                    <$ty>::from_raw(raw)
+                }
+            }
+        )*
+    };
+}
+
+impl_raw_vk!(i32 =>
+    vk::Format,
+    vk::VertexInputRate,
+    vk::DescriptorType,
+    vk::ColorSpaceKHR,
+    vk::PresentModeKHR,
+    vk::Filter,
+    vk::SamplerMipmapMode,
+    vk::SamplerAddressMode,
+    vk::CompareOp,
+    vk::BorderColor,
+);
+
+impl_raw_vk!(u32 => vk::ShaderStageFlags, vk::ImageUsageFlags);
+
+/// Serializes ash types through their raw Vulkan value, for use with `#[serde(with = "...")]`.
+pub mod raw {
+    use super::*;
+
+    pub fn serialize<T: RawVk, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_raw().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: RawVk, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        T::Raw::deserialize(deserializer).map(T::from_raw)
+    }
+}
+
+pub mod raw_option {
+    use super::*;
+
+    pub fn serialize<T: RawVk, S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(T::to_raw).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: RawVk, D: Deserializer<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+        Option::<T::Raw>::deserialize(deserializer).map(|raw| raw.map(T::from_raw))
+    }
+}
@@ -1,8 +1,39 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::u64;
 
 use ash::vk;
+use parking_lot::Mutex;
+
+use crate::core::stats;
+use crate::profiling::profile_scope;
+use crate::{CommandMarkers, Context, Error, Result};
+
+fn wait_for_fences(fences: &[vk::Fence], timeout: u64) -> Result<()> {
+    let device = Context::cached_device();
+
+    let result = match CommandMarkers::hang_timeout() {
+        Some(hang_timeout) if timeout as u128 > hang_timeout.as_nanos() => {
+            let hang_timeout_ns = hang_timeout.as_nanos() as u64;
+
+            match unsafe { device.wait_for_fences(fences, true, hang_timeout_ns) } {
+                Err(vk::Result::TIMEOUT) => {
+                    CommandMarkers::report_hang(hang_timeout);
+                    unsafe { device.wait_for_fences(fences, true, timeout - hang_timeout_ns) }
+                }
+                result => result,
+            }
+        }
+        _ => unsafe { device.wait_for_fences(fences, true, timeout) },
+    };
+
+    if result == Err(vk::Result::ERROR_DEVICE_LOST) {
+        CommandMarkers::report_device_lost();
+    }
 
-use crate::Context;
+    Ok(result?)
+}
 
 #[derive(cvk_macros::VkHandle)]
 pub struct Fence(vk::Fence);
@@ -10,7 +41,10 @@ pub struct Fence(vk::Fence);
 
 impl Fence {
     pub fn new(signaled: bool) -> Self {
+        Self::try_new(signaled).unwrap_or_else(|error| panic!("Failed to create fence: {error}"))
+    }
 
+    pub fn try_new(signaled: bool) -> Result<Self> {
         let flags = if signaled {
             vk::FenceCreateFlags::SIGNALED
         } else {
@@ -20,13 +54,24 @@ impl Fence {
         let info = vk::FenceCreateInfo::default()
             .flags(flags);
 
-        let handle = unsafe { Context::get_device().create_fence(&info, None) }.expect("Failed to create fence");
+        let handle = unsafe { Context::cached_device().create_fence(&info, None) }?;
 
-        Self(handle)
+        Ok(Self(handle))
     }
 
     pub fn wait_with_timeout(&self, timeout: u64) {
-        unsafe { Context::get_device().wait_for_fences(&[self.0], true, timeout) }.expect("Failed to wait for fence");
+        self.try_wait_with_timeout(timeout).unwrap_or_else(|error| panic!("Failed to wait for fence: {error}"));
+    }
+
+    pub fn try_wait_with_timeout(&self, timeout: u64) -> Result<()> {
+        stats::record_fence_wait();
+        self.try_wait_internal(timeout)
+    }
+
+    /// Waits without counting towards [`SubmitStats`](crate::SubmitStats), for waits cvk does on its own.
+    pub(crate) fn try_wait_internal(&self, timeout: u64) -> Result<()> {
+        profile_scope!("cvk::fence_wait", timeout);
+        wait_for_fences(&[self.0], timeout)
     }
 
     pub fn wait(&self) {
@@ -34,13 +79,18 @@ impl Fence {
     }
 
     pub fn reset(&self) {
-        unsafe { Context::get_device().reset_fences(&[self.0])}.expect("Failed to reset fence");
+        self.try_reset().unwrap_or_else(|error| panic!("Failed to reset fence: {error}"));
+    }
+
+    pub fn try_reset(&self) -> Result<()> {
+        unsafe { Context::cached_device().reset_fences(&[self.0]) }?;
+        Ok(())
     }
 }
 
 impl Drop for Fence {
     fn drop(&mut self) {
-        unsafe { Context::get_device().destroy_fence(self.0, None) };
+        unsafe { Context::cached_device().destroy_fence(self.0, None) };
     }
 }
 
@@ -50,16 +100,127 @@ pub struct Semaphore(vk::Semaphore);
 
 impl Semaphore {
     pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|error| panic!("Failed to create semaphore: {error}"))
+    }
+
+    pub fn try_new() -> Result<Self> {
         let info = vk::SemaphoreCreateInfo::default();
 
-        let handle = unsafe { Context::get_device().create_semaphore(&info, None) }.expect("Failed to create semaphore");
+        let handle = unsafe { Context::cached_device().create_semaphore(&info, None) }?;
 
-        Self(handle)
+        Ok(Self(handle))
     }
 }
 
 impl Drop for Semaphore {
     fn drop(&mut self) {
-        unsafe { Context::get_device().destroy_semaphore(self.0, None) };
+        unsafe { Context::cached_device().destroy_semaphore(self.0, None) };
+    }
+}
+
+// --------------------- Timeline ---------------------
+
+static NEXT_TIMELINE_VALUE: AtomicU64 = AtomicU64::new(1);
+static IN_FLIGHT: Mutex<BTreeMap<u64, vk::Fence>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimelineValue(pub u64);
+
+impl TimelineValue {
+    pub const NONE: TimelineValue = TimelineValue(0);
+
+    pub fn is_complete(self) -> bool {
+        let mut in_flight = IN_FLIGHT.lock();
+
+        let signaled = in_flight
+            .range(..=self.0)
+            .filter(|&(_, &fence)| unsafe { Context::cached_device().get_fence_status(fence) } == Ok(true))
+            .map(|(&value, _)| value)
+            .collect::<Vec<u64>>();
+
+        for value in signaled {
+            in_flight.remove(&value);
+        }
+
+        in_flight.range(..=self.0).next().is_none()
+    }
+
+    pub fn wait(self) {
+        self.try_wait()
+            .unwrap_or_else(|error| panic!("Failed to wait for timeline value {}: {error}", self.0));
+    }
+
+    pub fn try_wait(self) -> Result<()> {
+        let (values, fences): (Vec<u64>, Vec<vk::Fence>) = IN_FLIGHT
+            .lock()
+            .range(..=self.0)
+            .map(|(&value, &fence)| (value, fence))
+            .unzip();
+
+        if fences.is_empty() {
+            return Ok(());
+        }
+
+        profile_scope!("cvk::timeline_wait", value = self.0);
+        stats::record_fence_wait();
+
+        // The lock is released while waiting so submissions on other threads are not blocked behind the GPU.
+        wait_for_fences(&fences, u64::MAX)?;
+
+        let mut in_flight = IN_FLIGHT.lock();
+        for value in values {
+            in_flight.remove(&value);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn track_submission(fence: vk::Fence, submit: impl FnOnce() -> Result<()>) -> Result<TimelineValue> {
+    let mut in_flight = IN_FLIGHT.lock();
+
+    submit()?;
+
+    let value = NEXT_TIMELINE_VALUE.fetch_add(1, Ordering::Relaxed);
+    in_flight.insert(value, fence);
+
+    Ok(TimelineValue(value))
+}
+
+pub(crate) fn finish_submission(value: TimelineValue) {
+    IN_FLIGHT.lock().remove(&value.0);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GpuUse(Arc<AtomicU64>);
+
+impl GpuUse {
+    #[inline]
+    pub fn last(&self) -> TimelineValue {
+        TimelineValue(self.0.load(Ordering::Acquire))
+    }
+
+    #[inline]
+    pub(crate) fn mark(&self, value: TimelineValue) {
+        self.0.fetch_max(value.0, Ordering::AcqRel);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.last().is_complete()
+    }
+
+    pub fn wait_idle(&self) {
+        self.last().wait();
     }
-}
\ No newline at end of file
+
+    pub fn try_wait_idle(&self) -> Result<()> {
+        self.last().try_wait()
+    }
+
+    pub(crate) fn check_idle(&self) -> Result<()> {
+        if self.is_idle() {
+            Ok(())
+        } else {
+            Err(Error::ResourceInUse(self.last()))
+        }
+    }
+}
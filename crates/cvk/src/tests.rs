@@ -0,0 +1,283 @@
+use utils::Shared;
+
+use crate::resource::atlas::ShelfPacker;
+use crate::resource::readback::{ReadbackStatus, ReadbackTicket, best_fit};
+use crate::{
+    DescriptorBinding, DescriptorType, Extent2D, GpuUse, LayoutBinding, PushConstantRange, ShaderReflection, ShaderStage,
+    TimelineValue,
+};
+
+#[test]
+pub fn test_readback_best_fit() {
+    assert_eq!(best_fit([64, 256, 128], 100), Some(2));
+    assert_eq!(best_fit([64, 256, 128], 64), Some(0));
+    assert_eq!(best_fit([64, 256, 128], 257), None);
+    assert_eq!(best_fit([], 1), None);
+}
+
+#[test]
+pub fn test_readback_ticket_submitted() {
+    let gpu_use = GpuUse::default();
+    let recording = Shared::new(());
+    let ticket = ReadbackTicket::new(&gpu_use, &recording);
+
+    assert_eq!(ticket.status(), ReadbackStatus::Recorded);
+
+    // Nothing is in flight for this value, so it counts as complete right away.
+    gpu_use.mark(TimelineValue(u64::MAX - 1));
+    assert_eq!(ticket.status(), ReadbackStatus::Complete);
+
+    drop(recording);
+    assert_eq!(ticket.status(), ReadbackStatus::Complete);
+}
+
+#[test]
+pub fn test_readback_ticket_abandoned() {
+    let gpu_use = GpuUse::default();
+    let recording = Shared::new(());
+    let ticket = ReadbackTicket::new(&gpu_use, &recording);
+
+    drop(recording);
+    assert_eq!(ticket.status(), ReadbackStatus::Abandoned);
+}
+
+#[test]
+pub fn test_readback_ticket_ignores_earlier_uses() {
+    let gpu_use = GpuUse::default();
+    gpu_use.mark(TimelineValue(u64::MAX - 2));
+
+    let recording = Shared::new(());
+    let ticket = ReadbackTicket::new(&gpu_use, &recording);
+
+    assert_eq!(ticket.status(), ReadbackStatus::Recorded);
+
+    drop(recording);
+    assert_eq!(ticket.status(), ReadbackStatus::Abandoned);
+}
+
+// --------------------- Atlas ---------------------
+
+#[test]
+pub fn test_shelf_packer_rejects_double_free() {
+    let mut packer = ShelfPacker::new(Extent2D::new(64, 64));
+
+    let (first, x, _) = packer.allocate(16, 16).unwrap();
+    let (second, _, _) = packer.allocate(16, 16).unwrap();
+    assert_eq!(first, second);
+
+    packer.free(first, x, 16).unwrap();
+    assert!(matches!(packer.free(first, x, 16), Err(crate::Error::InvalidParams(_))));
+}
+
+#[test]
+pub fn test_shelf_packer_rejects_stale_regions() {
+    let mut packer = ShelfPacker::new(Extent2D::new(64, 64));
+
+    let (cleared, x, _) = packer.allocate(16, 16).unwrap();
+    packer.clear();
+    packer.allocate(16, 16).unwrap();
+    assert!(matches!(packer.free(cleared, x, 16), Err(crate::Error::InvalidParams(_))));
+
+    let mut packer = ShelfPacker::new(Extent2D::new(64, 64));
+
+    let (released, x, _) = packer.allocate(16, 16).unwrap();
+    packer.free(released, x, 16).unwrap();
+    let (reused, _, _) = packer.allocate(16, 16).unwrap();
+    assert_ne!(released, reused);
+    assert!(matches!(packer.free(released, x, 16), Err(crate::Error::InvalidParams(_))));
+    packer.free(reused, x, 16).unwrap();
+}
+
+// --------------------- Shader reflection ---------------------
+
+fn spirv(instructions: &[&[u32]]) -> Vec<u32> {
+    let mut words = vec![0x0723_0203, 0x0001_0000, 0, 64, 0];
+    for instruction in instructions {
+        words.push(((instruction.len() as u32) << 16) | instruction[0]);
+        words.extend_from_slice(&instruction[1..]);
+    }
+    words
+}
+
+/// `layout(set = 0, binding = 1) uniform Block { vec4 color; float scale; };`
+const UNIFORM_BLOCK: &[&[u32]] = &[
+    &[22, 1, 32],
+    &[23, 2, 1, 4],
+    &[30, 3, 2, 1],
+    &[72, 3, 0, 35, 0],
+    &[72, 3, 1, 35, 16],
+    &[32, 4, 2, 3],
+    &[59, 4, 5, 2],
+    &[71, 5, 34, 0],
+    &[71, 5, 33, 1],
+];
+
+/// `layout(set = 1, binding = 0) uniform sampler2D textures[4];`
+const SAMPLED_IMAGE_ARRAY: &[&[u32]] = &[
+    &[22, 1, 32],
+    &[25, 10, 1, 1, 0, 0, 0, 1, 0],
+    &[27, 11, 10],
+    &[21, 12, 32, 0],
+    &[43, 12, 13, 4],
+    &[28, 14, 11, 13],
+    &[32, 15, 0, 14],
+    &[59, 15, 16, 0],
+    &[71, 16, 34, 1],
+    &[71, 16, 33, 0],
+];
+
+/// `layout(push_constant) uniform Constants { vec4 tint; float time; };`
+const PUSH_CONSTANT_BLOCK: &[&[u32]] = &[
+    &[22, 1, 32],
+    &[23, 2, 1, 4],
+    &[30, 20, 2, 1],
+    &[72, 20, 0, 35, 0],
+    &[72, 20, 1, 35, 16],
+    &[32, 21, 9, 20],
+    &[59, 21, 22, 9],
+];
+
+#[test]
+pub fn test_reflection_rejects_malformed_code() {
+    assert!(matches!(
+        ShaderReflection::parse(&[0xdead_beef, 0, 0, 0, 0], ShaderStage::VERTEX),
+        Err(crate::Error::InvalidParams(_))
+    ));
+
+    let mut words = spirv(UNIFORM_BLOCK);
+    words.push(8 << 16 | 71);
+    assert!(matches!(
+        ShaderReflection::parse(&words, ShaderStage::VERTEX),
+        Err(crate::Error::InvalidParams(_))
+    ));
+
+    let mut words = spirv(UNIFORM_BLOCK);
+    words.push(71);
+    assert!(matches!(
+        ShaderReflection::parse(&words, ShaderStage::VERTEX),
+        Err(crate::Error::InvalidParams(_))
+    ));
+}
+
+#[test]
+pub fn test_reflection_uniform_block() {
+    let reflection = ShaderReflection::parse(&spirv(UNIFORM_BLOCK), ShaderStage::VERTEX).unwrap();
+
+    assert_eq!(
+        reflection.bindings(),
+        &[LayoutBinding::new(
+            0,
+            DescriptorBinding::new(1, DescriptorType::UNIFORM_BUFFER, ShaderStage::VERTEX)
+        )]
+    );
+    assert_eq!(reflection.push_constants(), None);
+}
+
+#[test]
+pub fn test_reflection_sampled_image_array() {
+    let reflection = ShaderReflection::parse(&spirv(SAMPLED_IMAGE_ARRAY), ShaderStage::FRAGMENT).unwrap();
+
+    assert_eq!(
+        reflection.bindings(),
+        &[LayoutBinding::new(
+            1,
+            DescriptorBinding::new(0, DescriptorType::COMBINED_IMAGE_SAMPLER, ShaderStage::FRAGMENT).with_count(4)
+        )]
+    );
+}
+
+#[test]
+pub fn test_reflection_push_constant_block() {
+    let reflection = ShaderReflection::parse(&spirv(PUSH_CONSTANT_BLOCK), ShaderStage::VERTEX).unwrap();
+
+    assert!(reflection.bindings().is_empty());
+    assert_eq!(
+        reflection.push_constants(),
+        Some(PushConstantRange::new(ShaderStage::VERTEX, 0, 20))
+    );
+}
+
+#[test]
+pub fn test_reflection_merge() {
+    let vertex = ShaderReflection::parse(
+        &spirv(&[UNIFORM_BLOCK, PUSH_CONSTANT_BLOCK].concat()),
+        ShaderStage::VERTEX,
+    )
+    .unwrap();
+    let fragment = ShaderReflection::parse(
+        &spirv(&[UNIFORM_BLOCK, SAMPLED_IMAGE_ARRAY].concat()),
+        ShaderStage::FRAGMENT,
+    )
+    .unwrap();
+
+    let merged = ShaderReflection::merge([&vertex, &fragment]).unwrap();
+
+    assert_eq!(
+        merged.bindings(),
+        &[
+            LayoutBinding::new(
+                0,
+                DescriptorBinding::new(
+                    1,
+                    DescriptorType::UNIFORM_BUFFER,
+                    ShaderStage::VERTEX | ShaderStage::FRAGMENT
+                )
+            ),
+            LayoutBinding::new(
+                1,
+                DescriptorBinding::new(0, DescriptorType::COMBINED_IMAGE_SAMPLER, ShaderStage::FRAGMENT).with_count(4)
+            ),
+        ]
+    );
+    assert_eq!(
+        merged.push_constants(),
+        Some(PushConstantRange::new(ShaderStage::VERTEX, 0, 20))
+    );
+
+    let conflicting = ShaderReflection::parse(
+        &spirv(&[UNIFORM_BLOCK, &[&[71, 3, 3]]].concat()),
+        ShaderStage::FRAGMENT,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        ShaderReflection::merge([&vertex, &conflicting]),
+        Err(crate::Error::InvalidParams(_))
+    ));
+}
+
+#[test]
+pub fn test_reflection_apply_overrides() {
+    let mut reflection = ShaderReflection::parse(
+        &spirv(&[SAMPLED_IMAGE_ARRAY, UNIFORM_BLOCK, PUSH_CONSTANT_BLOCK].concat()),
+        ShaderStage::VERTEX,
+    )
+    .unwrap();
+
+    let dynamic = LayoutBinding::new(
+        0,
+        DescriptorBinding::new(1, DescriptorType::UNIFORM_BUFFER_DYNAMIC, ShaderStage::VERTEX),
+    );
+    let storage = LayoutBinding::new(
+        0,
+        DescriptorBinding::new(0, DescriptorType::STORAGE_BUFFER, ShaderStage::FRAGMENT),
+    );
+
+    reflection.apply_overrides(&[dynamic, storage], &[]);
+
+    assert_eq!(
+        reflection.bindings(),
+        &[
+            storage,
+            dynamic,
+            LayoutBinding::new(
+                1,
+                DescriptorBinding::new(0, DescriptorType::COMBINED_IMAGE_SAMPLER, ShaderStage::VERTEX).with_count(4)
+            ),
+        ]
+    );
+    assert!(reflection.push_constants().is_some());
+
+    reflection.apply_overrides(&[], &[PushConstantRange::new(ShaderStage::VERTEX, 0, 64)]);
+    assert_eq!(reflection.push_constants(), None);
+}
@@ -1,24 +1,33 @@
 use proc_macro::TokenStream;
-use quote::quote;
 
 mod macro_impl;
 
-#[proc_macro_derive(Paramters, attributes(no_param, flag, vec))]
+#[proc_macro_derive(Paramters, attributes(param, no_param, required, flag, vec))]
 pub fn derive_parameters(input: TokenStream) -> TokenStream {
-    let parse_result = syn::parse::<syn::ItemStruct>(input);
+    let item = syn::parse_macro_input!(input as syn::Item);
 
-    match parse_result {
-        Ok(item) => macro_impl::derive_parameters(&item).into(),
-        Err(_) => quote! { compile_error!("Item needs to be a struct") }.into(),
-    } 
+    match &item {
+        syn::Item::Struct(item) => macro_impl::derive_parameters(item),
+        item => Err(macro_impl::unsupported_item(item, "Paramters can only be derived for structs")),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
 }
 
 #[proc_macro_derive(Share)]
 pub fn derive_share(input: TokenStream) -> TokenStream {
-    let parse_result = syn::parse::<syn::Item>(input);
+    let item = syn::parse_macro_input!(input as syn::Item);
 
-    match parse_result {
-        Ok(item) => macro_impl::derive_share(&item).into(),
-        Err(_) => quote! { compile_error!("Item needs to be a struct") }.into(),
-    } 
-}
\ No newline at end of file
+    macro_impl::derive_share(&item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ShareMut)]
+pub fn derive_share_mut(input: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(input as syn::Item);
+
+    macro_impl::derive_share_mut(&item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
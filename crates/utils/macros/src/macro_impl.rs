@@ -1,85 +1,209 @@
-use std::str::FromStr;
-
 use proc_macro2::TokenStream;
-use quote::{ToTokens, quote, quote_spanned};
+use quote::{format_ident, quote};
 use syn::spanned::Spanned;
 
-pub fn derive_parameters(item: &syn::ItemStruct) -> TokenStream {
+pub fn unsupported_item(item: &syn::Item, msg: &str) -> syn::Error {
+    let span = match item {
+        syn::Item::Enum(item) => item.enum_token.span,
+        syn::Item::Union(item) => item.union_token.span,
+        syn::Item::Struct(item) => item.struct_token.span,
+        item => item.span(),
+    };
+
+    syn::Error::new(span, msg)
+}
+
+pub fn derive_parameters(item: &syn::ItemStruct) -> syn::Result<TokenStream> {
     let item_ident = &item.ident;
 
+    if !matches!(item.fields, syn::Fields::Named(_)) {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "Paramters can only be derived for structs with named fields",
+        ));
+    }
+
     let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
 
     let mut field_functions: Vec<TokenStream> = vec![];
+    let mut field_defaults: Vec<TokenStream> = vec![];
+    let mut generate_default = false;
+
+    let tracker_ident = item
+        .fields
+        .iter()
+        .find(|field| is_tracker_field(field))
+        .and_then(|field| field.ident.clone());
+
+    let mut generate_getters = false;
+    let mut generate_snapshot = false;
 
-    'outer: for field in &item.fields {
+    for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("param")) {
+        let parse_result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("getters") {
+                generate_getters = true;
+                Ok(())
+            } else if meta.path.is_ident("snapshot") {
+                generate_snapshot = true;
+                Ok(())
+            } else {
+                Err(meta.error("Unsupported 'param' argument on a struct"))
+            }
+        });
+
+        parse_result?;
+    }
+
+    let mut required_fields: Vec<String> = vec![];
+    let mut snapshot_fields: Vec<TokenStream> = vec![];
+
+    for field in &item.fields {
         let field_type = field.ty.clone();
         let field_ident = field.ident.clone().unwrap();
         let mut flag_add_ident = None;
 
         let mut vec_push_ident = None;
 
+        let mut no_param = false;
+        let mut no_into = false;
+        let mut required = None;
+        let mut default_expr = None;
+
         for field_attr in &field.attrs {
-            if field_attr.path().is_ident("no_param") {
-                continue 'outer;
+            if field_attr.path().is_ident("param") {
+                let parse_result = field_attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("default") {
+                        default_expr = Some(meta.value()?.parse::<syn::Expr>()?);
+                        Ok(())
+                    } else if meta.path.is_ident("no_into") {
+                        no_into = true;
+                        Ok(())
+                    } else if meta.path.is_ident("tracker") {
+                        no_param = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("Unsupported 'param' argument"))
+                    }
+                });
+
+                parse_result?;
+            } else if field_attr.path().is_ident("no_param") {
+                no_param = true;
+            } else if field_attr.path().is_ident("required") {
+                field_attr.meta.require_path_only()?;
+                required = Some(field_attr);
             } else if field_attr.path().is_ident("flag") {
-                flag_add_ident = match field_attr.parse_args::<syn::Ident>() {
-                    Ok(ident) => Some(ident.to_token_stream()),
-                    Err(_) => TokenStream::from_str(format!("add_{}", field_ident).as_str()).ok(),
-                }
+                flag_add_ident = Some(match field_attr.meta {
+                    syn::Meta::Path(_) => format_ident!("add_{}", field_ident),
+                    _ => field_attr.parse_args::<syn::Ident>()?,
+                });
             } else if field_attr.path().is_ident("vec") {
-                vec_push_ident = if let syn::Type::Path(syn::TypePath {
-                    path: syn::Path { ref segments, .. },
-                    ..
-                }) = field_type
-                {
-                    if let Some(syn::PathSegment {
-                        ident,
-                        arguments:
-                            syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
-                                args,
-                                ..
-                            }),
-                    }) = segments.last()
-                    {
-                        if ident.to_string() != "Vec" {
-                            return quote_spanned! { field_attr.meta.span() => compile_error!("Attribute 'vec' on a non-'Vec' field"); };
-                        }
-
-                        let element_type = match args.first() {
-                            Some(syn::GenericArgument::Type(ty)) => ty.clone(),
-                            _ => {
-                                return quote_spanned! { field_attr.meta.span() => compile_error!("Could not identify element type"); };
-                            }
-                        };
-
-                        match field_attr.parse_args::<syn::Ident>() {
-                            Ok(ident) => Some((element_type, ident.to_token_stream())),
-                            Err(_) => {
-                                TokenStream::from_str(format!("push_{}", field_ident).as_str())
-                                    .ok()
-                                    .map(|id| (element_type, id))
-                            }
-                        }
-                    } else {
-                        return quote_spanned! { field_attr.meta.span() => compile_error!("Attribute 'vec' on a non-'Vec' field"); };
+                let element_type = generic_inner_type(&field_type, "Vec").ok_or_else(|| {
+                    syn::Error::new_spanned(&field.ty, "Attribute 'vec' requires a field of type 'Vec<T>'")
+                })?;
+
+                let push_ident = match field_attr.meta {
+                    syn::Meta::Path(_) => format_ident!("push_{}", field_ident),
+                    _ => field_attr.parse_args::<syn::Ident>()?,
+                };
+
+                vec_push_ident = Some((element_type.clone(), push_ident));
+            }
+        }
+
+        match default_expr {
+            Some(expr) => {
+                generate_default = true;
+                field_defaults.push(quote! { #field_ident: #expr });
+            }
+            None => field_defaults.push(quote! { #field_ident: ::core::default::Default::default() }),
+        }
+
+        if tracker_ident.as_ref() != Some(&field_ident) {
+            if generate_getters {
+                let getter_ident = format_ident!("get_{}", field_ident);
+
+                field_functions.push(quote! {
+                    pub fn #getter_ident(&self) -> &#field_type {
+                        &self.#field_ident
                     }
-                } else {
-                    return quote_spanned! { field_attr.meta.span() => compile_error!("Attribute 'vec' on a non-'Vec' field"); };
-                }
+                });
             }
+
+            let field_name = field_ident.to_string();
+            snapshot_fields.push(quote! { (#field_name, format!("{:?}", self.#field_ident)) });
         }
 
-        field_functions.push(quote! {
-            pub fn #field_ident(mut self, val: impl Into<#field_type>) -> Self {
-                self.#field_ident = val.into();
-                self
+        if no_param {
+            continue;
+        }
+
+        let mark = if let Some(required_attr) = required {
+            let Some(tracker_ident) = tracker_ident.as_ref() else {
+                return Err(syn::Error::new_spanned(
+                    required_attr,
+                    "Attribute 'required' needs a field marked with #[param(tracker)]",
+                ));
+            };
+
+            let index = required_fields.len() as u32;
+            required_fields.push(field_ident.to_string());
+
+            quote! { self.#tracker_ident.set(#index); }
+        } else {
+            TokenStream::new()
+        };
+
+        if let Some(inner_type) = generic_inner_type(&field_type, "Option") {
+            let clear_ident = format_ident!("clear_{}", field_ident);
+
+            if no_into {
+                field_functions.push(quote! {
+                    pub fn #field_ident(mut self, val: #inner_type) -> Self {
+                        self.#field_ident = Some(val);
+                        #mark
+                        self
+                    }
+                });
+            } else {
+                field_functions.push(quote! {
+                    pub fn #field_ident(mut self, val: impl Into<#inner_type>) -> Self {
+                        self.#field_ident = Some(val.into());
+                        #mark
+                        self
+                    }
+                });
             }
-        });
+
+            field_functions.push(quote! {
+                pub fn #clear_ident(mut self) -> Self {
+                    self.#field_ident = None;
+                    self
+                }
+            });
+        } else if no_into {
+            field_functions.push(quote! {
+                pub fn #field_ident(mut self, val: #field_type) -> Self {
+                    self.#field_ident = val;
+                    #mark
+                    self
+                }
+            });
+        } else {
+            field_functions.push(quote! {
+                pub fn #field_ident(mut self, val: impl Into<#field_type>) -> Self {
+                    self.#field_ident = val.into();
+                    #mark
+                    self
+                }
+            });
+        }
 
         if let Some(flag_add_ident) = flag_add_ident {
             field_functions.push(quote! {
                 pub fn #flag_add_ident(mut self, val: impl Into<#field_type>) -> Self {
                     self.#field_ident |= val.into();
+                    #mark
                     self
                 }
             });
@@ -87,19 +211,114 @@ pub fn derive_parameters(item: &syn::ItemStruct) -> TokenStream {
             field_functions.push(quote! {
                 pub fn #id(mut self, val: impl Into<#ty>) -> Self {
                     self.#field_ident.push(val.into());
+                    #mark
                     self
                 }
             });
         }
     }
-    quote! {
+    let default_impl = generate_default.then(|| {
+        quote! {
+            impl #impl_generics ::core::default::Default for #item_ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    Self {
+                        #(#field_defaults),*
+                    }
+                }
+            }
+        }
+    });
+
+    if generate_snapshot {
+        let builder_name = item_ident.to_string();
+
+        field_functions.push(quote! {
+            pub fn params(&self) -> ::utils::ParamsSnapshot {
+                ::utils::ParamsSnapshot::new(#builder_name, vec![#(#snapshot_fields),*])
+            }
+        });
+    }
+
+    if let Some(tracker_ident) = tracker_ident.as_ref() {
+        let indices = 0..required_fields.len() as u32;
+        let builder_name = item_ident.to_string();
+
+        field_functions.push(quote! {
+            pub fn check_required(&self) -> ::core::result::Result<(), ::utils::MissingParamsError> {
+                const REQUIRED: &[&str] = &[#(#required_fields),*];
+                const {
+                    assert!(
+                        REQUIRED.len() <= ::utils::ParamTracker::CAPACITY as usize,
+                        "ParamTracker cannot track more than 64 required parameters",
+                    )
+                };
+
+                let missing: ::std::vec::Vec<&'static str> = [#(#indices),*]
+                    .into_iter()
+                    .filter(|&index| !self.#tracker_ident.is_set(index))
+                    .map(|index| REQUIRED[index as usize])
+                    .collect();
+
+                if missing.is_empty() {
+                    Ok(())
+                } else {
+                    Err(::utils::MissingParamsError::new(#builder_name, missing))
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
         impl #impl_generics #item_ident #ty_generics #where_clause {
             #(#field_functions)*
         }
+
+        #default_impl
+    })
+}
+
+fn is_tracker_field(field: &syn::Field) -> bool {
+    let mut is_tracker = false;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("param")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tracker") {
+                is_tracker = true;
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+
+    is_tracker
+}
+
+fn generic_inner_type<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::Type> {
+    let syn::Type::Path(syn::TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+
+    let segment = path.segments.last()?;
+
+    if segment.ident != name {
+        return None;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments { args, .. })
+            if args.len() == 1 =>
+        {
+            match args.first() {
+                Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
     }
 }
 
-pub fn derive_share(item: &syn::Item) -> TokenStream {
+pub fn derive_share(item: &syn::Item) -> syn::Result<TokenStream> {
     let item_ident;
     let item_generics;
 
@@ -112,12 +331,12 @@ pub fn derive_share(item: &syn::Item) -> TokenStream {
             item_ident = &item.ident;
             item_generics = &item.generics;
         }
-        _ => return quote! { compile_error!("Item needs to be a struct or enum") },
+        item => return Err(unsupported_item(item, "Share can only be derived for structs and enums")),
     }
 
     let (impl_generics, ty_generics, where_clause) = item_generics.split_for_impl();
 
-    quote! {
+    Ok(quote! {
         impl #impl_generics ::utils::Share for #item_ident #ty_generics #where_clause {
             type Internal = #item_ident #ty_generics;
 
@@ -132,6 +351,50 @@ pub fn derive_share(item: &syn::Item) -> TokenStream {
             pub fn share(self) -> ::utils::Shared<#item_ident #ty_generics> {
                 ::utils::Shared::new(self)
             }
+
+            #[inline]
+            pub fn share_cyclic(
+                init: impl FnOnce(&::utils::WeakShared<#item_ident #ty_generics>) -> Self,
+            ) -> ::utils::Shared<#item_ident #ty_generics> {
+                ::utils::Shared::new_cyclic(init)
+            }
+        }
+    })
+}
+
+pub fn derive_share_mut(item: &syn::Item) -> syn::Result<TokenStream> {
+    let item_ident;
+    let item_generics;
+
+    match item {
+        syn::Item::Enum(item) => {
+            item_ident = &item.ident;
+            item_generics = &item.generics;
+        }
+        syn::Item::Struct(item) => {
+            item_ident = &item.ident;
+            item_generics = &item.generics;
         }
+        item => return Err(unsupported_item(item, "ShareMut can only be derived for structs and enums")),
     }
+
+    let (impl_generics, ty_generics, where_clause) = item_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::utils::ShareMut for #item_ident #ty_generics #where_clause {
+            type Internal = #item_ident #ty_generics;
+
+            #[inline]
+            fn share_mut(self) -> ::utils::SharedMut<Self::Internal> {
+                ::utils::Shared::new(::utils::RwLock::new(self))
+            }
+        }
+
+        impl #impl_generics #item_ident #ty_generics #where_clause {
+            #[inline]
+            pub fn share_mut(self) -> ::utils::SharedMut<#item_ident #ty_generics> {
+                ::utils::Shared::new(::utils::RwLock::new(self))
+            }
+        }
+    })
 }
@@ -1,8 +1,44 @@
+use std::fmt;
+
+use crate::Shared;
 
 pub trait Build {
     type Target;
 
     fn build(&self) -> Self::Target;
+
+    #[inline]
+    fn build_shared(&self) -> Shared<Self::Target> {
+        Shared::new(self.build())
+    }
+}
+
+pub trait TryBuild {
+    type Target;
+    type Error;
+
+    fn try_build(&self) -> Result<Self::Target, Self::Error>;
+
+    #[inline]
+    fn try_build_shared(&self) -> Result<Shared<Self::Target>, Self::Error> {
+        self.try_build().map(Shared::new)
+    }
+}
+
+impl<B> Build for B
+where
+    B: TryBuild,
+    B::Error: fmt::Display,
+{
+    type Target = B::Target;
+
+    #[inline]
+    fn build(&self) -> Self::Target {
+        match self.try_build() {
+            Ok(target) => target,
+            Err(error) => panic!("{error}"),
+        }
+    }
 }
 
 pub trait Buildable: Sized {
@@ -15,4 +51,81 @@ pub trait Buildable: Sized {
     fn build() -> Self {
         Self::builder().build()
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamTracker(u64);
+
+impl ParamTracker {
+    pub const CAPACITY: u32 = u64::BITS;
+
+    #[inline]
+    pub fn set(&mut self, index: u32) {
+        self.0 |= 1 << index;
+    }
+
+    #[inline]
+    pub const fn is_set(&self, index: u32) -> bool {
+        self.0 & (1 << index) != 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingParamsError {
+    pub builder: &'static str,
+    pub params: Vec<&'static str>,
+}
+
+impl MissingParamsError {
+    pub fn new(builder: &'static str, params: Vec<&'static str>) -> Self {
+        Self { builder, params }
+    }
+}
+
+impl fmt::Display for MissingParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Missing required parameters in {}: {}",
+            self.builder,
+            self.params.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for MissingParamsError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamsSnapshot {
+    pub builder: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+impl ParamsSnapshot {
+    pub fn new(builder: &'static str, params: Vec<(&'static str, String)>) -> Self {
+        Self { builder, params }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for ParamsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {{ ", self.builder)?;
+
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{name}: {value}")?;
+        }
+
+        write!(f, " }}")
+    }
+}
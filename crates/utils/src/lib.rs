@@ -1,16 +1,28 @@
+extern crate self as utils;
 
 pub mod build;
 pub mod ptr;
+pub mod scope;
 pub mod span;
 
+#[cfg(feature = "serde")]
+mod serialize;
+
 pub use build::*;
 pub use ptr::*;
+pub use scope::*;
 pub use span::*;
 
 pub use util_macros::Paramters;
 pub use util_macros::Share;
+pub use util_macros::ShareMut;
 
 pub use std::sync::Arc as Shared;
+pub use std::sync::Weak as WeakShared;
+
+pub use parking_lot::RwLock;
+
+pub type SharedMut<T> = Shared<RwLock<T>>;
 
 pub trait Share {
     type Internal;
@@ -27,5 +39,35 @@ impl<T> Share for &Shared<T> {
     }
 }
 
+pub trait ShareMut {
+    type Internal;
+
+    fn share_mut(self) -> SharedMut<Self::Internal>;
+}
+
+impl<T> ShareMut for &SharedMut<T> {
+    type Internal = T;
+
+    #[inline]
+    fn share_mut(self) -> SharedMut<Self::Internal> {
+        self.clone()
+    }
+}
+
+pub trait Downgrade {
+    type Internal;
+
+    fn downgrade(&self) -> WeakShared<Self::Internal>;
+}
+
+impl<T> Downgrade for Shared<T> {
+    type Internal = T;
+
+    #[inline]
+    fn downgrade(&self) -> WeakShared<Self::Internal> {
+        Shared::downgrade(self)
+    }
+}
+
 #[cfg(test)]
 pub mod tests;
\ No newline at end of file
@@ -0,0 +1,73 @@
+use std::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+pub struct ScopeGuard<T, F: FnOnce(T)> {
+    value: ManuallyDrop<T>,
+    callback: ManuallyDrop<F>,
+}
+
+impl<T, F: FnOnce(T)> ScopeGuard<T, F> {
+    #[inline]
+    pub fn new(value: T, callback: F) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            callback: ManuallyDrop::new(callback),
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(guard: Self) -> T {
+        let mut guard = ManuallyDrop::new(guard);
+
+        unsafe {
+            ManuallyDrop::drop(&mut guard.callback);
+            ManuallyDrop::take(&mut guard.value)
+        }
+    }
+
+    #[inline]
+    pub fn dismiss(self) {
+        drop(Self::into_inner(self));
+    }
+}
+
+impl<T, F: FnOnce(T)> Deref for ScopeGuard<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, F: FnOnce(T)> DerefMut for ScopeGuard<T, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T, F: FnOnce(T)> Drop for ScopeGuard<T, F> {
+    fn drop(&mut self) {
+        let (value, callback) = unsafe {
+            (
+                ManuallyDrop::take(&mut self.value),
+                ManuallyDrop::take(&mut self.callback),
+            )
+        };
+
+        callback(value);
+    }
+}
+
+#[inline]
+#[must_use = "the callback runs immediately if the guard is not bound to a variable"]
+pub fn guard<T, F: FnOnce(T)>(value: T, callback: F) -> ScopeGuard<T, F> {
+    ScopeGuard::new(value, callback)
+}
+
+#[inline]
+#[must_use = "the callback runs immediately if the guard is not bound to a variable"]
+pub fn defer<F: FnOnce()>(callback: F) -> ScopeGuard<(), impl FnOnce(())> {
+    ScopeGuard::new((), move |()| callback())
+}
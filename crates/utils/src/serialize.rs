@@ -0,0 +1,16 @@
+use serde::{Serialize, Serializer, ser::SerializeMap};
+
+use crate::ParamsSnapshot;
+
+impl Serialize for ParamsSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.params.len() + 1))?;
+        map.serialize_entry("builder", self.builder)?;
+
+        for (name, value) in &self.params {
+            map.serialize_entry(name, value)?;
+        }
+
+        map.end()
+    }
+}
@@ -1,7 +1,8 @@
-use std::ops::{Add, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive, Sub};
+use std::fmt;
+use std::ops::{Add, Mul, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive, Sub};
 
 pub trait SpanPrimitive:
-    Copy + Add<Self, Output = Self> + Sub<Self, Output = Self> + PartialOrd
+    Copy + Add<Self, Output = Self> + Sub<Self, Output = Self> + Mul<Self, Output = Self> + PartialOrd
 {
     const ZERO: Self;
     const ONE: Self;
@@ -30,6 +31,7 @@ impl_span_primitive! {u64}
 impl_span_primitive! {usize}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span<T: SpanPrimitive> {
     pub offset: T,
     pub count: T,
@@ -43,6 +45,271 @@ impl<T: SpanPrimitive> Span<T> {
     pub fn invalid() -> Self {
         Self { offset: T::ZERO, count: T::ZERO }
     }
+
+    #[inline]
+    pub fn end(&self) -> T {
+        self.offset + self.count
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == T::ZERO
+    }
+
+    #[inline]
+    pub fn contains(&self, value: T) -> bool {
+        self.offset <= value && value < self.end()
+    }
+
+    pub fn get(&self, index: T) -> Option<T> {
+        (index < self.count).then(|| self.offset + index)
+    }
+
+    pub fn subspan(self, range: impl ToSpan<T>) -> Self {
+        range.to_span(self)
+    }
+
+    pub fn iter(&self) -> SpanIter<T> {
+        SpanIter { front: self.offset, back: self.end() }
+    }
+
+    #[inline]
+    pub fn overlaps(&self, other: Span<T>) -> bool {
+        self.offset < other.end() && other.offset < self.end()
+    }
+
+    pub fn intersect(&self, other: Span<T>) -> Option<Self> {
+        if self.overlaps(other) {
+            let start = max(self.offset, other.offset);
+            let end = min(self.end(), other.end());
+            Some(Span::new(start, end - start))
+        } else {
+            None
+        }
+    }
+
+    pub fn union(&self, other: Span<T>) -> SpanUnion<T> {
+        if self.offset <= other.end() && other.offset <= self.end() {
+            let start = min(self.offset, other.offset);
+            let end = max(self.end(), other.end());
+            SpanUnion::Merged(Span::new(start, end - start))
+        } else if self.offset < other.offset {
+            SpanUnion::Disjoint(*self, other)
+        } else {
+            SpanUnion::Disjoint(other, *self)
+        }
+    }
+
+    pub fn split_at(&self, mid: T) -> (Self, Self) {
+        assert!(mid <= self.count, "Split point is outside of the span");
+
+        (
+            Span::new(self.offset, mid),
+            Span::new(self.offset + mid, self.count - mid),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StridedSpan<T: SpanPrimitive> {
+    pub offset: T,
+    pub count: T,
+    pub stride: T,
+}
+
+impl<T: SpanPrimitive> StridedSpan<T> {
+    pub fn new(offset: T, count: T, stride: T) -> Self {
+        Self { offset, count, stride }
+    }
+
+    pub fn invalid() -> Self {
+        Self { offset: T::ZERO, count: T::ZERO, stride: T::ONE }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == T::ZERO
+    }
+
+    pub fn get(&self, index: T) -> Option<T> {
+        (index < self.count).then(|| self.offset + index * self.stride)
+    }
+
+    pub fn extent(&self) -> Span<T> {
+        if self.is_empty() {
+            Span::new(self.offset, T::ZERO)
+        } else {
+            Span::new(self.offset, (self.count - T::ONE) * self.stride + T::ONE)
+        }
+    }
+
+    pub fn try_subspan(self, range: impl TryToSpan<T>) -> Result<Self, SpanError<T>> {
+        let elements = range.try_to_span(Span::new(T::ZERO, self.count))?;
+        Ok(Self::new(self.offset + elements.offset * self.stride, elements.count, self.stride))
+    }
+
+    pub fn subspan(self, range: impl TryToSpan<T>) -> Self {
+        self.try_subspan(range).unwrap_or_else(|_| Self::invalid())
+    }
+
+    pub fn iter(&self) -> StridedSpanIter<T> {
+        StridedSpanIter { span: *self, front: T::ZERO, back: self.count }
+    }
+}
+
+impl<T: SpanPrimitive> From<Span<T>> for StridedSpan<T> {
+    fn from(span: Span<T>) -> Self {
+        Self::new(span.offset, span.count, T::ONE)
+    }
+}
+
+impl<T> TryToSpan<T> for StridedSpan<T>
+where
+    T: SpanPrimitive,
+{
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
+        self.extent().try_to_span(span)
+    }
+}
+
+impl<T: SpanPrimitive> IntoIterator for StridedSpan<T> {
+    type Item = T;
+    type IntoIter = StridedSpanIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StridedSpanIter<T: SpanPrimitive> {
+    span: StridedSpan<T>,
+    front: T,
+    back: T,
+}
+
+impl<T: SpanPrimitive> Iterator for StridedSpanIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            let value = self.span.offset + self.front * self.span.stride;
+            self.front = self.front + T::ONE;
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: SpanPrimitive> DoubleEndedIterator for StridedSpanIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back = self.back - T::ONE;
+            Some(self.span.offset + self.back * self.span.stride)
+        } else {
+            None
+        }
+    }
+}
+
+#[inline]
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a { b } else { a }
+}
+
+#[inline]
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a { b } else { a }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanUnion<T: SpanPrimitive> {
+    Merged(Span<T>),
+    Disjoint(Span<T>, Span<T>),
+}
+
+impl<T: SpanPrimitive> IntoIterator for Span<T> {
+    type Item = T;
+    type IntoIter = SpanIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: SpanPrimitive> IntoIterator for &Span<T> {
+    type Item = T;
+    type IntoIter = SpanIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpanIter<T: SpanPrimitive> {
+    front: T,
+    back: T,
+}
+
+impl<T: SpanPrimitive> Iterator for SpanIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            let value = self.front;
+            self.front = self.front + T::ONE;
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: SpanPrimitive> DoubleEndedIterator for SpanIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back = self.back - T::ONE;
+            Some(self.back)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpanError<T: SpanPrimitive> {
+    pub start: T,
+    pub end: T,
+    pub available: T,
+}
+
+impl<T: SpanPrimitive> SpanError<T> {
+    #[inline]
+    pub fn new(start: T, end: T, available: T) -> Self {
+        Self { start, end, available }
+    }
+}
+
+impl<T: SpanPrimitive + fmt::Display> fmt::Display for SpanError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Requested range {}..{} exceeds the available count of {}",
+            self.start, self.end, self.available
+        )
+    }
+}
+
+impl<T: SpanPrimitive + fmt::Debug + fmt::Display> std::error::Error for SpanError<T> {}
+
+pub trait TryToSpan<T>
+where
+    T: SpanPrimitive,
+{
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>>;
 }
 
 pub trait ToSpan<T>
@@ -52,100 +319,132 @@ where
     fn to_span(self, span: Span<T>) -> Span<T>;
 }
 
-impl<T> ToSpan<T> for Span<T> where T: SpanPrimitive {
+impl<T, R> ToSpan<T> for R
+where
+    T: SpanPrimitive,
+    R: TryToSpan<T>,
+{
+    #[inline]
     fn to_span(self, span: Span<T>) -> Span<T> {
+        self.try_to_span(span).unwrap_or_else(|_| Span::invalid())
+    }
+}
+
+impl<T> TryToSpan<T> for Span<T> where T: SpanPrimitive {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self.offset + self.count <= span.count {
-            Span::new(span.offset + self.offset, self.count)
+            Ok(Span::new(span.offset + self.offset, self.count))
         } else {
-            Span::invalid()
+            Err(SpanError::new(self.offset, self.offset + self.count, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for T
+impl<T> TryToSpan<T> for T
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self < span.count {
-            Span::new(span.offset + self, T::ONE)
+            Ok(Span::new(span.offset + self, T::ONE))
         } else {
-            Span::invalid()
+            Err(SpanError::new(self, self + T::ONE, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for Range<T>
+impl<T> TryToSpan<T> for Range<T>
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self.end <= span.count {
-            Span::new(span.offset + self.start, self.end.saturating_sub(self.start))
+            Ok(Span::new(span.offset + self.start, self.end.saturating_sub(self.start)))
         } else {
-            Span::invalid()
+            Err(SpanError::new(self.start, self.end, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for RangeInclusive<T>
+impl<T> TryToSpan<T> for RangeInclusive<T>
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if *self.end() < span.count {
-            Span::new(span.offset + *self.start(), self.end().saturating_sub(*self.start()) + T::ONE)
+            Ok(Span::new(span.offset + *self.start(), self.end().saturating_sub(*self.start()) + T::ONE))
         } else {
-            Span::invalid()
+            Err(SpanError::new(*self.start(), *self.end() + T::ONE, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for RangeTo<T>
+impl<T> TryToSpan<T> for RangeTo<T>
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self.end <= span.count {
-            Span::new(span.offset, self.end)
+            Ok(Span::new(span.offset, self.end))
         } else {
-            Span::invalid()
+            Err(SpanError::new(T::ZERO, self.end, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for RangeToInclusive<T>
+impl<T> TryToSpan<T> for RangeToInclusive<T>
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self.end < span.count {
-            Span::new(span.offset, self.end + T::ONE)
+            Ok(Span::new(span.offset, self.end + T::ONE))
         } else {
-            Span::invalid()
+            Err(SpanError::new(T::ZERO, self.end + T::ONE, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for RangeFrom<T>
+impl<T> TryToSpan<T> for RangeFrom<T>
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         if self.start < span.count {
-            Span::new(span.offset + self.start, span.count - self.start)
+            Ok(Span::new(span.offset + self.start, span.count - self.start))
         } else {
-            Span::invalid()
+            Err(SpanError::new(self.start, span.count, span.count))
         }
     }
 }
 
-impl<T> ToSpan<T> for RangeFull
+impl<T> TryToSpan<T> for RangeFull
 where
     T: SpanPrimitive,
 {
-    fn to_span(self, span: Span<T>) -> Span<T> {
-        span
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
+        Ok(span)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Last<T: SpanPrimitive>(pub T);
+
+#[inline]
+pub fn last<T: SpanPrimitive>(count: T) -> Last<T> {
+    Last(count)
+}
+
+impl<T> TryToSpan<T> for Last<T>
+where
+    T: SpanPrimitive,
+{
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
+        if self.0 <= span.count {
+            Ok(Span::new(span.offset + span.count - self.0, self.0))
+        } else {
+            Err(SpanError::new(T::ZERO, self.0, span.count))
+        }
     }
 }
 
@@ -158,18 +457,20 @@ pub enum AnyRange<T: SpanPrimitive> {
     RangeToInclusive(RangeToInclusive<T>),
     RangeFrom(RangeFrom<T>),
     RangeFull(RangeFull),
+    Last(Last<T>),
 }
 
-impl<T: SpanPrimitive> ToSpan<T> for AnyRange<T> {
-    fn to_span(self, span: Span<T>) -> Span<T> {
+impl<T: SpanPrimitive> TryToSpan<T> for AnyRange<T> {
+    fn try_to_span(self, span: Span<T>) -> Result<Span<T>, SpanError<T>> {
         match self {
-            AnyRange::Value(value) => value.to_span(span),
-            AnyRange::Range(range) => range.to_span(span),
-            AnyRange::RangeInclusive(range_inclusive) => range_inclusive.to_span(span),
-            AnyRange::RangeTo(range_to) => range_to.to_span(span),
-            AnyRange::RangeToInclusive(range_to_inclusive) => range_to_inclusive.to_span(span),
-            AnyRange::RangeFrom(range_from) => range_from.to_span(span),
-            AnyRange::RangeFull(range_full) => range_full.to_span(span),
+            AnyRange::Value(value) => value.try_to_span(span),
+            AnyRange::Range(range) => range.try_to_span(span),
+            AnyRange::RangeInclusive(range_inclusive) => range_inclusive.try_to_span(span),
+            AnyRange::RangeTo(range_to) => range_to.try_to_span(span),
+            AnyRange::RangeToInclusive(range_to_inclusive) => range_to_inclusive.try_to_span(span),
+            AnyRange::RangeFrom(range_from) => range_from.try_to_span(span),
+            AnyRange::RangeFull(range_full) => range_full.try_to_span(span),
+            AnyRange::Last(last) => last.try_to_span(span),
         }
     }
 }
@@ -215,3 +516,9 @@ impl<T: SpanPrimitive> From<RangeFull> for AnyRange<T> {
         Self::RangeFull(value)
     }
 }
+
+impl<T: SpanPrimitive> From<Last<T>> for AnyRange<T> {
+    fn from(value: Last<T>) -> Self {
+        Self::Last(value)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::Build;
 use crate::Buildable;
+use crate::{AnyRange, Span, SpanError, SpanUnion, StridedSpan, ToSpan, TryToSpan, last};
 
 #[derive(crate::Paramters, Default)]
 struct FooBuilder {
@@ -34,3 +35,333 @@ pub fn test_builder() {
     assert_eq!(foo.0, "franz");
     assert_eq!(foo.1, 32);
 }
+
+#[test]
+pub fn test_span_access() {
+    let span = Span::new(4u32, 3);
+
+    assert_eq!(span.end(), 7);
+    assert!(!span.is_empty());
+    assert!(Span::new(4u32, 0).is_empty());
+
+    assert!(span.contains(4));
+    assert!(span.contains(6));
+    assert!(!span.contains(7));
+    assert!(!span.contains(3));
+
+    assert_eq!(span.get(0), Some(4));
+    assert_eq!(span.get(2), Some(6));
+    assert_eq!(span.get(3), None);
+
+    assert_eq!(span.subspan(1..), Span::new(5, 2));
+    assert_eq!(span.subspan(..=1), Span::new(4, 2));
+
+    assert_eq!(span.into_iter().collect::<Vec<_>>(), vec![4, 5, 6]);
+    assert_eq!(span.iter().rev().collect::<Vec<_>>(), vec![6, 5, 4]);
+    assert_eq!(Span::new(4u32, 0).iter().count(), 0);
+}
+
+#[test]
+pub fn test_span_set_operations() {
+    let a = Span::new(0u64, 10);
+    let b = Span::new(5u64, 10);
+    let c = Span::new(10u64, 4);
+    let d = Span::new(20u64, 2);
+
+    assert!(a.overlaps(b));
+    assert!(!a.overlaps(c));
+
+    assert_eq!(a.intersect(b), Some(Span::new(5, 5)));
+    assert_eq!(a.intersect(c), None);
+
+    assert_eq!(a.union(b), SpanUnion::Merged(Span::new(0, 15)));
+    assert_eq!(a.union(c), SpanUnion::Merged(Span::new(0, 14)));
+    assert_eq!(d.union(a), SpanUnion::Disjoint(a, d));
+
+    assert_eq!(b.split_at(3), (Span::new(5, 3), Span::new(8, 7)));
+    assert_eq!(b.split_at(10), (Span::new(5, 10), Span::new(15, 0)));
+}
+
+#[test]
+pub fn test_try_to_span() {
+    let span = Span::new(8u64, 4);
+
+    assert_eq!((1..3).try_to_span(span), Ok(Span::new(9, 2)));
+    assert_eq!(2.try_to_span(span), Ok(Span::new(10, 1)));
+    assert_eq!((..).try_to_span(span), Ok(span));
+
+    assert_eq!((2..6).try_to_span(span), Err(SpanError::new(2, 6, 4)));
+    assert_eq!((..=4).try_to_span(span), Err(SpanError::new(0, 5, 4)));
+    assert_eq!(4.try_to_span(span), Err(SpanError::new(4, 5, 4)));
+    assert_eq!(AnyRange::from(3..=4).try_to_span(span), Err(SpanError::new(3, 5, 4)));
+
+    assert_eq!((2..6).to_span(span), Span::invalid());
+    assert_eq!(
+        SpanError::new(2u64, 6, 4).to_string(),
+        "Requested range 2..6 exceeds the available count of 4"
+    );
+}
+
+#[test]
+pub fn test_last_span() {
+    let span = Span::new(10u32, 6);
+
+    assert_eq!(last(3).to_span(span), Span::new(13, 3));
+    assert_eq!(last(6).to_span(span), span);
+    assert_eq!(last(0).to_span(span), Span::new(16, 0));
+    assert_eq!(last(7).try_to_span(span), Err(SpanError::new(0, 7, 6)));
+
+    assert_eq!(AnyRange::from(last(2)).to_span(span), Span::new(14, 2));
+    assert_eq!(span.subspan(last(2)), Span::new(14, 2));
+}
+
+#[test]
+pub fn test_strided_span() {
+    let strided = StridedSpan::new(2u64, 4, 8);
+
+    assert_eq!(strided.get(0), Some(2));
+    assert_eq!(strided.get(3), Some(26));
+    assert_eq!(strided.get(4), None);
+    assert_eq!(strided.extent(), Span::new(2, 25));
+    assert_eq!(strided.iter().collect::<Vec<_>>(), vec![2, 10, 18, 26]);
+    assert_eq!(strided.iter().next_back(), Some(26));
+
+    assert_eq!(strided.subspan(1..3), StridedSpan::new(10, 2, 8));
+    assert_eq!(strided.subspan(last(1)), StridedSpan::new(26, 1, 8));
+    assert_eq!(strided.try_subspan(2..5), Err(SpanError::new(2, 5, 4)));
+
+    assert_eq!(strided.to_span(Span::new(100, 32)), Span::new(102, 25));
+    assert_eq!(strided.to_span(Span::new(100, 16)), Span::invalid());
+
+    assert_eq!(StridedSpan::from(Span::new(3u64, 2)).iter().collect::<Vec<_>>(), vec![3, 4]);
+}
+
+#[derive(crate::Paramters)]
+struct DefaultedParams {
+    #[param(default = String::from("unnamed"))]
+    name: String,
+    #[param(default = 4)]
+    count: u32,
+    #[param(no_into)]
+    tag: Option<u8>,
+    #[no_param]
+    #[param(default = 1.5)]
+    scale: f32,
+}
+
+#[test]
+pub fn test_param_defaults() {
+    let params = DefaultedParams::default();
+
+    assert_eq!(params.name, "unnamed");
+    assert_eq!(params.count, 4);
+    assert_eq!(params.tag, None);
+    assert_eq!(params.scale, 1.5);
+
+    let params = params.count(7u32).tag(3);
+
+    assert_eq!(params.count, 7);
+    assert_eq!(params.tag, Some(3));
+}
+
+#[derive(crate::Paramters, Default)]
+struct OptionParams {
+    label: Option<String>,
+}
+
+#[test]
+pub fn test_option_params() {
+    let params = OptionParams::default().label("franz");
+    assert_eq!(params.label.as_deref(), Some("franz"));
+
+    let params = params.clear_label();
+    assert_eq!(params.label, None);
+}
+
+#[derive(crate::Paramters, Default)]
+struct RequiredParams {
+    #[required]
+    name: String,
+    #[required]
+    #[flag]
+    bits: u32,
+    optional: u32,
+    #[param(tracker)]
+    tracker: crate::ParamTracker,
+}
+
+#[test]
+pub fn test_required_params() {
+    let params = RequiredParams::default().optional(1u32);
+
+    let error = params.check_required().unwrap_err();
+    assert_eq!(error.params, vec!["name", "bits"]);
+    assert_eq!(error.to_string(), "Missing required parameters in RequiredParams: name, bits");
+
+    let params = params.add_bits(2u32);
+    assert_eq!(params.check_required().unwrap_err().params, vec!["name"]);
+
+    let params = params.name("franz");
+    assert!(params.check_required().is_ok());
+}
+
+#[derive(crate::Paramters, Default)]
+#[param(getters, snapshot)]
+struct IntrospectParams {
+    name: String,
+    #[no_param]
+    count: u32,
+}
+
+#[test]
+pub fn test_param_introspection() {
+    let params = IntrospectParams::default().name("franz");
+
+    assert_eq!(params.get_name(), "franz");
+    assert_eq!(*params.get_count(), 0);
+
+    let snapshot = params.params();
+    assert_eq!(snapshot.get("name"), Some("\"franz\""));
+    assert_eq!(snapshot.get("count"), Some("0"));
+    assert_eq!(snapshot.get("missing"), None);
+    assert_eq!(snapshot.to_string(), "IntrospectParams { name: \"franz\", count: 0 }");
+}
+
+#[derive(crate::Share)]
+struct Node {
+    name: &'static str,
+    this: crate::WeakShared<Node>,
+    parent: Option<crate::WeakShared<Node>>,
+}
+
+#[test]
+pub fn test_weak_shared() {
+    use crate::Downgrade;
+
+    let root = Node::share_cyclic(|this| Node { name: "root", this: this.clone(), parent: None });
+    assert!(root.parent.is_none());
+    assert_eq!(root.this.upgrade().unwrap().name, "root");
+
+    let child = Node {
+        name: "child",
+        this: crate::WeakShared::new(),
+        parent: Some(root.downgrade()),
+    }
+    .share();
+
+    assert_eq!(child.parent.as_ref().unwrap().upgrade().unwrap().name, "root");
+    assert_eq!(crate::Shared::strong_count(&root), 1);
+
+    drop(root);
+    assert!(child.parent.as_ref().unwrap().upgrade().is_none());
+}
+
+#[derive(crate::ShareMut, Debug, PartialEq)]
+struct Pipeline(u32);
+
+#[test]
+pub fn test_shared_mut() {
+    use crate::ShareMut;
+
+    let pipeline = Pipeline(1).share_mut();
+    let handle = (&pipeline).share_mut();
+
+    *pipeline.write() = Pipeline(2);
+
+    assert_eq!(*handle.read(), Pipeline(2));
+    assert_eq!(crate::Shared::strong_count(&handle), 2);
+}
+
+#[derive(Default)]
+struct CheckedBuilder {
+    value: i32,
+}
+
+impl crate::TryBuild for CheckedBuilder {
+    type Target = u32;
+    type Error = String;
+
+    fn try_build(&self) -> Result<Self::Target, Self::Error> {
+        u32::try_from(self.value).map_err(|_| format!("Value {} is negative", self.value))
+    }
+}
+
+#[test]
+pub fn test_try_build() {
+    use crate::TryBuild;
+
+    assert_eq!(CheckedBuilder { value: 3 }.try_build(), Ok(3));
+    assert_eq!(CheckedBuilder { value: 3 }.build(), 3);
+    assert_eq!(
+        CheckedBuilder { value: -1 }.try_build(),
+        Err("Value -1 is negative".to_string())
+    );
+}
+
+#[test]
+#[should_panic(expected = "Value -1 is negative")]
+pub fn test_try_build_panics() {
+    CheckedBuilder { value: -1 }.build();
+}
+
+#[test]
+pub fn test_build_shared() {
+    use crate::TryBuild;
+
+    let foo = Foo::builder().name("franz").build_shared();
+    assert_eq!(foo.0, "franz");
+
+    let value = CheckedBuilder { value: 5 }.try_build_shared().unwrap();
+    assert_eq!(*value, 5);
+    assert!(CheckedBuilder { value: -5 }.try_build_shared().is_err());
+}
+
+#[test]
+pub fn test_defer() {
+    use std::cell::Cell;
+
+    let counter = Cell::new(0);
+
+    {
+        let _guard = crate::defer(|| counter.set(counter.get() + 1));
+        assert_eq!(counter.get(), 0);
+    }
+    assert_eq!(counter.get(), 1);
+
+    let guard = crate::defer(|| counter.set(counter.get() + 1));
+    guard.dismiss();
+    assert_eq!(counter.get(), 1);
+}
+
+#[test]
+pub fn test_scope_guard() {
+    use std::cell::RefCell;
+
+    let released = RefCell::new(vec![]);
+
+    {
+        let mut value = crate::guard(vec![1], |value| released.borrow_mut().extend(value));
+        value.push(2);
+    }
+    assert_eq!(*released.borrow(), vec![1, 2]);
+
+    let value = crate::guard(vec![3], |value| released.borrow_mut().extend(value));
+    assert_eq!(crate::ScopeGuard::into_inner(value), vec![3]);
+    assert_eq!(*released.borrow(), vec![1, 2]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn test_span_deserialize() {
+    use serde::Deserialize;
+    use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+
+    let map = MapDeserializer::<_, Error>::new([("count", 4u64), ("offset", 2u64)].into_iter());
+    assert_eq!(Span::<u64>::deserialize(map), Ok(Span::new(2, 4)));
+
+    let seq = SeqDeserializer::<_, Error>::new([1u32, 5, 2].into_iter());
+    assert_eq!(StridedSpan::<u32>::deserialize(seq), Ok(StridedSpan::new(1, 5, 2)));
+
+    let map = MapDeserializer::<_, Error>::new([("offset", 2u64)].into_iter());
+    assert!(Span::<u64>::deserialize(map).is_err());
+}
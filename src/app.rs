@@ -1,23 +1,119 @@
+use std::cell::OnceCell;
 use std::ffi::{CStr, CString};
 
-use utils::{Build, Buildable};
+use utils::{Build, Buildable, RwLock, Shared, SharedMut, TryBuild};
 use winit::{
     application::ApplicationHandler,
     dpi::LogicalSize,
-    event::WindowEvent,
+    event::{ElementState, KeyEvent, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{Key, ModifiersState, NamedKey},
     window::{Window, WindowId},
 };
 
+use crate::display::{self, DisplaySettings};
+use crate::draw_list::{DrawItem, DrawList, DrawListStats, Geometry, SortMode};
+use crate::registry::ResourceRegistry;
+use crate::renderer::Renderer;
+
 const APP_NAME: &'static CStr = c"Caustix Viewer";
 const ENGINE_NAME: &'static CStr = c"Caustix";
+const PIPELINE_CACHE_PATH: &str = "pipeline_cache.bin";
+const PERMUTATION_WARMUP_PATH: &str = "shader_permutations.txt";
+const VERTEX_SHADER_ID: &str = "shaders/tri_vert";
+const FRAGMENT_SHADER_ID: &str = "shaders/tri_frag";
+
+/// Binds one [`cvk::ShaderAssertBuffer`] per frame in flight at the set and binding `debug.glsl` uses by default.
+struct ShaderAsserts<'a> {
+    buffers: &'a [cvk::ShaderAssertBuffer],
+    sets: Vec<cvk::DescriptorSet<'a>>,
+}
+
+impl<'a> ShaderAsserts<'a> {
+    const SET: u32 = 0;
+    const BINDING: u32 = 0;
+
+    /// Returns `None` if the pipeline was built without `CVK_SHADER_DEBUG` and has no assert binding.
+    fn for_pipeline(
+        pipeline: &cvk::GraphicsPipeline,
+        buffers: &'a OnceCell<Vec<cvk::ShaderAssertBuffer>>,
+        frames_in_flight: usize,
+    ) -> Option<Self> {
+        let layout = pipeline.layout().set_layout(Self::SET)?;
+
+        if layout
+            .binding(Self::BINDING)
+            .is_none_or(|binding| binding.ty != cvk::DescriptorType::STORAGE_BUFFER)
+        {
+            return None;
+        }
+
+        let buffers = buffers.get_or_init(|| {
+            (0..frames_in_flight)
+                .map(|_| cvk::ShaderAssertBuffer::default())
+                .collect()
+        });
+
+        let pool = cvk::DescriptorPool::builder()
+            .max_sets(buffers.len() as u32)
+            .for_layout(layout, buffers.len() as u32)
+            .build_shared();
+
+        let sets = buffers
+            .iter()
+            .map(|buffer| {
+                let mut set = pool.allocate(layout);
+                set.update(&[cvk::DescriptorWrite::buffer(Self::BINDING, buffer.buffer())]);
+                set
+            })
+            .collect();
+
+        Some(Self { buffers, sets })
+    }
+
+    /// Reports what the previous use of `frame` recorded, now that its fence has signalled, and clears the
+    /// buffer for the new recording.
+    fn begin_frame(&self, frame: usize, recording: &mut cvk::Recording<'a>) {
+        let buffer = &self.buffers[frame];
+        let report = buffer.read_failures();
+
+        for failure in &report.failures {
+            println!(
+                "Shader assert {} failed at line {} (values: {:?})",
+                failure.id, failure.line, failure.values
+            );
+        }
+
+        if report.dropped() > 0 {
+            println!("{} more shader assert failures were dropped", report.dropped());
+        }
 
-pub struct App {
+        recording.reset_shader_asserts(buffer);
+    }
+
+    #[inline]
+    fn set(&self, frame: usize) -> &cvk::DescriptorSet<'a> {
+        &self.sets[frame]
+    }
+}
+
+pub struct App<'a> {
     name: CString,
     engine_name: CString,
+    display: DisplaySettings,
+    modifiers: ModifiersState,
+    registry: ResourceRegistry,
+    renderer: Option<Renderer>,
+    pipeline: Option<cvk::GraphicsPipeline>,
+    pipeline_cache: Option<Shared<cvk::PipelineCache>>,
+    permutation_warmup: cvk::PermutationWarmup,
+    fragment_permutations: Option<SharedMut<cvk::ShaderPermutations<'static>>>,
+    assert_buffers: &'a OnceCell<Vec<cvk::ShaderAssertBuffer>>,
+    shader_asserts: Option<ShaderAsserts<'a>>,
+    draw_stats: DrawListStats,
 }
 
-impl App {
+impl<'a> App<'a> {
     fn init(&mut self, event_loop: &ActiveEventLoop) {
         let window_attribs = Window::default_attributes()
             .with_title(self.name.to_string_lossy())
@@ -25,6 +121,13 @@ impl App {
             .with_resizable(false);
 
         let window = event_loop.create_window(window_attribs).unwrap();
+        let window_size = window.inner_size();
+
+        display::print_monitors(event_loop);
+
+        if self.display.start_fullscreen {
+            display::set_fullscreen(&window, &self.display, true);
+        }
 
         let context_info = cvk::ContextInfo::default()
             .app_name(self.name.clone())
@@ -35,38 +138,230 @@ impl App {
 
         cvk::Context::init(context_info);
 
-        let _vertex_shader = cvk::Shader::builder()
-            .stage(cvk::ShaderStage::VERTEX)
-            .glsl_file("assets/shaders/tri_vert.glsl")
-            .build();
+        #[cfg(debug_assertions)]
+        cvk::CommandMarkers::enable(cvk::CommandMarkers::DEFAULT_HANG_TIMEOUT);
+
+        let renderer = Renderer::new(
+            (window_size.width, window_size.height),
+            Renderer::DEFAULT_FRAMES_IN_FLIGHT,
+        );
+
+        #[cfg(feature = "embedded-shaders")]
+        let (vertex_builder, fragment_builder) = (
+            cvk::Shader::builder().spv_buf(cvk::compile_glsl!(vertex, "assets/shaders/tri_vert.glsl")),
+            cvk::Shader::builder().spv_buf(cvk::compile_glsl!(fragment, "assets/shaders/tri_frag.glsl")),
+        );
+
+        #[cfg(not(feature = "embedded-shaders"))]
+        let (vertex_builder, fragment_builder) = (
+            cvk::Shader::builder().glsl_file("assets/shaders/tri_vert.glsl"),
+            cvk::Shader::builder().glsl_file("assets/shaders/tri_frag.glsl"),
+        );
+
+        let fragment_defines: &[&str] = if cfg!(all(debug_assertions, not(feature = "embedded-shaders"))) {
+            &["CVK_SHADER_DEBUG"]
+        } else {
+            &[]
+        };
+
+        let vertex_builder = vertex_builder.stage(cvk::ShaderStage::VERTEX);
+        self.registry
+            .insert_with_loader(VERTEX_SHADER_ID, move || vertex_builder.try_build())
+            .unwrap_or_else(|error| panic!("Failed to create vertex shader: {error}"));
+
+        self.permutation_warmup = cvk::PermutationWarmup::load(PERMUTATION_WARMUP_PATH).unwrap_or_else(|error| {
+            println!("Failed to load shader permutations: {error}");
+            cvk::PermutationWarmup::new()
+        });
+
+        let mut fragment_permutations = cvk::ShaderPermutations::new(fragment_builder.stage(cvk::ShaderStage::FRAGMENT));
+        for error in self.permutation_warmup.warm_up(FRAGMENT_SHADER_ID, &mut fragment_permutations) {
+            println!("Failed to warm up shader permutation: {error}");
+        }
+
+        let fragment_permutations = Shared::new(RwLock::new(fragment_permutations));
+        let permutations = fragment_permutations.clone();
+        self.registry
+            .insert_with_loader(FRAGMENT_SHADER_ID, move || permutations.write().get(fragment_defines.iter().copied()))
+            .unwrap_or_else(|error| panic!("Failed to create fragment shader: {error}"));
 
-        let _fragment_shader = cvk::Shader::builder()
-            .stage(cvk::ShaderStage::FRAGMENT)
-            .glsl_file("assets/shaders/tri_frag.glsl")
-            .build();
+        let pipeline_cache = Shared::new(cvk::PipelineCache::load(PIPELINE_CACHE_PATH));
 
-        let _image = cvk::Image::builder()
-            .extent((1280, 720))
-            .format(cvk::Format::R8G8B8A8_UNORM)
-            .usage(cvk::ImageUsage::TRANSFER_DST)
-            .memory_usage(cvk::MemoryUsage::PreferDevice)
-            .build();
+        let pipeline = self
+            .try_build_pipeline(renderer.format(), &pipeline_cache)
+            .unwrap_or_else(|error| panic!("Failed to create pipeline: {error}"));
 
-        dbg!(_image.extent());
+        self.shader_asserts = ShaderAsserts::for_pipeline(&pipeline, self.assert_buffers, renderer.frames_in_flight());
+        self.renderer = Some(renderer);
+        self.pipeline = Some(pipeline);
+        self.pipeline_cache = Some(pipeline_cache);
+        self.fragment_permutations = Some(fragment_permutations);
+    }
 
-        let shared_image = _image.share();
+    /// Builds the triangle pipeline from the shaders currently in the registry.
+    fn try_build_pipeline(
+        &self,
+        color_format: cvk::Format,
+        pipeline_cache: &Shared<cvk::PipelineCache>,
+    ) -> cvk::Result<cvk::GraphicsPipeline> {
+        let vertex_shader = self
+            .registry
+            .get::<cvk::Shader>(VERTEX_SHADER_ID)
+            .expect("Vertex shader is not registered");
+        let fragment_shader = self
+            .registry
+            .get::<Shared<cvk::Shader>>(FRAGMENT_SHADER_ID)
+            .expect("Fragment shader is not registered");
 
-        let shared_image2 = shared_image.clone();
+        cvk::GraphicsPipeline::builder()
+            .stage(&*vertex_shader.read())
+            .stage(&**fragment_shader.read())
+            .color_format(color_format)
+            .pipeline_cache(pipeline_cache.clone())
+            .try_build()
+    }
+
+    /// Reloads the shaders and rebuilds the pipeline, which keeps the modules it was created from.
+    fn reload_shaders(&mut self) {
+        // Cached permutations were compiled from the old sources.
+        if let Some(fragment_permutations) = &self.fragment_permutations {
+            fragment_permutations.write().clear();
+        }
 
-        dbg!(&shared_image);
-        dbg!(&shared_image2);
+        let errors = self.registry.reload_all();
+        for error in &errors {
+            println!("{error}");
+        }
+        print!("{}", self.registry.report());
+
+        let (Some(renderer), Some(pipeline_cache)) = (&self.renderer, &self.pipeline_cache) else {
+            return;
+        };
+        if !errors.is_empty() {
+            return;
+        }
+
+        match self.try_build_pipeline(renderer.format(), pipeline_cache) {
+            Ok(pipeline) => {
+                self.shader_asserts = ShaderAsserts::for_pipeline(&pipeline, self.assert_buffers, renderer.frames_in_flight());
+                self.pipeline = Some(pipeline);
+            }
+            Err(error) => println!("Failed to rebuild pipeline: {error}"),
+        }
     }
 
-    fn redraw(&mut self) {}
+    fn redraw(&mut self) {
+        let (Some(renderer), Some(pipeline)) = (self.renderer.as_mut(), self.pipeline.as_ref()) else {
+            return;
+        };
+
+        let draw_stats = &mut self.draw_stats;
+        let shader_asserts = self.shader_asserts.as_ref();
+        let frame = renderer.current_frame();
+
+        let result = renderer.render_frame(|recording, target| {
+            if let Some(shader_asserts) = shader_asserts {
+                shader_asserts.begin_frame(frame, recording);
+            }
+
+            let extent = target.image().extent();
 
-    fn handle_event(&mut self, event: WindowEvent, _event_loop: &ActiveEventLoop) {
+            let mut draws = DrawList::new(SortMode::FrontToBack);
+            draws.push(DrawItem {
+                pipeline,
+                material: None,
+                geometry: Geometry::Vertices { count: 3, first: 0 },
+                depth: 0.0,
+                instance: 0,
+            });
+            draws.sort();
+            *draw_stats = *draws.stats();
+
+            recording.marker("main pass begin");
+            recording.begin_rendering(cvk::RenderingAttachments::new().color(target.clear_color([0.0, 0.0, 0.0, 1.0])));
+            recording.set_viewport(
+                cvk::Viewport::default()
+                    .width(extent.width as f32)
+                    .height(extent.height as f32)
+                    .max_depth(1.0),
+            );
+            recording.set_scissor(cvk::Rect2D::default().extent(extent.to_vk()));
+            if let Some(shader_asserts) = shader_asserts {
+                recording.bind_descriptor_sets(
+                    cvk::PipelineBindPoint::GRAPHICS,
+                    pipeline.layout(),
+                    ShaderAsserts::SET,
+                    &[shader_asserts.set(frame)],
+                );
+            }
+            draws.record(recording);
+            recording.end_rendering();
+            recording.marker("main pass end");
+        });
+
+        if let Err(error) = result {
+            println!("Failed to render frame: {error}");
+        }
+    }
+
+    fn handle_event(&mut self, event: WindowEvent, window: &Window, _event_loop: &ActiveEventLoop) {
         // println!("event: {:#?}", event);
         match event {
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::Resized(size) => {
+                if let Some(ref mut renderer) = self.renderer {
+                    renderer.resize((size.width, size.height));
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::Enter),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if self.modifiers.alt_key() => {
+                display::set_fullscreen(window, &self.display, window.fullscreen().is_none())
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::F12),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                for path in ["cvk_objects.dot", "cvk_objects.json"] {
+                    match cvk::Context::export_object_graph(path) {
+                        Ok(()) => println!("Exported live objects to '{path}'"),
+                        Err(error) => println!("{error}"),
+                    }
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::F3),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => print!("{}", self.draw_stats),
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::F5),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => self.reload_shaders(),
             _ => (),
         }
     }
@@ -75,18 +370,34 @@ impl App {
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
 
+        let assert_buffers = OnceCell::new();
+
         let mut app = App {
             name: APP_NAME.into(),
             engine_name: ENGINE_NAME.into(),
+            display: DisplaySettings::from_args(std::env::args()),
+            modifiers: ModifiersState::empty(),
+            registry: ResourceRegistry::new(),
+            renderer: None,
+            pipeline: None,
+            pipeline_cache: None,
+            permutation_warmup: cvk::PermutationWarmup::new(),
+            fragment_permutations: None,
+            assert_buffers: &assert_buffers,
+            shader_asserts: None,
+            draw_stats: DrawListStats::default(),
         };
 
         event_loop.run_app(&mut app).unwrap();
 
+        drop(app);
+        drop(assert_buffers);
+
         cvk::Context::destroy();
     }
 }
 
-impl ApplicationHandler for App {
+impl ApplicationHandler for App<'_> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         self.init(event_loop);
     }
@@ -95,16 +406,27 @@ impl ApplicationHandler for App {
         match event {
             WindowEvent::CloseRequested => {
                 println!("The close button was pressed; stopping");
+                if let Some(pipeline_cache) = &self.pipeline_cache
+                    && let Err(error) = pipeline_cache.try_save(PIPELINE_CACHE_PATH)
+                {
+                    println!("Failed to save pipeline cache: {error}");
+                }
+                if let Some(fragment_permutations) = &self.fragment_permutations {
+                    self.permutation_warmup.record(FRAGMENT_SHADER_ID, &fragment_permutations.read());
+                    if let Err(error) = self.permutation_warmup.save(PERMUTATION_WARMUP_PATH) {
+                        println!("Failed to save shader permutations: {error}");
+                    }
+                }
                 event_loop.exit();
             }
             other => {
-                if let Some(window) = cvk::Context::get().window() {
+                if let Some(window) = cvk::Context::get().window_as::<Window>() {
                     match other {
                         WindowEvent::RedrawRequested => {
                             self.redraw();
                             window.request_redraw();
                         }
-                        event => self.handle_event(event, event_loop),
+                        event => self.handle_event(event, window, event_loop),
                     }
                 }
             }
@@ -0,0 +1,208 @@
+use std::{
+    ffi::CString,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use utils::{Build, Buildable};
+
+const UPLOAD_SIZE: u64 = 64 * 1024 * 1024;
+const COPY_SIZE: u64 = 256 * 1024 * 1024;
+const ITERATIONS: u32 = 8;
+const DRAW_CALLS: u32 = 10_000;
+
+struct BenchResult {
+    name: &'static str,
+    iterations: u32,
+    total: Duration,
+    metric: Option<(&'static str, f64)>,
+    skipped: Option<&'static str>,
+}
+
+impl BenchResult {
+    fn measured(name: &'static str, iterations: u32, total: Duration) -> Self {
+        Self {
+            name,
+            iterations,
+            total,
+            metric: None,
+            skipped: None,
+        }
+    }
+
+    fn skipped(name: &'static str, reason: &'static str) -> Self {
+        Self {
+            name,
+            iterations: 0,
+            total: Duration::ZERO,
+            metric: None,
+            skipped: Some(reason),
+        }
+    }
+
+    fn with_metric(mut self, unit: &'static str, value: f64) -> Self {
+        self.metric = Some((unit, value));
+        self
+    }
+
+    fn write_json(&self, out: &mut String) {
+        write!(out, "{{\"name\":\"{}\",\"iterations\":{}", self.name, self.iterations).unwrap();
+        write!(out, ",\"total_ms\":{:.3}", self.total.as_secs_f64() * 1000.0).unwrap();
+
+        if self.iterations > 0 {
+            let average = self.total.as_secs_f64() * 1000.0 / self.iterations as f64;
+            write!(out, ",\"average_ms\":{average:.3}").unwrap();
+        }
+
+        if let Some((unit, value)) = self.metric {
+            write!(out, ",\"{unit}\":{value:.3}").unwrap();
+        }
+
+        if let Some(reason) = self.skipped {
+            write!(out, ",\"skipped\":\"{reason}\"").unwrap();
+        }
+
+        out.push('}');
+    }
+}
+
+fn bytes_per_second(bytes: u64, iterations: u32, total: Duration) -> f64 {
+    (bytes * iterations as u64) as f64 / total.as_secs_f64() / (1024.0 * 1024.0)
+}
+
+fn bench_upload() -> BenchResult {
+    let data = vec![0xABu8; UPLOAD_SIZE as usize];
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _buffer = cvk::Buffer::<u8>::builder()
+            .usage(cvk::BufferUsage::TRANSFER_DST | cvk::BufferUsage::STORAGE_BUFFER)
+            .memory_usage(cvk::MemoryUsage::PreferDevice)
+            .data(&data)
+            .build();
+    }
+    let total = start.elapsed();
+
+    BenchResult::measured("upload_bandwidth", ITERATIONS, total)
+        .with_metric("mib_per_s", bytes_per_second(UPLOAD_SIZE, ITERATIONS, total))
+}
+
+fn bench_copy() -> BenchResult {
+    let src = cvk::Buffer::<u8>::builder()
+        .count(COPY_SIZE)
+        .usage(cvk::BufferUsage::TRANSFER_SRC)
+        .memory_usage(cvk::MemoryUsage::PreferDevice)
+        .build();
+
+    let dst = cvk::Buffer::<u8>::builder()
+        .count(COPY_SIZE)
+        .usage(cvk::BufferUsage::TRANSFER_DST)
+        .memory_usage(cvk::MemoryUsage::PreferDevice)
+        .build();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        cvk::CommandBuffer::run_single_use(|recording| recording.copy_buffer(&src, &dst));
+    }
+    let total = start.elapsed();
+
+    BenchResult::measured("copy_throughput", ITERATIONS, total)
+        .with_metric("mib_per_s", bytes_per_second(COPY_SIZE, ITERATIONS, total))
+}
+
+fn bench_shader_compile() -> BenchResult {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _shader = cvk::Shader::builder()
+            .stage(cvk::ShaderStage::VERTEX)
+            .glsl_file("assets/shaders/tri_vert.glsl")
+            .build();
+    }
+    let total = start.elapsed();
+
+    BenchResult::measured("shader_compile", ITERATIONS, total)
+}
+
+fn bench_draw_calls() -> BenchResult {
+    if !cvk::Context::get().has_dynamic_rendering() {
+        return BenchResult::skipped("draw_call_overhead", "device does not support dynamic rendering");
+    }
+
+    let target = cvk::Image::builder()
+        .extent((256, 256))
+        .format(cvk::Format::R8G8B8A8_UNORM)
+        .usage(cvk::ImageUsage::COLOR_ATTACHMENT)
+        .memory_usage(cvk::MemoryUsage::PreferDevice)
+        .build_shared();
+    let view = target.view();
+
+    let vertex_shader = cvk::Shader::builder()
+        .stage(cvk::ShaderStage::VERTEX)
+        .glsl_file("assets/shaders/tri_vert.glsl")
+        .build();
+    let fragment_shader = cvk::Shader::builder()
+        .stage(cvk::ShaderStage::FRAGMENT)
+        .glsl_file("assets/shaders/tri_frag.glsl")
+        .build();
+
+    let pipeline = cvk::GraphicsPipeline::builder()
+        .stage(&vertex_shader)
+        .stage(&fragment_shader)
+        .color_format(target.format())
+        .build();
+
+    let extent = target.extent();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        cvk::CommandBuffer::run_single_use(|recording| {
+            recording.begin_rendering(cvk::RenderingAttachments::new().color(cvk::Attachment::new(&view)));
+            recording.set_viewport(
+                cvk::Viewport::default()
+                    .width(extent.width as f32)
+                    .height(extent.height as f32)
+                    .max_depth(1.0),
+            );
+            recording.set_scissor(cvk::Rect2D::default().extent(extent.to_vk()));
+            recording.bind_pipeline(&pipeline);
+
+            for _ in 0..DRAW_CALLS {
+                recording.draw(3, 1, 0, 0);
+            }
+
+            recording.end_rendering();
+        });
+    }
+    let total = start.elapsed();
+
+    BenchResult::measured("draw_call_overhead", ITERATIONS, total)
+        .with_metric("draws_per_s", (DRAW_CALLS * ITERATIONS) as f64 / total.as_secs_f64())
+}
+
+pub fn run() {
+    let context_info = cvk::ContextInfo::default()
+        .app_name(CString::from(c"Caustix Viewer Bench"))
+        .version(cvk::ApiVersion::V1_2);
+
+    cvk::Context::init(context_info);
+
+    let results = [
+        bench_upload(),
+        bench_copy(),
+        bench_shader_compile(),
+        bench_draw_calls(),
+    ];
+
+    let mut report = String::from("{\"results\":[");
+    for (i, result) in results.iter().enumerate() {
+        if i > 0 {
+            report.push(',');
+        }
+        result.write_json(&mut report);
+    }
+    report.push_str("]}");
+
+    println!("{report}");
+
+    cvk::Context::destroy();
+}
@@ -0,0 +1,109 @@
+use winit::{
+    event_loop::ActiveEventLoop,
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::{Fullscreen, Window},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FullscreenMode {
+    #[default]
+    Borderless,
+    Exclusive,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DisplaySettings {
+    pub fullscreen_mode: FullscreenMode,
+    pub start_fullscreen: bool,
+    pub refresh_rate_hz: Option<u32>,
+}
+
+impl DisplaySettings {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut settings = Self::default();
+
+        for arg in args {
+            if let Some(mode) = arg.strip_prefix("--fullscreen=") {
+                settings.start_fullscreen = true;
+                match mode {
+                    "borderless" => settings.fullscreen_mode = FullscreenMode::Borderless,
+                    "exclusive" => settings.fullscreen_mode = FullscreenMode::Exclusive,
+                    _ => println!("Unknown fullscreen mode '{mode}', using borderless"),
+                }
+            } else if arg == "--fullscreen" {
+                settings.start_fullscreen = true;
+            } else if let Some(rate) = arg.strip_prefix("--refresh-rate=") {
+                match rate.parse::<u32>() {
+                    Ok(rate) => settings.refresh_rate_hz = Some(rate),
+                    Err(_) => println!("Invalid refresh rate '{rate}'"),
+                }
+            }
+        }
+
+        settings
+    }
+}
+
+pub fn print_monitors(event_loop: &ActiveEventLoop) {
+    let primary = event_loop.primary_monitor();
+
+    for (i, monitor) in event_loop.available_monitors().enumerate() {
+        let size = monitor.size();
+
+        println!(
+            "Display {i}: {}{} {}x{} @ {:.2} Hz",
+            monitor.name().unwrap_or_else(|| String::from("<unnamed>")),
+            if Some(&monitor) == primary.as_ref() { " (primary)" } else { "" },
+            size.width,
+            size.height,
+            monitor.refresh_rate_millihertz().unwrap_or(0) as f64 / 1000.0,
+        );
+    }
+}
+
+pub fn select_video_mode(monitor: &MonitorHandle, refresh_rate_hz: Option<u32>) -> Option<VideoModeHandle> {
+    let size = monitor.size();
+
+    let modes = monitor
+        .video_modes()
+        .filter(|mode| mode.size() == size)
+        .collect::<Vec<_>>();
+
+    let modes = if modes.is_empty() { monitor.video_modes().collect() } else { modes };
+
+    match refresh_rate_hz {
+        Some(rate) => modes
+            .into_iter()
+            .min_by_key(|mode| mode.refresh_rate_millihertz().abs_diff(rate.saturating_mul(1000))),
+        None => modes.into_iter().max_by_key(|mode| (mode.refresh_rate_millihertz(), mode.bit_depth())),
+    }
+}
+
+pub fn set_fullscreen(window: &Window, settings: &DisplaySettings, fullscreen: bool) {
+    if !fullscreen {
+        window.set_fullscreen(None);
+        return;
+    }
+
+    let monitor = window.current_monitor().or_else(|| window.primary_monitor());
+
+    let target = match settings.fullscreen_mode {
+        FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        FullscreenMode::Exclusive => monitor
+            .and_then(|monitor| select_video_mode(&monitor, settings.refresh_rate_hz))
+            .map(Fullscreen::Exclusive),
+    };
+
+    match target {
+        Some(Fullscreen::Exclusive(ref mode)) => println!(
+            "Entering exclusive fullscreen {}x{} @ {:.2} Hz",
+            mode.size().width,
+            mode.size().height,
+            mode.refresh_rate_millihertz() as f64 / 1000.0
+        ),
+        Some(Fullscreen::Borderless(_)) => println!("Entering borderless fullscreen"),
+        None => println!("No video mode available, staying in windowed mode"),
+    }
+
+    window.set_fullscreen(target);
+}
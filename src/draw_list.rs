@@ -0,0 +1,243 @@
+use std::{cmp::Ordering, fmt};
+
+use cvk::{DescriptorSet, GraphicsPipeline, PipelineBindPoint, Recording};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Geometry {
+    Vertices { count: u32, first: u32 },
+    Indexed { count: u32, first_index: u32, vertex_offset: i32 },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortMode {
+    #[default]
+    FrontToBack,
+    BackToFront,
+    Submission,
+}
+
+#[derive(Clone, Copy)]
+pub struct DrawItem<'a> {
+    pub pipeline: &'a GraphicsPipeline,
+    pub material: Option<&'a DescriptorSet<'a>>,
+    pub geometry: Geometry,
+    pub depth: f32,
+    pub instance: u32,
+}
+
+impl DrawItem<'_> {
+    #[inline]
+    fn pipeline_key(&self) -> u64 {
+        self.pipeline.raw_handle()
+    }
+
+    #[inline]
+    fn material_key(&self) -> u64 {
+        self.material.map_or(0, |material| material.raw_handle())
+    }
+
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.pipeline_key() == other.pipeline_key()
+            && self.material_key() == other.material_key()
+            && self.geometry == other.geometry
+    }
+
+    fn state_cmp(&self, other: &Self) -> Ordering {
+        self.pipeline_key()
+            .cmp(&other.pipeline_key())
+            .then(self.material_key().cmp(&other.material_key()))
+            .then(self.geometry.cmp(&other.geometry))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DrawBatch {
+    item: usize,
+    first_instance: u32,
+    instance_count: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawListStats {
+    pub draws: usize,
+    pub batches: usize,
+    pub pipeline_changes_before: usize,
+    pub pipeline_changes_after: usize,
+    pub material_changes_before: usize,
+    pub material_changes_after: usize,
+}
+
+impl fmt::Display for DrawListStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Draw list: {} draws in {} batches", self.draws, self.batches)?;
+        writeln!(
+            f,
+            "  pipeline changes: {} -> {}",
+            self.pipeline_changes_before, self.pipeline_changes_after
+        )?;
+        writeln!(
+            f,
+            "  material changes: {} -> {}",
+            self.material_changes_before, self.material_changes_after
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct DrawList<'a> {
+    mode: SortMode,
+    items: Vec<DrawItem<'a>>,
+    batches: Vec<DrawBatch>,
+    instances: Vec<u32>,
+    stats: DrawListStats,
+    sorted: bool,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new(mode: SortMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn mode(&self) -> SortMode {
+        self.mode
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, item: DrawItem<'a>) {
+        self.items.push(item);
+        self.sorted = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.batches.clear();
+        self.instances.clear();
+        self.stats = DrawListStats::default();
+        self.sorted = false;
+    }
+
+    pub fn sort(&mut self) {
+        let (pipeline_changes_before, material_changes_before) = Self::state_changes(&self.items);
+
+        match self.mode {
+            SortMode::FrontToBack => self
+                .items
+                .sort_by(|a, b| a.state_cmp(b).then(a.depth.total_cmp(&b.depth))),
+            SortMode::BackToFront => self.items.sort_by(|a, b| b.depth.total_cmp(&a.depth)),
+            SortMode::Submission => (),
+        }
+
+        self.batches.clear();
+        self.instances.clear();
+
+        for (index, item) in self.items.iter().enumerate() {
+            match self.batches.last_mut() {
+                Some(batch) if self.items[batch.item].is_compatible(item) => batch.instance_count += 1,
+                _ => self.batches.push(DrawBatch {
+                    item: index,
+                    first_instance: index as u32,
+                    instance_count: 1,
+                }),
+            }
+            self.instances.push(item.instance);
+        }
+
+        let batch_items = self.batches.iter().map(|batch| self.items[batch.item]).collect::<Vec<_>>();
+        let (pipeline_changes_after, material_changes_after) = Self::state_changes(&batch_items);
+
+        self.stats = DrawListStats {
+            draws: self.items.len(),
+            batches: self.batches.len(),
+            pipeline_changes_before,
+            pipeline_changes_after,
+            material_changes_before,
+            material_changes_after,
+        };
+        self.sorted = true;
+    }
+
+    pub fn instances(&self) -> &[u32] {
+        assert!(self.sorted, "Draw list needs to be sorted before reading its instances");
+        &self.instances
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &DrawListStats {
+        &self.stats
+    }
+
+    pub fn record(&self, recording: &mut Recording<'a>) {
+        assert!(self.sorted, "Draw list needs to be sorted before recording");
+
+        let mut pipeline = None;
+        let mut material = None;
+
+        for batch in &self.batches {
+            let item = self.items[batch.item];
+
+            if pipeline != Some(item.pipeline_key()) {
+                recording.bind_pipeline(item.pipeline);
+                pipeline = Some(item.pipeline_key());
+                material = None;
+            }
+
+            if let Some(set) = item.material
+                && material != Some(item.material_key())
+            {
+                recording.bind_descriptor_sets(PipelineBindPoint::GRAPHICS, item.pipeline.layout(), 0, &[set]);
+                material = Some(item.material_key());
+            }
+
+            match item.geometry {
+                Geometry::Vertices { count, first } => {
+                    recording.draw(count, batch.instance_count, first, batch.first_instance)
+                }
+                Geometry::Indexed {
+                    count,
+                    first_index,
+                    vertex_offset,
+                } => recording.draw_indexed(
+                    count,
+                    batch.instance_count,
+                    first_index,
+                    vertex_offset,
+                    batch.first_instance,
+                ),
+            }
+        }
+    }
+
+    fn state_changes(items: &[DrawItem<'a>]) -> (usize, usize) {
+        let mut pipeline = None;
+        let mut material = None;
+        let (mut pipeline_changes, mut material_changes) = (0, 0);
+
+        for item in items {
+            if pipeline != Some(item.pipeline_key()) {
+                pipeline = Some(item.pipeline_key());
+                material = None;
+                pipeline_changes += 1;
+            }
+
+            if item.material.is_some() && material != Some(item.material_key()) {
+                material = Some(item.material_key());
+                material_changes += 1;
+            }
+        }
+
+        (pipeline_changes, material_changes)
+    }
+}
@@ -1,7 +1,16 @@
 pub mod app;
+pub mod bench;
+pub mod display;
+pub mod draw_list;
+pub mod registry;
+pub mod renderer;
 
 pub use app::*;
 
 fn main() {
-    App::run();
+    if std::env::args().any(|arg| arg == "--bench") {
+        bench::run();
+    } else {
+        App::run();
+    }
 }
@@ -0,0 +1,171 @@
+use std::{any::Any, collections::BTreeMap, fmt};
+
+use utils::{RwLock, Shared, SharedMut};
+
+type Reloader = Box<dyn Fn(&dyn Any) -> Result<(), String>>;
+
+struct RegistryEntry {
+    resource: Box<dyn Any>,
+    type_name: &'static str,
+    ref_count: fn(&dyn Any) -> usize,
+    reloader: Option<Reloader>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistryError {
+    NotFound(String),
+    NotReloadable(String),
+    Reload(String, String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound(id) => write!(f, "No resource registered as '{id}'"),
+            RegistryError::NotReloadable(id) => write!(f, "Resource '{id}' was registered without a loader"),
+            RegistryError::Reload(id, error) => write!(f, "Failed to reload resource '{id}': {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryReportEntry {
+    pub id: String,
+    pub type_name: &'static str,
+    pub ref_count: usize,
+    pub reloadable: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryReport {
+    pub entries: Vec<RegistryReportEntry>,
+}
+
+impl fmt::Display for RegistryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Registered resources: {}", self.entries.len())?;
+
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "  '{}' ({}): {} external references{}",
+                entry.id,
+                entry.type_name,
+                entry.ref_count,
+                if entry.reloadable { ", reloadable" } else { "" }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ResourceRegistry {
+    entries: BTreeMap<String, RegistryEntry>,
+}
+
+impl ResourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<T: 'static>(&mut self, id: impl Into<String>, resource: T) -> SharedMut<T> {
+        let resource = Shared::new(RwLock::new(resource));
+
+        self.entries.insert(id.into(), Self::entry(resource.clone(), None));
+        resource
+    }
+
+    pub fn insert_with_loader<T, E>(
+        &mut self,
+        id: impl Into<String>,
+        loader: impl Fn() -> Result<T, E> + 'static,
+    ) -> Result<SharedMut<T>, E>
+    where
+        T: 'static,
+        E: fmt::Display,
+    {
+        let resource = Shared::new(RwLock::new(loader()?));
+
+        let reloader: Reloader = Box::new(move |resource| {
+            let resource = resource
+                .downcast_ref::<SharedMut<T>>()
+                .expect("Registry entry changed its type");
+
+            *resource.write() = loader().map_err(|error| error.to_string())?;
+            Ok(())
+        });
+
+        self.entries.insert(id.into(), Self::entry(resource.clone(), Some(reloader)));
+        Ok(resource)
+    }
+
+    fn entry<T: 'static>(resource: SharedMut<T>, reloader: Option<Reloader>) -> RegistryEntry {
+        RegistryEntry {
+            resource: Box::new(resource),
+            type_name: std::any::type_name::<T>(),
+            ref_count: |resource| {
+                resource
+                    .downcast_ref::<SharedMut<T>>()
+                    .map_or(0, |resource| Shared::strong_count(resource) - 1)
+            },
+            reloader,
+        }
+    }
+
+    pub fn get<T: 'static>(&self, id: &str) -> Option<SharedMut<T>> {
+        self.entries.get(id)?.resource.downcast_ref::<SharedMut<T>>().cloned()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.entries.remove(id).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn reload(&self, id: &str) -> Result<(), RegistryError> {
+        let entry = self
+            .entries
+            .get(id)
+            .ok_or_else(|| RegistryError::NotFound(id.to_string()))?;
+
+        let reloader = entry
+            .reloader
+            .as_ref()
+            .ok_or_else(|| RegistryError::NotReloadable(id.to_string()))?;
+
+        reloader(entry.resource.as_ref()).map_err(|error| RegistryError::Reload(id.to_string(), error))
+    }
+
+    pub fn reload_all(&self) -> Vec<RegistryError> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.reloader.is_some())
+            .filter_map(|(id, _)| self.reload(id).err())
+            .collect()
+    }
+
+    pub fn report(&self) -> RegistryReport {
+        RegistryReport {
+            entries: self
+                .entries
+                .iter()
+                .map(|(id, entry)| RegistryReportEntry {
+                    id: id.clone(),
+                    type_name: entry.type_name,
+                    ref_count: (entry.ref_count)(entry.resource.as_ref()),
+                    reloadable: entry.reloader.is_some(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use cvk::{
+    Access, Attachment, BarrierBuilder, CommandBuffer, CommandBufferUses, Extent2D, Format, ImageLayout,
+    PipelineStage, Recording, Semaphore, SubmitInfoBuilder, SubmittedRecording, Swapchain,
+};
+use utils::{Buildable, TryBuild};
+
+struct Frame {
+    command_buffer: Option<CommandBuffer>,
+    in_flight: Option<SubmittedRecording>,
+    image_available: Semaphore,
+    render_finished: Semaphore,
+}
+
+impl Frame {
+    fn try_new() -> cvk::Result<Self> {
+        Ok(Self {
+            command_buffer: Some(CommandBuffer::try_new(CommandBufferUses::Multi)?),
+            in_flight: None,
+            image_available: Semaphore::try_new()?,
+            render_finished: Semaphore::try_new()?,
+        })
+    }
+}
+
+pub struct Renderer {
+    frames: Vec<Frame>,
+    swapchain: Swapchain,
+    current_frame: usize,
+    extent: Extent2D,
+    needs_recreate: bool,
+}
+
+impl Renderer {
+    pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+    pub fn new(extent: impl Into<Extent2D>, frames_in_flight: usize) -> Self {
+        Self::try_new(extent, frames_in_flight).unwrap_or_else(|error| panic!("Failed to create renderer: {error}"))
+    }
+
+    pub fn try_new(extent: impl Into<Extent2D>, frames_in_flight: usize) -> cvk::Result<Self> {
+        let extent = extent.into();
+
+        let swapchain = Swapchain::builder().extent(extent).try_build()?;
+
+        let frames = (0..frames_in_flight.max(1))
+            .map(|_| Frame::try_new())
+            .collect::<cvk::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            frames,
+            swapchain,
+            current_frame: 0,
+            extent,
+            needs_recreate: false,
+        })
+    }
+
+    #[inline]
+    pub fn swapchain(&self) -> &Swapchain {
+        &self.swapchain
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.swapchain.format()
+    }
+
+    #[inline]
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The frame the next [`render_frame`](Self::render_frame) records into.
+    #[inline]
+    pub fn current_frame(&self) -> usize {
+        self.current_frame
+    }
+
+    pub fn resize(&mut self, extent: impl Into<Extent2D>) {
+        self.extent = extent.into();
+        self.needs_recreate = true;
+    }
+
+    /// Records and submits the next frame without waiting for it. The recording tracks what the callback
+    /// uses, so those resources wait for the frame before they are destroyed.
+    pub fn render_frame<'a>(&'a mut self, record: impl FnOnce(&mut Recording<'a>, Attachment<'a>)) -> cvk::Result<()> {
+        let Renderer {
+            frames,
+            swapchain,
+            current_frame,
+            extent,
+            needs_recreate,
+        } = self;
+
+        if *needs_recreate {
+            if extent.width == 0 || extent.height == 0 {
+                return Ok(());
+            }
+
+            swapchain.try_recreate(*extent)?;
+            *needs_recreate = false;
+        }
+
+        let swapchain: &'a Swapchain = swapchain;
+        let frame = &mut frames[*current_frame];
+
+        // The fence of the frame's previous submission guards its command buffer and semaphores.
+        if let Some(in_flight) = frame.in_flight.take() {
+            frame.command_buffer = Some(in_flight.try_wait()?);
+        }
+
+        let command_buffer = match frame.command_buffer.take() {
+            Some(command_buffer) => command_buffer,
+            None => CommandBuffer::try_new(CommandBufferUses::Multi)?,
+        };
+        let mut recording = command_buffer.try_start_recording()?;
+
+        let index = match swapchain.try_acquire_next_image(&frame.image_available) {
+            Ok((index, suboptimal)) => {
+                *needs_recreate |= suboptimal;
+                index
+            }
+            Err(error) if error.is_out_of_date() => {
+                *needs_recreate = true;
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+
+        recording.marker("frame begin");
+        record(&mut recording, swapchain.attachment(index));
+        recording.marker("frame recorded");
+
+        recording.pipeline_barrier(BarrierBuilder::new().image(
+            swapchain.image(index),
+            Access::COLOR_ATTACHMENT_WRITE,
+            Access::NONE,
+            ImageLayout::PRESENT_SRC_KHR,
+        ));
+        recording.marker("present transition");
+
+        let submitted = recording.try_submit_with(
+            SubmitInfoBuilder::new()
+                .wait(&frame.image_available, PipelineStage::COLOR_ATTACHMENT_OUTPUT)
+                .signal(&frame.render_finished),
+        )?;
+
+        let presented = swapchain.try_present(index, &frame.render_finished);
+        frame.in_flight = Some(submitted);
+
+        *current_frame = (*current_frame + 1) % frames.len();
+
+        match presented {
+            Ok(suboptimal) => *needs_recreate |= suboptimal,
+            Err(error) if error.is_out_of_date() => *needs_recreate = true,
+            Err(error) => return Err(error),
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        let _ = cvk::Context::try_wait_idle();
+    }
+}